@@ -0,0 +1,489 @@
+use crate::utils::{
+    be_bytes_to_decimal, decode_hex, encode_hex, format_label_and_value, keccak256,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{fmt, fs};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamType {
+    Address,
+    Uint(usize),
+    Int(usize),
+    Bool,
+    FixedBytes(usize),
+    /// External function pointer: an address and a selector, encoded like `bytes24`.
+    Function,
+    Bytes,
+    String,
+    Array(Box<ParamType>),
+    FixedArray(Box<ParamType>, usize),
+    Tuple(Vec<ParamType>),
+}
+
+impl ParamType {
+    pub fn parse(kind: &str) -> Result<ParamType, String> {
+        let kind = kind.trim();
+
+        if kind.ends_with(']') {
+            let open = kind.rfind('[').ok_or(format!("Invalid type: {}", kind))?;
+            let inner = ParamType::parse(&kind[..open])?;
+            let size = &kind[open + 1..kind.len() - 1];
+            if size.is_empty() {
+                return Ok(ParamType::Array(Box::new(inner)));
+            }
+            let size = size
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid array size: {}", kind))?;
+            return Ok(ParamType::FixedArray(Box::new(inner), size));
+        }
+
+        if kind.starts_with('(') && kind.ends_with(')') {
+            let members = split_top_level(&kind[1..kind.len() - 1])
+                .iter()
+                .map(|member| ParamType::parse(member))
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(ParamType::Tuple(members));
+        }
+
+        let param = match kind {
+            "address" => ParamType::Address,
+            "bool" => ParamType::Bool,
+            "string" => ParamType::String,
+            "bytes" => ParamType::Bytes,
+            "uint" => ParamType::Uint(256),
+            "int" => ParamType::Int(256),
+            "function" => ParamType::Function,
+            _ => {
+                let size = |prefix: &str| {
+                    kind.strip_prefix(prefix)
+                        .and_then(|size| size.parse::<usize>().ok())
+                };
+                match (size("bytes"), size("uint"), size("int")) {
+                    (Some(size), _, _) if (1..=32).contains(&size) => ParamType::FixedBytes(size),
+                    (_, Some(bits), _) if bits % 8 == 0 && (8..=256).contains(&bits) => {
+                        ParamType::Uint(bits)
+                    }
+                    (_, _, Some(bits)) if bits % 8 == 0 && (8..=256).contains(&bits) => {
+                        ParamType::Int(bits)
+                    }
+                    _ => return Err(format!("Unsupported type: {}", kind)),
+                }
+            }
+        };
+
+        Ok(param)
+    }
+
+    pub fn canonical(&self) -> String {
+        match self {
+            ParamType::Address => "address".to_string(),
+            ParamType::Uint(bits) => format!("uint{}", bits),
+            ParamType::Int(bits) => format!("int{}", bits),
+            ParamType::Bool => "bool".to_string(),
+            ParamType::FixedBytes(size) => format!("bytes{}", size),
+            ParamType::Function => "function".to_string(),
+            ParamType::Bytes => "bytes".to_string(),
+            ParamType::String => "string".to_string(),
+            ParamType::Array(inner) => format!("{}[]", inner.canonical()),
+            ParamType::FixedArray(inner, size) => format!("{}[{}]", inner.canonical(), size),
+            ParamType::Tuple(members) => format!(
+                "({})",
+                members
+                    .iter()
+                    .map(|member| member.canonical())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+        }
+    }
+
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            ParamType::Bytes | ParamType::String | ParamType::Array(_) => true,
+            ParamType::FixedArray(inner, _) => inner.is_dynamic(),
+            ParamType::Tuple(members) => members.iter().any(|member| member.is_dynamic()),
+            _ => false,
+        }
+    }
+
+    fn head_size(&self) -> usize {
+        if self.is_dynamic() {
+            return 32;
+        }
+        match self {
+            ParamType::FixedArray(inner, size) => inner.head_size().saturating_mul(*size),
+            ParamType::Tuple(members) => members.iter().map(|member| member.head_size()).sum(),
+            _ => 32,
+        }
+    }
+}
+
+/// Splits a comma separated list of types while respecting nested tuples.
+fn split_top_level(list: &str) -> Vec<String> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut current = String::new();
+
+    for ch in list.chars() {
+        match ch {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(ch);
+    }
+
+    if !current.trim().is_empty() {
+        parts.push(current.trim().to_string());
+    }
+
+    parts
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Address(String),
+    Uint(String),
+    Int(String),
+    Bool(bool),
+    FixedBytes(String),
+    Bytes(String),
+    String(String),
+    Array(Vec<Token>),
+    Tuple(Vec<Token>),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |tokens: &Vec<Token>| {
+            tokens
+                .iter()
+                .map(|token| token.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        match self {
+            Token::Address(val)
+            | Token::Uint(val)
+            | Token::Int(val)
+            | Token::FixedBytes(val)
+            | Token::Bytes(val) => write!(f, "{}", val),
+            Token::Bool(val) => write!(f, "{}", val),
+            Token::String(val) => write!(f, "{:?}", val),
+            Token::Array(tokens) => write!(f, "[{}]", join(tokens)),
+            Token::Tuple(tokens) => write!(f, "({})", join(tokens)),
+        }
+    }
+}
+
+fn read_word(data: &[u8], pos: usize) -> Result<&[u8], String> {
+    data.get(pos..pos + 32)
+        .ok_or(format!("Data too short to read word at {}", pos))
+}
+
+fn read_usize(data: &[u8], pos: usize) -> Result<usize, String> {
+    let word = read_word(data, pos)?;
+    if word[..24].iter().any(|byte| *byte != 0) {
+        return Err(format!("Offset or length at {} is too large", pos));
+    }
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(&word[24..]);
+    let value = u64::from_be_bytes(buf) as usize;
+    if value > data.len() {
+        return Err(format!("Offset or length at {} is out of bounds", pos));
+    }
+    Ok(value)
+}
+
+fn decode_signed(word: &[u8]) -> String {
+    if word[0] & 0x80 == 0 {
+        return be_bytes_to_decimal(word);
+    }
+
+    // Two's complement: invert and add one to get the magnitude.
+    let mut magnitude: Vec<u8> = word.iter().map(|byte| !byte).collect();
+    for byte in magnitude.iter_mut().rev() {
+        let (value, overflow) = byte.overflowing_add(1);
+        *byte = value;
+        if !overflow {
+            break;
+        }
+    }
+    format!("-{}", be_bytes_to_decimal(&magnitude))
+}
+
+/// Makes sure the heads of `count` elements fit in what's left of `data` before
+/// allocating them.
+fn check_elements(inner: &ParamType, count: usize, data: &[u8], pos: usize) -> Result<(), String> {
+    let needed = inner.head_size().max(1).saturating_mul(count);
+    match needed > data.len().saturating_sub(pos) {
+        true => Err(format!(
+            "{} array elements at {} are out of bounds",
+            count, pos
+        )),
+        false => Ok(()),
+    }
+}
+
+fn decode_tuple(types: &[ParamType], data: &[u8], base: usize) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut offset = base;
+
+    for kind in types {
+        let token = if kind.is_dynamic() {
+            let pointer = read_usize(data, offset)?;
+            decode_at(kind, data, base + pointer)?
+        } else {
+            decode_at(kind, data, offset)?
+        };
+        tokens.push(token);
+        offset += kind.head_size();
+    }
+
+    Ok(tokens)
+}
+
+fn decode_at(kind: &ParamType, data: &[u8], pos: usize) -> Result<Token, String> {
+    let token = match kind {
+        ParamType::Address => {
+            let word = read_word(data, pos)?;
+            if word[..12].iter().any(|byte| *byte != 0) {
+                return Err("Invalid address padding".to_string());
+            }
+            Token::Address(encode_hex(&word[12..]))
+        }
+        ParamType::Uint(bits) => {
+            let word = read_word(data, pos)?;
+            let unused = 32 - bits / 8;
+            if word[..unused].iter().any(|byte| *byte != 0) {
+                return Err(format!("Value does not fit in uint{}", bits));
+            }
+            Token::Uint(be_bytes_to_decimal(word))
+        }
+        ParamType::Int(_) => Token::Int(decode_signed(read_word(data, pos)?)),
+        ParamType::Bool => {
+            let word = read_word(data, pos)?;
+            if word[..31].iter().any(|byte| *byte != 0) || word[31] > 1 {
+                return Err("Invalid bool".to_string());
+            }
+            Token::Bool(word[31] == 1)
+        }
+        ParamType::FixedBytes(size) => {
+            let word = read_word(data, pos)?;
+            Token::FixedBytes(encode_hex(&word[..*size]))
+        }
+        ParamType::Function => Token::FixedBytes(encode_hex(&read_word(data, pos)?[..24])),
+        ParamType::Bytes | ParamType::String => {
+            let len = read_usize(data, pos)?;
+            let bytes = data
+                .get(pos + 32..pos + 32 + len)
+                .ok_or("Dynamic data is out of bounds".to_string())?;
+            if *kind == ParamType::Bytes {
+                Token::Bytes(encode_hex(bytes))
+            } else {
+                let value = String::from_utf8(bytes.to_vec()).map_err(|e| e.to_string())?;
+                Token::String(value)
+            }
+        }
+        ParamType::Array(inner) => {
+            let len = read_usize(data, pos)?;
+            check_elements(inner, len, data, pos + 32)?;
+            let types = vec![*inner.clone(); len];
+            Token::Array(decode_tuple(&types, data, pos + 32)?)
+        }
+        ParamType::FixedArray(inner, size) => {
+            check_elements(inner, *size, data, pos)?;
+            let types = vec![*inner.clone(); *size];
+            Token::Array(decode_tuple(&types, data, pos)?)
+        }
+        ParamType::Tuple(members) => Token::Tuple(decode_tuple(members, data, pos)?),
+    };
+
+    Ok(token)
+}
+
+/// Decodes ABI encoded `data` (without the selector) into tokens for `types`.
+pub fn decode(types: &[ParamType], data: &[u8]) -> Result<Vec<Token>, String> {
+    decode_tuple(types, data, 0)
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AbiParam {
+    #[serde(default)]
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub components: Vec<AbiParam>,
+}
+
+impl AbiParam {
+    pub fn param_type(&self) -> Result<ParamType, String> {
+        match self.kind.strip_prefix("tuple") {
+            Some(suffix) => {
+                let members = self
+                    .components
+                    .iter()
+                    .map(|component| component.param_type().map(|kind| kind.canonical()))
+                    .collect::<Result<Vec<_>, String>>()?;
+                ParamType::parse(&format!("({}){}", members.join(","), suffix))
+            }
+            None => ParamType::parse(&self.kind),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AbiItem {
+    #[serde(rename = "type", default)]
+    pub kind: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub inputs: Vec<AbiParam>,
+}
+
+impl AbiItem {
+    /// Builds an item from a text signature such as `transfer(address,uint256)`.
+    pub fn from_signature(kind: &str, signature: &str) -> Result<AbiItem, String> {
+        let open = signature
+            .find('(')
+            .ok_or(format!("Invalid signature: {}", signature))?;
+        let name = signature[..open].trim().to_string();
+        let params = ParamType::parse(&signature[open..])?;
+        let members = match params {
+            ParamType::Tuple(members) => members,
+            _ => return Err(format!("Invalid signature: {}", signature)),
+        };
+
+        Ok(AbiItem {
+            kind: kind.to_string(),
+            name,
+            inputs: members
+                .iter()
+                .map(|member| AbiParam {
+                    name: String::new(),
+                    kind: member.canonical(),
+                    components: vec![],
+                })
+                .collect(),
+        })
+    }
+
+    pub fn param_types(&self) -> Result<Vec<ParamType>, String> {
+        self.inputs.iter().map(|input| input.param_type()).collect()
+    }
+
+    pub fn signature(&self) -> Result<String, String> {
+        let types = self
+            .param_types()?
+            .iter()
+            .map(|kind| kind.canonical())
+            .collect::<Vec<_>>();
+        Ok(format!("{}({})", self.name, types.join(",")))
+    }
+
+    pub fn selector(&self) -> Result<String, String> {
+        let hash = keccak256(self.signature()?.as_bytes());
+        Ok(encode_hex(&hash[..4]))
+    }
+
+    /// Decodes `data` (selector included) against this item's inputs.
+    pub fn decode(&self, data: &[u8]) -> Result<DecodedCall, String> {
+        let body = data.get(4..).ok_or("Data is shorter than a selector")?;
+        let tokens = decode(&self.param_types()?, body)?;
+        let params = self
+            .inputs
+            .iter()
+            .zip(tokens)
+            .enumerate()
+            .map(|(idx, (input, token))| {
+                let name = match input.name.is_empty() {
+                    true => format!("arg{}", idx),
+                    false => input.name.clone(),
+                };
+                let kind = input.param_type().map(|kind| kind.canonical());
+                (name, kind.unwrap_or(input.kind.clone()), token)
+            })
+            .collect();
+
+        Ok(DecodedCall {
+            selector: encode_hex(&data[..4]),
+            signature: self.signature()?,
+            params,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Abi {
+    pub items: Vec<AbiItem>,
+}
+
+impl Abi {
+    /// Loads a JSON ABI, either a bare array or a compiler artifact with an `abi` key.
+    pub fn from_file(path: &str) -> Result<Abi, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let json: Value = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        let items = match json.get("abi") {
+            Some(abi) => abi.clone(),
+            None => json,
+        };
+        let items: Vec<AbiItem> = serde_json::from_value(items).map_err(|e| e.to_string())?;
+        Ok(Abi { items })
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = &AbiItem> {
+        self.items.iter().filter(|item| item.kind == "function")
+    }
+
+    pub fn function_by_selector(&self, selector: &str) -> Option<&AbiItem> {
+        self.functions()
+            .find(|item| item.selector().map(|s| s == selector).unwrap_or(false))
+    }
+
+    /// Custom errors (`error InsufficientBalance(uint256 available)`), since solidity 0.8.4.
+    pub fn errors(&self) -> impl Iterator<Item = &AbiItem> {
+        self.items.iter().filter(|item| item.kind == "error")
+    }
+
+    pub fn error_by_selector(&self, selector: &str) -> Option<&AbiItem> {
+        self.errors()
+            .find(|item| item.selector().map(|s| s == selector).unwrap_or(false))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DecodedCall {
+    pub selector: String,
+    pub signature: String,
+    pub params: Vec<(String, String, Token)>,
+}
+
+impl DecodedCall {
+    /// Tries every candidate in order and returns the first one that decodes cleanly.
+    pub fn decode(input: &str, candidates: &[AbiItem]) -> Option<DecodedCall> {
+        let data = decode_hex(input).ok()?;
+        candidates.iter().find_map(|item| item.decode(&data).ok())
+    }
+}
+
+impl fmt::Display for DecodedCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let function = format!("{} [{}]", self.signature, self.selector);
+        write!(f, "{}", format_label_and_value("Function", &function))?;
+        for (name, kind, token) in &self.params {
+            let label = format!("  {} ({})", name, kind);
+            write!(f, "{}", format_label_and_value(&label, &token.to_string()))?;
+        }
+
+        write!(f, "")
+    }
+}
@@ -0,0 +1,171 @@
+use crate::chains::ChainInfo;
+use crate::providers::{Provider, ProviderError};
+use crate::types::ResultParser;
+use crate::utils::{decode_hex, encode_hex, format_label_and_value, format_units, keccak256};
+use std::fmt;
+
+/// Prefix of the code an EIP-7702 authorization installs on an EOA, followed by the
+/// 20 byte delegate address.
+const DELEGATION_PREFIX: [u8; 3] = [0xef, 0x01, 0x00];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccountKind {
+    Eoa,
+    Contract,
+    /// EOA whose code points to a delegate contract (EIP-7702).
+    Delegated(String),
+}
+
+impl AccountKind {
+    pub fn from_code(code: &[u8]) -> AccountKind {
+        if code.is_empty() {
+            return AccountKind::Eoa;
+        }
+        if code.len() == 23 && code.starts_with(&DELEGATION_PREFIX) {
+            return AccountKind::Delegated(encode_hex(&code[3..]));
+        }
+        AccountKind::Contract
+    }
+}
+
+impl fmt::Display for AccountKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountKind::Eoa => write!(f, "EOA"),
+            AccountKind::Contract => write!(f, "Contract"),
+            AccountKind::Delegated(delegate) => {
+                write!(f, "EOA delegated to {} (EIP-7702)", delegate)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AccountInfo {
+    pub address: String,
+    pub kind: AccountKind,
+    pub balance: i128,
+    pub nonce: i128,
+    pub code_size: usize,
+    /// Keccak-256 of the code, which for an EOA is the hash of empty code.
+    pub code_hash: [u8; 32],
+}
+
+impl AccountInfo {
+    pub async fn fetch<P: Provider>(
+        provider: &P,
+        addr: &str,
+        block: Option<i32>,
+    ) -> Result<AccountInfo, ProviderError> {
+        let code = provider.get_code(addr, block).await?;
+        let code = decode_hex(&code.result()).map_err(ProviderError::Invalid)?;
+        let balance = provider.get_balance(addr, block).await?.to_decimals();
+        let nonce = provider.get_tx_count(addr, block).await?.to_decimals();
+
+        Ok(AccountInfo {
+            address: addr.to_string(),
+            kind: AccountKind::from_code(&code),
+            balance,
+            nonce,
+            code_size: code.len(),
+            code_hash: keccak256(&code),
+        })
+    }
+}
+
+impl fmt::Display for AccountInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chain = ChainInfo::current();
+        let lines = [
+            ("Address", self.address.clone()),
+            ("Type", self.kind.to_string()),
+            (
+                "Balance",
+                format!(
+                    "{} {}",
+                    format_units(self.balance, chain.decimals),
+                    chain.symbol
+                ),
+            ),
+            ("Nonce", self.nonce.to_string()),
+            ("Code Size", format!("{} bytes", self.code_size)),
+            ("Code Hash", encode_hex(&self.code_hash)),
+        ];
+
+        for (label, value) in lines {
+            write!(f, "{}", format_label_and_value(label, &value))?;
+        }
+
+        write!(f, "")
+    }
+}
+
+/// Parses a 256 bit word given in decimal or as `0x` prefixed hex.
+pub fn parse_word(value: &str) -> Result<[u8; 32], String> {
+    let value = value.trim();
+    let bytes = match value.strip_prefix("0x") {
+        Some(_) => decode_hex(value)?,
+        None => {
+            let mut bytes = vec![0u8; 32];
+            for c in value.chars() {
+                let mut carry = c
+                    .to_digit(10)
+                    .ok_or_else(|| format!("Invalid number {}", value))?;
+                for byte in bytes.iter_mut().rev() {
+                    let next = *byte as u32 * 10 + carry;
+                    *byte = (next & 0xff) as u8;
+                    carry = next >> 8;
+                }
+                if carry > 0 {
+                    return Err(format!("{} does not fit in 256 bits", value));
+                }
+            }
+            bytes
+        }
+    };
+
+    if bytes.len() > 32 {
+        return Err(format!("{} does not fit in 256 bits", value));
+    }
+    let mut word = [0u8; 32];
+    word[32 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(word)
+}
+
+/// Adds two 256 bit words, wrapping on overflow like the EVM.
+fn add_words(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    let mut sum = [0u8; 32];
+    let mut carry = 0u16;
+    for i in (0..32).rev() {
+        let value = a[i] as u16 + b[i] as u16 + carry;
+        sum[i] = (value & 0xff) as u8;
+        carry = value >> 8;
+    }
+    sum
+}
+
+/// Encodes a mapping key the way solidity hashes it: value types are left padded
+/// to 32 bytes, a `"quoted"` key is hashed as raw string bytes.
+fn encode_key(key: &str) -> Result<Vec<u8>, String> {
+    let key = key.trim();
+    if let Some(key) = key.strip_prefix('"').and_then(|key| key.strip_suffix('"')) {
+        return Ok(key.as_bytes().to_vec());
+    }
+    Ok(parse_word(key)?.to_vec())
+}
+
+/// Slot of `mapping[key]` for a mapping declared at `slot`: `keccak256(key . slot)`.
+pub fn mapping_slot(slot: &[u8; 32], key: &str) -> Result<[u8; 32], String> {
+    let mut data = encode_key(key)?;
+    data.extend_from_slice(slot);
+    Ok(keccak256(&data))
+}
+
+/// Slot of `array[index]` for a dynamic array declared at `slot`, with elements
+/// taking `element_slots` slots each: `keccak256(slot) + index * element_slots`.
+pub fn array_slot(slot: &[u8; 32], index: u64, element_slots: u64) -> [u8; 32] {
+    let offset = (index as u128).saturating_mul(element_slots as u128);
+    let mut word = [0u8; 32];
+    word[16..].copy_from_slice(&offset.to_be_bytes());
+    add_words(keccak256(slot), word)
+}
@@ -0,0 +1,171 @@
+use crate::header::{Header, HeaderIssue};
+use crate::providers::{block_param, Provider, ProviderError};
+use crate::trie::ordered_root;
+use crate::types::{Method, ReceiptHex, TransactionHex};
+use crate::utils::{encode_hex, format_label_and_value, hex_to_decimals};
+use serde_json::{json, Value};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RootStatus {
+    Match,
+    Mismatch {
+        computed: String,
+    },
+    /// The data needed to rebuild the trie couldn't be fetched or encoded.
+    Unavailable(String),
+}
+
+/// A root from the header and what the block data actually hashes to.
+#[derive(Debug, Clone)]
+pub struct RootCheck {
+    pub header: String,
+    /// Number of items the trie was rebuilt from.
+    pub count: usize,
+    pub status: RootStatus,
+}
+
+impl RootCheck {
+    fn new(header: &str, encoded: Result<Vec<Vec<u8>>, String>) -> RootCheck {
+        let (count, status) = match encoded {
+            Ok(values) => {
+                let computed = encode_hex(&ordered_root(&values));
+                let status = match computed == header.to_lowercase() {
+                    true => RootStatus::Match,
+                    false => RootStatus::Mismatch { computed },
+                };
+                (values.len(), status)
+            }
+            Err(e) => (0, RootStatus::Unavailable(e)),
+        };
+        RootCheck {
+            header: header.to_string(),
+            count,
+            status,
+        }
+    }
+
+    fn describe(&self, items: &str) -> String {
+        match &self.status {
+            RootStatus::Match => format!("ok ({} {})", self.count, items),
+            RootStatus::Mismatch { computed } => format!(
+                "MISMATCH, header has {} but {} {} hash to {}",
+                self.header, self.count, items, computed
+            ),
+            RootStatus::Unavailable(reason) => format!("not checked, {}", reason),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct BodyReport {
+    pub number: i128,
+    pub hash: String,
+    pub header: Result<(), HeaderIssue>,
+    pub transactions: RootCheck,
+    pub receipts: RootCheck,
+}
+
+impl BodyReport {
+    /// Whether the header hash and both roots were checked and matched.
+    pub fn is_valid(&self) -> bool {
+        self.header.is_ok()
+            && self.transactions.status == RootStatus::Match
+            && self.receipts.status == RootStatus::Match
+    }
+}
+
+impl fmt::Display for BodyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let header = match &self.header {
+            Ok(()) => "ok".to_string(),
+            Err(issue) => issue.to_string(),
+        };
+        let lines = [
+            ("Block", format!("{} {}", self.number, self.hash)),
+            ("Header Hash", header),
+            (
+                "Transactions Root",
+                self.transactions.describe("transactions"),
+            ),
+            ("Receipts Root", self.receipts.describe("receipts")),
+        ];
+        for (label, value) in lines {
+            write!(f, "{}", format_label_and_value(label, &value))?;
+        }
+
+        write!(f, "")
+    }
+}
+
+/// Receipts of the block, one `eth_getTransactionReceipt` per transaction when the
+/// node doesn't support `eth_getBlockReceipts`. The inner error tells which receipt
+/// is missing, a root over part of the receipts would only be a false mismatch.
+async fn fetch_receipts<P: Provider>(
+    provider: &P,
+    number: i128,
+    tx_hashes: &[String],
+) -> Result<Result<Vec<ReceiptHex>, String>, ProviderError> {
+    let params = vec![json!(format!("0x{:x}", number))];
+    if let Some(receipts) = provider.request(Method::GetBlockReceipts, params).await? {
+        return Ok(Ok(serde_json::from_value(receipts)?));
+    }
+
+    let mut receipts = vec![];
+    for hash in tx_hashes {
+        match provider
+            .request(Method::GetTxReceipt, vec![json!(hash)])
+            .await?
+        {
+            Some(receipt) => receipts.push(serde_json::from_value(receipt)?),
+            None => return Ok(Err(format!("receipt of {} is missing", hash))),
+        }
+    }
+    Ok(Ok(receipts))
+}
+
+/// Rebuilds the transactions and receipts tries of a block and compares their roots,
+/// and the block hash, with its header.
+pub async fn verify_block<P: Provider>(
+    provider: &P,
+    number: Option<i32>,
+) -> Result<BodyReport, ProviderError> {
+    let params = vec![json!(block_param(number)), json!(true)];
+    let res = provider.fetch(Method::GetBlockByNumber, params).await?;
+    let block = match &res["result"] {
+        Value::Null => return Err(ProviderError::NotFound("Block".to_string())),
+        block => block.clone(),
+    };
+    let header: Header = serde_json::from_value(block.clone())?;
+    let transactions: Vec<TransactionHex> = serde_json::from_value(block["transactions"].clone())?;
+    let tx_hashes: Vec<String> = block["transactions"]
+        .as_array()
+        .map(|txs| {
+            txs.iter()
+                .filter_map(|tx| tx["hash"].as_str().map(|hash| hash.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let number = hex_to_decimals(&header.number, true);
+    let receipts = fetch_receipts(provider, number, &tx_hashes).await?;
+
+    let encoded_txs = transactions
+        .iter()
+        .map(|tx| tx.encode())
+        .collect::<Result<Vec<_>, _>>();
+    let encoded_receipts = receipts.and_then(|receipts| {
+        receipts
+            .iter()
+            .map(|receipt| receipt.encode())
+            .collect::<Result<Vec<_>, _>>()
+    });
+
+    Ok(BodyReport {
+        number,
+        hash: header.hash.clone(),
+        header: header.verify(),
+        transactions: RootCheck::new(&header.transactions_root, encoded_txs),
+        receipts: RootCheck::new(&header.receipts_root, encoded_receipts),
+    })
+}
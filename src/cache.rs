@@ -0,0 +1,378 @@
+use crate::utils::{data_dir, encode_hex, hex_to_decimals, keccak256};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, OnceLock,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Blocks this far below the highest block seen are considered final.
+const FINALITY_DEPTH: i128 = 64;
+const DEFAULT_MAX_MB: u64 = 256;
+const DEFAULT_TTL_SECS: u64 = 15;
+const HEAD_FILE: &str = "head";
+
+static CACHE: OnceLock<Option<Cache>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    Immutable,
+    Ttl(u64),
+    Skip,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    stored_at: u64,
+    expires_at: Option<u64>,
+    response: Value,
+}
+
+#[derive(Debug)]
+pub struct CacheStats {
+    pub dir: PathBuf,
+    pub entries: usize,
+    pub size: u64,
+    pub max_size: u64,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// On-disk cache of JSON-RPC responses keyed by method and params.
+///
+/// Responses are kept in one namespace per chain and endpoint (see `Cache::namespace`),
+/// each with its own finality head, so switching the config profile or
+/// `BLOCKCHAIN_CLI_RPC` never serves data from another node.
+///
+/// Configured with env vars: `BLOCKCHAIN_CLI_CACHE=off` disables it,
+/// `BLOCKCHAIN_CLI_CACHE_DIR` sets the location, `BLOCKCHAIN_CLI_CACHE_MAX_MB`
+/// the size limit and `BLOCKCHAIN_CLI_CACHE_TTL` the TTL (seconds) of mutable results.
+pub struct Cache {
+    dir: PathBuf,
+    max_size: u64,
+    ttl: u64,
+    /// Running total of the entries size, `None` until the directory was scanned once.
+    size: Mutex<Option<u64>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn block_number_of(param: Option<&Value>) -> Option<i128> {
+    let tag = param?.as_str()?;
+    match tag.starts_with("0x") {
+        true => Some(hex_to_decimals(tag, true)),
+        false => None,
+    }
+}
+
+impl Cache {
+    pub fn global() -> Option<&'static Cache> {
+        CACHE.get_or_init(Cache::from_env).as_ref()
+    }
+
+    fn from_env() -> Option<Cache> {
+        if let Ok("off" | "0" | "false") = env::var("BLOCKCHAIN_CLI_CACHE").as_deref() {
+            return None;
+        }
+
+        let dir = match env::var("BLOCKCHAIN_CLI_CACHE_DIR") {
+            Ok(dir) => PathBuf::from(dir),
+            Err(_) => data_dir().join("cache"),
+        };
+        let max_mb = env::var("BLOCKCHAIN_CLI_CACHE_MAX_MB")
+            .ok()
+            .and_then(|val| val.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_MAX_MB);
+        let ttl = env::var("BLOCKCHAIN_CLI_CACHE_TTL")
+            .ok()
+            .and_then(|val| val.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_TTL_SECS);
+
+        Some(Cache::new(dir, max_mb * 1024 * 1024, ttl))
+    }
+
+    pub fn new(dir: PathBuf, max_size: u64, ttl: u64) -> Cache {
+        Cache {
+            dir,
+            max_size,
+            ttl,
+            size: Mutex::new(None),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn key(request: &Value) -> Option<String> {
+        let method = request.get("method")?.as_str()?;
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+        let key = format!("{}:{}", method, params);
+        Some(encode_hex(&keccak256(key.as_bytes()))[2..].to_string())
+    }
+
+    /// Subdirectory holding the responses of `endpoint` on chain `chain_id`.
+    pub fn namespace(chain_id: u64, endpoint: &str) -> String {
+        let endpoint = encode_hex(&keccak256(endpoint.as_bytes()));
+        format!("{}-{}", chain_id, &endpoint[2..18])
+    }
+
+    fn entry_path(&self, namespace: &str, key: &str) -> PathBuf {
+        self.dir.join(namespace).join(format!("{}.json", key))
+    }
+
+    /// Highest block number observed in responses of `namespace`, used to decide finality.
+    fn head(&self, namespace: &str) -> Option<i128> {
+        fs::read_to_string(self.dir.join(namespace).join(HEAD_FILE))
+            .ok()
+            .and_then(|val| val.trim().parse::<i128>().ok())
+    }
+
+    fn observe_head(&self, namespace: &str, number: i128) {
+        if self
+            .head(namespace)
+            .map(|head| number > head)
+            .unwrap_or(true)
+        {
+            let dir = self.dir.join(namespace);
+            let _ = fs::create_dir_all(&dir);
+            let _ = fs::write(dir.join(HEAD_FILE), number.to_string());
+        }
+    }
+
+    fn is_final(&self, namespace: &str, number: Option<i128>) -> bool {
+        match (number, self.head(namespace)) {
+            (Some(number), Some(head)) => number + FINALITY_DEPTH <= head,
+            _ => false,
+        }
+    }
+
+    /// Block of the transaction `hash` as found in the cached transaction or receipt,
+    /// `debug_traceTransaction` results don't carry it.
+    fn cached_block_of(&self, namespace: &str, hash: Option<&Value>) -> Option<i128> {
+        let hash = hash?;
+        ["eth_getTransactionReceipt", "eth_getTransactionByHash"]
+            .iter()
+            .find_map(|method| {
+                let request = json!({ "method": method, "params": [hash] });
+                let path = self.entry_path(namespace, &Cache::key(&request)?);
+                let entry = serde_json::from_str::<Entry>(&fs::read_to_string(path).ok()?).ok()?;
+                block_number_of(entry.response.get("result")?.get("blockNumber"))
+            })
+    }
+
+    /// Decides how long the response of `request` can be reused.
+    pub fn policy(&self, namespace: &str, request: &Value, response: &Value) -> Policy {
+        let result = match response.get("result") {
+            Some(Value::Null) | None => return Policy::Skip,
+            Some(result) => result,
+        };
+        let method = request.get("method").and_then(|m| m.as_str()).unwrap_or("");
+        let params = request.get("params").and_then(|p| p.as_array());
+        let param = |idx: usize| params.and_then(|p| p.get(idx));
+
+        match method {
+            "eth_blockNumber" => {
+                if let Some(number) = result.as_str() {
+                    self.observe_head(namespace, hex_to_decimals(number, true));
+                }
+                Policy::Skip
+            }
+            "eth_getBlockByHash" => Policy::Immutable,
+            "eth_getBlockByNumber" | "eth_getBlockReceipts" => {
+                if let Some(number) = result.get("number").and_then(|n| n.as_str()) {
+                    self.observe_head(namespace, hex_to_decimals(number, true));
+                }
+                match self.is_final(namespace, block_number_of(param(0))) {
+                    true => Policy::Immutable,
+                    false => Policy::Skip,
+                }
+            }
+            // Mined but not final yet, a reorg can still move them to another block.
+            "eth_getTransactionByHash" | "eth_getTransactionReceipt" => {
+                match self.is_final(namespace, block_number_of(result.get("blockNumber"))) {
+                    true => Policy::Immutable,
+                    false => Policy::Skip,
+                }
+            }
+            // Traces follow the finality of their transaction's block.
+            "debug_traceTransaction" | "trace_transaction" => {
+                let number = match result.get(0).and_then(|trace| trace.get("blockNumber")) {
+                    Some(number) => number.as_i64().map(i128::from),
+                    None => self.cached_block_of(namespace, param(0)),
+                };
+                match self.is_final(namespace, number) {
+                    true => Policy::Immutable,
+                    false => Policy::Skip,
+                }
+            }
+            "eth_getBalance" | "eth_getTransactionCount" => match block_number_of(param(1)) {
+                Some(number) if self.is_final(namespace, Some(number)) => Policy::Immutable,
+                Some(_) => Policy::Skip,
+                None => Policy::Ttl(self.ttl),
+            },
+            _ => Policy::Skip,
+        }
+    }
+
+    pub fn get(&self, namespace: &str, request: &Value) -> Option<Value> {
+        let key = Cache::key(request)?;
+        let path = self.entry_path(namespace, &key);
+        let entry = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Entry>(&content).ok());
+
+        match entry {
+            Some(entry) if entry.expires_at.map(|at| at > now()).unwrap_or(true) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(entry.response)
+            }
+            Some(_) => {
+                let _ = fs::remove_file(path);
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    pub fn store(&self, namespace: &str, request: &Value, response: &Value) {
+        let expires_at = match self.policy(namespace, request, response) {
+            Policy::Skip => return,
+            Policy::Immutable => None,
+            Policy::Ttl(ttl) => Some(now() + ttl),
+        };
+        let key = match Cache::key(request) {
+            Some(key) => key,
+            None => return,
+        };
+
+        let entry = Entry {
+            stored_at: now(),
+            expires_at,
+            response: response.clone(),
+        };
+
+        if fs::create_dir_all(self.dir.join(namespace)).is_err() {
+            return;
+        }
+        let content = match serde_json::to_string(&entry) {
+            Ok(content) => content,
+            Err(_) => return,
+        };
+        if fs::write(self.entry_path(namespace, &key), &content).is_err() {
+            return;
+        }
+
+        // The directory is only scanned again once the running total goes over the limit.
+        let mut size = self.size.lock().unwrap();
+        let total = match *size {
+            Some(total) => total + content.len() as u64,
+            None => self.entries().iter().map(|(_, len, _)| len).sum(),
+        };
+        *size = Some(match total > self.max_size {
+            true => self.evict(),
+            false => total,
+        });
+    }
+
+    fn json_files(dir: &Path) -> Vec<(PathBuf, u64, SystemTime)> {
+        let dir = match fs::read_dir(dir) {
+            Ok(dir) => dir,
+            Err(_) => return vec![],
+        };
+
+        dir.filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .path()
+                    .extension()
+                    .map(|e| e == "json")
+                    .unwrap_or(false)
+            })
+            .filter_map(|entry| {
+                let meta = entry.metadata().ok()?;
+                Some((entry.path(), meta.len(), meta.modified().ok()?))
+            })
+            .collect()
+    }
+
+    fn namespaces(&self) -> Vec<PathBuf> {
+        match fs::read_dir(&self.dir) {
+            Ok(dir) => dir
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    /// Entries of every namespace.
+    fn entries(&self) -> Vec<(PathBuf, u64, SystemTime)> {
+        self.namespaces()
+            .iter()
+            .flat_map(|dir| Cache::json_files(dir))
+            .collect()
+    }
+
+    /// Removes the oldest entries until the cache fits in `max_size`, returns the
+    /// size left.
+    fn evict(&self) -> u64 {
+        let mut entries = self.entries();
+        let mut size: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        if size <= self.max_size {
+            return size;
+        }
+
+        entries.sort_by_key(|(_, _, modified)| *modified);
+        for (path, len, _) in entries {
+            if size <= self.max_size {
+                break;
+            }
+            if fs::remove_file(path).is_ok() {
+                size -= len;
+            }
+        }
+        size
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let entries = self.entries();
+        CacheStats {
+            dir: self.dir.clone(),
+            entries: entries.len(),
+            size: entries.iter().map(|(_, len, _)| len).sum(),
+            max_size: self.max_size,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Deletes every cached response, returns how many were removed.
+    pub fn clear(&self) -> usize {
+        let entries = self.entries();
+        let removed = entries
+            .iter()
+            .filter(|(path, _, _)| fs::remove_file(path).is_ok())
+            .count();
+        for dir in self.namespaces() {
+            let _ = fs::remove_file(dir.join(HEAD_FILE));
+        }
+        *self.size.lock().unwrap() = Some(0);
+        removed
+    }
+}
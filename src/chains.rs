@@ -0,0 +1,109 @@
+use crate::providers::{Provider, ProviderError};
+use std::sync::OnceLock;
+
+static CHAIN: OnceLock<ChainInfo> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainInfo {
+    pub id: u64,
+    pub name: &'static str,
+    pub symbol: &'static str,
+    pub decimals: u32,
+    pub explorer: Option<&'static str>,
+}
+
+const fn chain(
+    id: u64,
+    name: &'static str,
+    symbol: &'static str,
+    explorer: &'static str,
+) -> ChainInfo {
+    ChainInfo {
+        id,
+        name,
+        symbol,
+        decimals: 18,
+        explorer: Some(explorer),
+    }
+}
+
+/// Metadata for well known chains, used to label values and link to explorers.
+const CHAINS: &[ChainInfo] = &[
+    chain(1, "Ethereum Mainnet", "ETH", "https://etherscan.io"),
+    chain(3, "Ropsten", "ETH", "https://ropsten.etherscan.io"),
+    chain(5, "Goerli", "ETH", "https://goerli.etherscan.io"),
+    chain(17000, "Holesky", "ETH", "https://holesky.etherscan.io"),
+    chain(560048, "Hoodi", "ETH", "https://hoodi.etherscan.io"),
+    chain(11155111, "Sepolia", "ETH", "https://sepolia.etherscan.io"),
+    chain(10, "OP Mainnet", "ETH", "https://optimistic.etherscan.io"),
+    chain(
+        11155420,
+        "OP Sepolia",
+        "ETH",
+        "https://sepolia-optimism.etherscan.io",
+    ),
+    chain(8453, "Base", "ETH", "https://basescan.org"),
+    chain(84532, "Base Sepolia", "ETH", "https://sepolia.basescan.org"),
+    chain(42161, "Arbitrum One", "ETH", "https://arbiscan.io"),
+    chain(
+        421614,
+        "Arbitrum Sepolia",
+        "ETH",
+        "https://sepolia.arbiscan.io",
+    ),
+    chain(59144, "Linea", "ETH", "https://lineascan.build"),
+    chain(534352, "Scroll", "ETH", "https://scrollscan.com"),
+    chain(324, "zkSync Era", "ETH", "https://explorer.zksync.io"),
+    chain(81457, "Blast", "ETH", "https://blastscan.io"),
+    chain(56, "BNB Smart Chain", "BNB", "https://bscscan.com"),
+    chain(137, "Polygon", "POL", "https://polygonscan.com"),
+    chain(100, "Gnosis", "xDAI", "https://gnosisscan.io"),
+    chain(43114, "Avalanche C-Chain", "AVAX", "https://snowtrace.io"),
+    chain(250, "Fantom", "FTM", "https://ftmscan.com"),
+    chain(5000, "Mantle", "MNT", "https://mantlescan.xyz"),
+    chain(42220, "Celo", "CELO", "https://celoscan.io"),
+];
+
+impl ChainInfo {
+    pub fn by_id(id: u64) -> ChainInfo {
+        CHAINS
+            .iter()
+            .find(|chain| chain.id == id)
+            .copied()
+            .unwrap_or(ChainInfo {
+                id,
+                name: "Unknown chain",
+                symbol: "ETH",
+                decimals: 18,
+                explorer: None,
+            })
+    }
+
+    /// Chain detected at startup, defaults to an unknown 18 decimals chain.
+    pub fn current() -> ChainInfo {
+        CHAIN.get().copied().unwrap_or(ChainInfo::by_id(0))
+    }
+
+    /// Asks the node which chain it serves (`eth_chainId`, falling back to `net_version`)
+    /// and remembers it for labelling values.
+    pub async fn detect<P: Provider>(provider: &P) -> Result<Option<ChainInfo>, ProviderError> {
+        let id = match provider.chain_id().await? {
+            Some(id) => Some(id),
+            None => provider.net_version().await?,
+        };
+
+        Ok(id.map(|id| {
+            let chain = ChainInfo::by_id(id);
+            let _ = CHAIN.set(chain);
+            chain
+        }))
+    }
+
+    pub fn tx_url(&self, hash: &str) -> Option<String> {
+        self.explorer.map(|url| format!("{}/tx/{}", url, hash))
+    }
+
+    pub fn address_url(&self, addr: &str) -> Option<String> {
+        self.explorer.map(|url| format!("{}/address/{}", url, addr))
+    }
+}
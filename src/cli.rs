@@ -1,7 +1,29 @@
 use colored::*;
-use std::{io::{self, Write}};
+use std::{io::{self, Write}, process};
 
-use crate::{rpc_calls::RpcCalls, types::ResultParser};
+use crate::{
+    abi::{Abi, DecodedCall},
+    account::{self, AccountInfo, AccountKind},
+    body,
+    cache::Cache,
+    chains::ChainInfo,
+    config::Config,
+    cost::TxCost,
+    header,
+    indexer::{IndexError, Indexer},
+    node::NodeInfo,
+    profile::GasProfile,
+    providers::{self, Provider, ProviderError},
+    proxy,
+    ratelimit,
+    revert::{self, RevertReason},
+    scanner::Scanner,
+    signatures::SignatureDb,
+    statediff::StorageLayout,
+    trace::{self, CallFrame},
+    types::{ResultParser, Transaction, TransactionHex},
+    utils::{decode_hex, encode_hex, format_label_and_value, format_units, hex_to_decimals},
+};
 
 pub enum Option {
     GetAccounts,
@@ -12,6 +34,22 @@ pub enum Option {
     GetBlockTxCount,
     GetBlock,
     GetTx,
+    ImportSignatures,
+    ScanAddresses,
+    IndexBlocks,
+    QueryIndex,
+    Cache,
+    NodeInfo,
+    Disagreements,
+    RateLimits,
+    Account,
+    VerifyProof,
+    VerifyHeaders,
+    VerifyBody,
+    TraceTx,
+    InternalTransfers,
+    StateDiff,
+    GasProfile,
     None,
 }
 
@@ -26,6 +64,22 @@ impl Option {
             Option::GetBlockTxCount => "6) Get block tx count",
             Option::GetBlock => "7) Get block info",
             Option::GetTx => "8) Get transaction",
+            Option::ImportSignatures => "9) Import 4byte signatures",
+            Option::ScanAddresses => "10) Scan address activity",
+            Option::IndexBlocks => "11) Index blocks locally",
+            Option::QueryIndex => "12) Query local index",
+            Option::Cache => "13) Response cache (stats/clear)",
+            Option::NodeInfo => "14) Node health and info",
+            Option::Disagreements => "15) Provider disagreements",
+            Option::RateLimits => "16) Rate limiter stats",
+            Option::Account => "17) Inspect account (code/storage)",
+            Option::VerifyProof => "18) Verified balance and storage (eth_getProof)",
+            Option::VerifyHeaders => "19) Verify block headers",
+            Option::VerifyBody => "20) Verify block transactions and receipts",
+            Option::TraceTx => "21) Trace transaction calls",
+            Option::InternalTransfers => "22) Internal transfers (tx or block)",
+            Option::StateDiff => "23) Transaction state diff",
+            Option::GasProfile => "24) Gas profile of a transaction",
             Option::None => "Invalid input",
         }
     }
@@ -40,14 +94,38 @@ impl Option {
             6 => Option::GetBlockTxCount,
             7 => Option::GetBlock,
             8 => Option::GetTx,
+            9 => Option::ImportSignatures,
+            10 => Option::ScanAddresses,
+            11 => Option::IndexBlocks,
+            12 => Option::QueryIndex,
+            13 => Option::Cache,
+            14 => Option::NodeInfo,
+            15 => Option::Disagreements,
+            16 => Option::RateLimits,
+            17 => Option::Account,
+            18 => Option::VerifyProof,
+            19 => Option::VerifyHeaders,
+            20 => Option::VerifyBody,
+            21 => Option::TraceTx,
+            22 => Option::InternalTransfers,
+            23 => Option::StateDiff,
+            24 => Option::GasProfile,
             _ => Option::None,
         }
     }
 }
 
-pub struct CommandLine;
-impl CommandLine {
-    pub async fn select_option() -> Result<bool, reqwest::Error>{
+pub struct CommandLine<P: Provider> {
+    provider: P,
+}
+
+impl<P: Provider> CommandLine<P> {
+    pub fn new(provider: P) -> CommandLine<P> {
+        CommandLine { provider }
+    }
+
+    /// Runs one option picked by the user, returns whether they want to continue.
+    pub async fn select_option(&self) -> bool {
         let options = [
             Option::GetAccounts,
             Option::GetGasPrice,
@@ -57,82 +135,145 @@ impl CommandLine {
             Option::GetBlockTxCount,
             Option::GetBlock,
             Option::GetTx,
+            Option::ImportSignatures,
+            Option::ScanAddresses,
+            Option::IndexBlocks,
+            Option::QueryIndex,
+            Option::Cache,
+            Option::NodeInfo,
+            Option::Disagreements,
+            Option::RateLimits,
+            Option::Account,
+            Option::VerifyProof,
+            Option::VerifyHeaders,
+            Option::VerifyBody,
+            Option::TraceTx,
+            Option::InternalTransfers,
+            Option::StateDiff,
+            Option::GasProfile,
         ];
 
         for option in &options {
             println!(
                 "{}",
-                format!("{}", option.value()).underline().bold().cyan()
+                option.value().underline().bold().cyan()
             )
         }
 
-        let input = CommandLine::user_input("Select an option: ");
-        let option_idx = input.parse::<u32>().unwrap_or_else(|_| 0);
+        let input = Self::user_input("Select an option: ");
+        let option_idx = input.parse::<u32>().unwrap_or(0);
         let option = Option::get_option_by_idx(option_idx);
-        CommandLine::execute_option(option).await?;
-        let should_continue = match CommandLine::user_input("Continue?(Y/N): ").as_str() {
+        if let Err(e) = self.execute_option(option).await {
+            eprintln!("{}", format!("Request failed: {}", e).red());
+        }
+        match Self::user_input("Continue?(Y/N): ").as_str() {
             "Y" | "y" => true,
             "N" | "n" => false,
             _ => true,
-        };
+        }
+    }
 
-        Ok(should_continue)
+    /// Detects the chain served by the configured endpoint and warns when it isn't
+    /// the one the active profile expects.
+    pub async fn connect(&self) {
+        let config = Config::global();
+        println!(
+            "{}",
+            format!(
+                "Profile {} ({}, {})",
+                config.profile_name,
+                self.provider.endpoint(),
+                config.profile.strategy.value()
+            )
+            .bold()
+            .green()
+        );
+
+        match ChainInfo::detect(&self.provider).await {
+            Ok(Some(chain)) => {
+                println!("{}", format!("Connected to {} (chain id {})", chain.name, chain.id).bold().green());
+                if let Some(expected) = config.profile.chain_id {
+                    if expected != chain.id {
+                        let expected = ChainInfo::by_id(expected);
+                        println!(
+                            "{}",
+                            format!(
+                                "Warning: profile {} expects {} (chain id {}) but the node serves chain id {}",
+                                config.profile_name, expected.name, expected.id, chain.id
+                            )
+                            .bold()
+                            .yellow()
+                        );
+                    }
+                }
+            }
+            Ok(None) => println!("{}", "Warning: could not detect the chain id".bold().yellow()),
+            Err(e) => println!("{}", format!("Warning: could not detect the chain id: {}", e).bold().yellow()),
+        }
     }
 
     fn user_input(prefix: &str) -> String {
-        print!("{}", format!("{}", prefix).bold().on_cyan());
+        print!("{}", prefix.bold().on_cyan());
         io::stdout().flush().unwrap();
         let mut buf = String::new();
-        io::stdin().read_line(&mut buf).unwrap();
+        // Nothing more can be answered once stdin is closed (Ctrl-D).
+        if let Ok(0) | Err(_) = io::stdin().read_line(&mut buf) {
+            println!();
+            process::exit(0);
+        }
         let input = buf.trim();
 
         input.to_string()
     }
 
-    async fn execute_option(option: Option) -> Result<(), reqwest::Error> {
+    async fn execute_option(&self, option: Option) -> Result<(), ProviderError> {
         match option {
             Option::GetBalance => {
                 println!("Get user balance.");
-                let addr = CommandLine::user_input("Address: ");
-                CommandLine::loading();
-                let balance = RpcCalls::get_balance(addr.as_str(), None).await.unwrap();
-                CommandLine::display_label_and_value("ETH", balance.from_wei().to_string().as_str());
-                CommandLine::display_label_and_value("Wei", balance.to_decimals().to_string().as_str());
+                let addr = Self::user_input("Address: ");
+                Self::loading();
+                let balance = self.provider.get_balance(addr.as_str(), None).await?;
+                let chain = ChainInfo::current();
+                Self::display_label_and_value(chain.symbol, balance.to_native().as_str());
+                Self::display_label_and_value("Wei", balance.to_decimals().to_string().as_str());
+                if let Some(url) = chain.address_url(addr.as_str()) {
+                    Self::display_label_and_value("explorer", url.as_str());
+                }
                 Ok(())
             }, 
             Option::GetAccounts => {
                 println!("Get node accounts...");
-                CommandLine::loading();
-                let accounts = RpcCalls::get_accounts().await?;
+                Self::loading();
+                let accounts = self.provider.get_accounts().await?;
                 println!("{:#?}", accounts);
                 Ok(())
             },
             Option::GetGasPrice => {
                 println!("Get Gas price...");
-                CommandLine::loading();
-                let gas_price = RpcCalls::gas_price().await?;
+                Self::loading();
+                let gas_price = self.provider.gas_price().await?;
                 let gas_in_wei = gas_price.to_decimals();
-                CommandLine::display_label_and_value("Wei", gas_in_wei.to_string().as_str());
+                Self::display_label_and_value("Wei", gas_in_wei.to_string().as_str());
                 Ok(())
             },
             Option::GetBlockNumber => {
                 println!("Get latest block number...");
-                CommandLine::loading();
-                let block_number = RpcCalls::block_number().await?;
-                CommandLine::display_label_and_value("block", block_number.to_decimals().to_string().as_str());
+                Self::loading();
+                let block_number = self.provider.block_number().await?;
+                Self::display_label_and_value("block", block_number.to_decimals().to_string().as_str());
                 Ok(())
             },
             Option::GetTxCount => {
                 println!("Get tx counts...");
-                let addr = CommandLine::user_input("Address: ");
-                CommandLine::loading();
-                let tx_count = RpcCalls::get_tx_count(addr.as_str(), None).await?;
-                CommandLine::display_label_and_value("Transaction", tx_count.to_decimals().to_string().as_str());
+                let addr = Self::user_input("Address: ");
+                Self::loading();
+                let tx_count = self.provider.get_tx_count(addr.as_str(), None).await?;
+                Self::display_label_and_value("Transaction", tx_count.to_decimals().to_string().as_str());
                 Ok(())
             },
             Option::GetBlockTxCount => {
                 println!("Get tx counts...");
-                let number = match CommandLine::user_input("Block Number: ").parse::<i32>() {
+                let number = match Self::user_input("Block Number: ").parse::<i32>() {
                     Ok(val) => match val {
                             0 => None,
                             _ => Some(val)
@@ -143,14 +284,14 @@ impl CommandLine {
                         return Ok(())
                     }
                 };
-                CommandLine::loading();
-                let tx_count = RpcCalls::block_tx_count(None, number).await?;
-                CommandLine::display_label_and_value("Transaction", tx_count.to_decimals().to_string().as_str());
+                Self::loading();
+                let tx_count = self.provider.block_tx_count(None, number).await?;
+                Self::display_label_and_value("Transaction", tx_count.to_decimals().to_string().as_str());
                 Ok(())
             },
             Option::GetBlock => {
                 println!("Get block info...");
-                let number = match CommandLine::user_input("Block Number: ").parse::<i32>() {
+                let number = match Self::user_input("Block Number: ").parse::<i32>() {
                     Ok(val) => match val {
                             0 => None,
                             _ => Some(val)
@@ -161,17 +302,533 @@ impl CommandLine {
                         return Ok(())
                     }
                 };
-                CommandLine::loading();
-                let block = RpcCalls::get_block(None, number).await?;
+                Self::loading();
+                let block = self.provider.get_block(None, number).await?;
                 println!("{}", block);
                 Ok(())
             },
             Option::GetTx => {
                 println!("Get Tx info...");
-                let tx_hash = CommandLine::user_input("Tx Hash: ");
-                CommandLine::loading();
-                let tx = RpcCalls::get_tx(tx_hash.as_str()).await?;
+                let tx_hash = Self::user_input("Tx Hash: ");
+                Self::loading();
+                let tx = self.provider.get_tx(tx_hash.as_str()).await?;
                 println!("{}", tx);
+                match tx.check() {
+                    Ok(check) => println!("{}", check),
+                    Err(e) => println!("{}", format!("Can't re-encode the transaction: {}", e).red()),
+                }
+                let receipt = match tx.is_pending() {
+                    true => None,
+                    false => self.provider.get_tx_receipt(tx.hash.as_str()).await.unwrap_or(None),
+                };
+                if let Some(receipt) = &receipt {
+                    match TxCost::fetch(&self.provider, &tx, receipt).await {
+                        Ok(cost) => println!("{}", cost),
+                        Err(e) => println!("{}", format!("Can't break down the cost: {}", e).red()),
+                    }
+                }
+                if let Some(url) = ChainInfo::current().tx_url(tx.hash.as_str()) {
+                    Self::display_label_and_value("explorer", url.as_str());
+                }
+                self.display_calldata(&tx).await;
+                if receipt.is_some_and(|receipt| receipt.status == Some(0)) {
+                    self.display_failure(&tx).await;
+                }
+                Ok(())
+            },
+            Option::ImportSignatures => {
+                println!("Import 4byte signatures...");
+                let path = Self::user_input("Dump file: ");
+                let mut db = SignatureDb::load();
+                match db.import(path.as_str()) {
+                    Ok(added) => {
+                        Self::display_label_and_value("new signatures", added.to_string().as_str());
+                        Self::display_label_and_value("signatures", db.len().to_string().as_str());
+                    }
+                    Err(e) => println!("Failed to import signatures: {}", e),
+                }
+                Ok(())
+            },
+            Option::ScanAddresses => {
+                println!("Scan address activity...");
+                let addresses = Self::user_input("Addresses (comma separated): ");
+                let concurrency = Self::user_input("Concurrency (default 8): ")
+                    .parse::<usize>()
+                    .unwrap_or(8);
+                let mut scanner = Scanner::new(
+                    addresses.split(',').map(|addr| addr.to_string()).collect(),
+                    concurrency,
+                );
+                let internal = Self::user_input("Include internal transfers (needs tracing)?(Y/N): ");
+                if let "Y" | "y" = internal.as_str() {
+                    scanner = scanner.with_internal_transfers();
+                }
+
+                let mut from = match Self::user_input("From block: ").parse::<i32>() {
+                    Ok(val) => val,
+                    Err(e) => {
+                        println!("Invalid block number: {:#?}", e.kind());
+                        return Ok(())
+                    }
+                };
+                if let Some(last) = scanner.checkpoint(&self.provider).await? {
+                    let prompt = format!("Resume from block {}?(Y/N): ", last + 1);
+                    if let "Y" | "y" = Self::user_input(prompt.as_str()).as_str() {
+                        from = last + 1;
+                    }
+                }
+
+                let to = match Self::user_input("To block (empty for latest): ").parse::<i32>() {
+                    Ok(val) => val,
+                    Err(_) => self.provider.block_number().await?.to_decimals() as i32,
+                };
+
+                Self::loading();
+                let report = match scanner.scan(&self.provider, from, to).await {
+                    Ok(report) => report,
+                    // Most nodes don't serve traces, any other failure is reported as usual.
+                    Err(e) if internal.eq_ignore_ascii_case("y") && e.is_unsupported_method() => {
+                        println!("{}", format!("Scan stopped, the node doesn't serve traces: {}", e).red());
+                        return Ok(())
+                    }
+                    Err(e) => return Err(e),
+                };
+                for item in &report.matches {
+                    println!(">>>>>>>>>>>>>>>>>>>>>>>>><<<<<<<<<<<<<<<<<<<<<<<<<<<<<<");
+                    println!("{}", item);
+                }
+                Self::display_label_and_value("matches", report.matches.len().to_string().as_str());
+                if let Some(last) = report.last_scanned {
+                    let range = format!("{}..={}", report.from_block, last);
+                    Self::display_label_and_value("blocks scanned", range.as_str());
+                }
+                Ok(())
+            },
+            Option::IndexBlocks => {
+                println!("Index blocks...");
+                let mut indexer = match Indexer::open(Indexer::path()) {
+                    Ok(indexer) => indexer,
+                    Err(e) => {
+                        println!("Failed to open index: {}", e);
+                        return Ok(())
+                    }
+                };
+                if let Ok(Some(head)) = indexer.head() {
+                    Self::display_label_and_value("indexed head", head.to_string().as_str());
+                }
+                let from = Self::user_input("From block (empty to continue from head): ")
+                    .parse::<i32>()
+                    .ok();
+                let to = match Self::user_input("To block (empty for latest): ").parse::<i32>() {
+                    Ok(val) => val,
+                    Err(_) => self.provider.block_number().await?.to_decimals() as i32,
+                };
+
+                Self::loading();
+                match indexer.sync(&self.provider, from, to).await {
+                    Ok(report) => {
+                        Self::display_label_and_value("blocks indexed", report.indexed.to_string().as_str());
+                        Self::display_label_and_value("blocks rolled back", report.rolled_back.to_string().as_str());
+                        if let Some(head) = report.head {
+                            Self::display_label_and_value("indexed head", head.to_string().as_str());
+                        }
+                    }
+                    Err(IndexError::Rpc(e)) => return Err(e),
+                    Err(e) => println!("Indexing failed: {}", e),
+                }
+                Ok(())
+            },
+            Option::QueryIndex => {
+                println!("Query local index...");
+                let indexer = match Indexer::open(Indexer::path()) {
+                    Ok(indexer) => indexer,
+                    Err(e) => {
+                        println!("Failed to open index: {}", e);
+                        return Ok(())
+                    }
+                };
+                let txs = match Self::user_input("Query by (address/block/time): ").as_str() {
+                    "address" => {
+                        let addr = Self::user_input("Address: ");
+                        indexer.txs_by_address(addr.as_str())
+                    }
+                    "block" => match Self::user_input("Block Number: ").parse::<i32>() {
+                        Ok(number) => indexer.txs_by_block(number),
+                        Err(e) => {
+                            println!("Invalid block number: {:#?}", e.kind());
+                            return Ok(())
+                        }
+                    },
+                    "time" => {
+                        let from = Self::user_input("From (unix seconds): ").parse::<i64>();
+                        let to = Self::user_input("To (unix seconds): ").parse::<i64>();
+                        match (from, to) {
+                            (Ok(from), Ok(to)) => indexer.txs_by_time(from, to),
+                            _ => {
+                                println!("Invalid timestamp");
+                                return Ok(())
+                            }
+                        }
+                    }
+                    _ => {
+                        println!("Invalid input");
+                        return Ok(())
+                    }
+                };
+
+                match txs {
+                    Ok(txs) => {
+                        for tx in &txs {
+                            println!(">>>>>>>>>>>>>>>>>>>>>>>>><<<<<<<<<<<<<<<<<<<<<<<<<<<<<<");
+                            println!("{}", tx);
+                        }
+                        Self::display_label_and_value("transactions", txs.len().to_string().as_str());
+                    }
+                    Err(e) => println!("Query failed: {}", e),
+                }
+                Ok(())
+            },
+            Option::Cache => {
+                let cache = match Cache::global() {
+                    Some(cache) => cache,
+                    None => {
+                        println!("Cache is disabled (BLOCKCHAIN_CLI_CACHE=off)");
+                        return Ok(())
+                    }
+                };
+                match Self::user_input("Action (stats/clear): ").as_str() {
+                    "stats" => {
+                        let stats = cache.stats();
+                        Self::display_label_and_value("location", stats.dir.display().to_string().as_str());
+                        Self::display_label_and_value("entries", stats.entries.to_string().as_str());
+                        Self::display_label_and_value("bytes used", stats.size.to_string().as_str());
+                        Self::display_label_and_value("bytes limit", stats.max_size.to_string().as_str());
+                        Self::display_label_and_value("hits this session", stats.hits.to_string().as_str());
+                        Self::display_label_and_value("misses this session", stats.misses.to_string().as_str());
+                    }
+                    "clear" => {
+                        let removed = cache.clear();
+                        Self::display_label_and_value("entries removed", removed.to_string().as_str());
+                    }
+                    _ => println!("Invalid input"),
+                }
+                Ok(())
+            },
+            Option::NodeInfo => {
+                println!("Get node info...");
+                Self::loading();
+                let info = NodeInfo::fetch(&self.provider).await?;
+                println!("{}", info);
+                Ok(())
+            },
+            Option::Disagreements => {
+                let disagreements = providers::disagreements();
+                for disagreement in &disagreements {
+                    println!(">>>>>>>>>>>>>>>>>>>>>>>>><<<<<<<<<<<<<<<<<<<<<<<<<<<<<<");
+                    println!("{}", disagreement);
+                }
+                Self::display_label_and_value("disagreements", disagreements.len().to_string().as_str());
+                Ok(())
+            },
+            Option::RateLimits => {
+                let stats = ratelimit::stats();
+                if stats.is_empty() {
+                    println!("No request sent yet");
+                }
+                for (url, stats) in stats {
+                    print!("{}", format_label_and_value(url.as_str(), &stats.to_string()));
+                }
+                Ok(())
+            },
+            Option::Account => {
+                println!("Inspect account.");
+                let addr = Self::user_input("Address: ");
+                Self::loading();
+                let info = AccountInfo::fetch(&self.provider, addr.as_str(), None).await?;
+                println!("{}", info);
+                if info.kind == AccountKind::Contract {
+                    match proxy::resolve(&self.provider, addr.as_str(), None).await {
+                        Ok(hops) if !hops.is_empty() => {
+                            for hop in &hops {
+                                print!("{}", format_label_and_value("Proxy", hop.to_string().as_str()));
+                            }
+                            let implementation = proxy::implementation(addr.as_str(), &hops);
+                            println!("Implementation:");
+                            match AccountInfo::fetch(&self.provider, implementation.as_str(), None).await {
+                                Ok(info) => println!("{}", info),
+                                Err(e) => println!("Could not fetch the implementation: {}", e),
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(e) => println!("Could not check for a proxy: {}", e),
+                    }
+                }
+
+                loop {
+                    let slot = Self::user_input("Storage slot (empty to finish): ");
+                    if slot.is_empty() {
+                        break;
+                    }
+                    let keys = Self::user_input("Mapping keys (comma separated, empty for none): ");
+                    let index = Self::user_input("Array index (empty for none): ");
+                    let slot = match Self::storage_slot(&slot, &keys, &index) {
+                        Ok(slot) => encode_hex(&slot),
+                        Err(e) => {
+                            println!("Invalid slot: {}", e);
+                            continue;
+                        }
+                    };
+                    let value = self.provider.get_storage_at(addr.as_str(), slot.as_str(), None).await?;
+                    print!("{}", format_label_and_value("Slot", slot.as_str()));
+                    print!("{}", format_label_and_value("Value", value.result().as_str()));
+                    Self::display_label_and_value("as uint", value.to_decimals().to_string().as_str());
+                }
+                Ok(())
+            },
+            Option::VerifyProof => {
+                println!("Verify account with eth_getProof.");
+                let addr = Self::user_input("Address: ");
+                let number = match Self::user_input("Block Number (empty for latest): ").as_str() {
+                    "" => None,
+                    val => match val.parse::<i32>() {
+                        Ok(val) => Some(val),
+                        Err(e) => {
+                            println!("Invalid block number: {:#?}", e.kind());
+                            return Ok(())
+                        }
+                    },
+                };
+                let slots = Self::user_input("Storage slots (comma separated, empty for none): ");
+                let slots = match slots
+                    .split(',')
+                    .filter(|slot| !slot.trim().is_empty())
+                    .map(|slot| account::parse_word(slot).map(|slot| encode_hex(&slot)))
+                    .collect::<Result<Vec<String>, String>>()
+                {
+                    Ok(slots) => slots,
+                    Err(e) => {
+                        println!("Invalid slot: {}", e);
+                        return Ok(())
+                    }
+                };
+                Self::loading();
+
+                // Pin the block first so the proof and the state root come from the same state.
+                let header = self.provider.get_header(None, number).await?;
+                let number = hex_to_decimals(&header.number, true) as i32;
+                let proof = self.provider.get_proof(addr.as_str(), &slots, Some(number)).await?;
+                let status = match proof.verify(&header.state_root) {
+                    Ok(()) => {
+                        println!("{}", format!("Proof verified against the state root of block {}", number).bold().green());
+                        "verified"
+                    }
+                    Err(e) => {
+                        println!("{}", format!("Proof verification FAILED: {}", e).bold().red());
+                        "unverified"
+                    }
+                };
+
+                let chain = ChainInfo::current();
+                let balance = format_units(hex_to_decimals(&proof.balance, true), chain.decimals);
+                let lines = [
+                    ("Balance", format!("{} {} ({})", balance, chain.symbol, status)),
+                    ("Nonce", format!("{} ({})", hex_to_decimals(&proof.nonce, true), status)),
+                    ("Code Hash", proof.code_hash.clone()),
+                    ("Storage Hash", proof.storage_hash.clone()),
+                ];
+                for (label, value) in lines {
+                    print!("{}", format_label_and_value(label, value.as_str()));
+                }
+                for slot in &proof.storage_proof {
+                    let label = format!("Slot {}", slot.key);
+                    print!("{}", format_label_and_value(label.as_str(), format!("{} ({})", slot.value, status).as_str()));
+                }
+                Ok(())
+            },
+            Option::VerifyHeaders => {
+                println!("Verify block headers.");
+                let from = match Self::user_input("From block (empty for latest): ").as_str() {
+                    "" => self.provider.block_number().await?.to_decimals() as i32,
+                    val => match val.parse::<i32>() {
+                        Ok(val) => val,
+                        Err(e) => {
+                            println!("Invalid block number: {:#?}", e.kind());
+                            return Ok(())
+                        }
+                    },
+                };
+                let to = match Self::user_input("To block (empty for the same): ").parse::<i32>() {
+                    Ok(val) => val,
+                    Err(_) => from,
+                };
+                if to < from {
+                    println!("Invalid range {}..={}", from, to);
+                    return Ok(())
+                }
+                Self::loading();
+
+                let mut headers = vec![];
+                for number in from..=to {
+                    headers.push(self.provider.get_header(None, Some(number)).await?);
+                }
+                let issues = header::verify_chain(&headers);
+                for item in &headers {
+                    let status = match issues.iter().any(|issue| issue.number() == item.number()) {
+                        true => "FAILED".red(),
+                        false => "ok".green(),
+                    };
+                    println!("Block {} ({}) {}: {}", item.number(), item.fork(), item.hash, status);
+                }
+                for issue in &issues {
+                    println!("{}", issue.to_string().bold().red());
+                }
+                Self::display_label_and_value("headers checked", headers.len().to_string().as_str());
+                Self::display_label_and_value("issues", issues.len().to_string().as_str());
+                Ok(())
+            },
+            Option::VerifyBody => {
+                println!("Verify block transactions and receipts.");
+                let number = match Self::user_input("Block Number (empty for latest): ").as_str() {
+                    "" => None,
+                    val => match val.parse::<i32>() {
+                        Ok(val) => Some(val),
+                        Err(e) => {
+                            println!("Invalid block number: {:#?}", e.kind());
+                            return Ok(())
+                        }
+                    },
+                };
+                Self::loading();
+                let report = body::verify_block(&self.provider, number).await?;
+                println!("{}", report);
+                match report.is_valid() {
+                    true => println!("{}", "Block data matches its header".bold().green()),
+                    false => println!("{}", "Block data does NOT match its header".bold().red()),
+                }
+                Ok(())
+            },
+            Option::TraceTx => {
+                println!("Trace transaction calls...");
+                let tx_hash = Self::user_input("Tx Hash: ");
+                Self::loading();
+                let tx = self.provider.get_tx(tx_hash.as_str()).await?;
+                if tx.is_pending() {
+                    println!("{}", "Pending transactions can't be traced".red());
+                    return Ok(())
+                }
+                let trace = match self.provider.trace_calls(tx.hash.as_str()).await {
+                    Ok(trace) => trace,
+                    Err(e) => {
+                        println!("{}", format!("Tracing failed: {}", e).red());
+                        return Ok(())
+                    }
+                };
+                print!("{}", trace.render(&SignatureDb::load()));
+                let frames = trace.frames();
+                let failed = frames.iter().filter(|(_, frame)| frame.failed()).count();
+                Self::display_label_and_value("calls", frames.len().to_string().as_str());
+                Self::display_label_and_value("failed calls", failed.to_string().as_str());
+                Self::save_trace(&trace);
+                Ok(())
+            },
+            Option::InternalTransfers => {
+                println!("Internal transfers...");
+                let target = Self::user_input("Tx hash or block number: ");
+                Self::loading();
+                let transfers = match target.parse::<i32>() {
+                    Ok(number) => {
+                        let block = self.provider.get_block(None, Some(number)).await?;
+                        trace::block_transfers(&self.provider, &block).await
+                    }
+                    Err(_) => trace::tx_transfers(&self.provider, target.as_str()).await,
+                };
+                let transfers = match transfers {
+                    Ok(transfers) => transfers,
+                    Err(e) => {
+                        println!("{}", format!("Tracing failed: {}", e).red());
+                        return Ok(())
+                    }
+                };
+                for transfer in &transfers {
+                    println!("{}", transfer);
+                }
+                let total: i128 = transfers.iter().map(|transfer| transfer.value).sum();
+                let chain = ChainInfo::current();
+                let total = format!("{} {}", format_units(total, chain.decimals), chain.symbol);
+                Self::display_label_and_value("internal transfers", transfers.len().to_string().as_str());
+                Self::display_label_and_value("moved internally", total.as_str());
+                Ok(())
+            },
+            Option::StateDiff => {
+                println!("Transaction state diff...");
+                let tx_hash = Self::user_input("Tx Hash: ");
+                Self::loading();
+                let tx = self.provider.get_tx(tx_hash.as_str()).await?;
+                if tx.is_pending() {
+                    println!("{}", "Pending transactions can't be traced".red());
+                    return Ok(())
+                }
+                let diff = match self.provider.trace_state_diff(tx.hash.as_str()).await {
+                    Ok(diff) => diff,
+                    Err(e) => {
+                        println!("{}", format!("Tracing failed: {}", e).red());
+                        return Ok(())
+                    }
+                };
+                let mut accounts = diff.accounts();
+
+                let path = Self::user_input("Storage layout JSON (empty to skip): ");
+                if !path.is_empty() {
+                    match StorageLayout::from_file(path.as_str()) {
+                        Ok(layout) => {
+                            let contract = match Self::user_input("Contract of the layout (empty for the tx recipient): ") {
+                                contract if contract.is_empty() => tx.to.clone().unwrap_or_default(),
+                                contract => contract,
+                            };
+                            let keys = StorageLayout::mapping_keys(&tx, &accounts);
+                            for account in accounts.iter_mut().filter(|account| account.address.eq_ignore_ascii_case(&contract)) {
+                                layout.label_slots(account, &keys);
+                            }
+                        }
+                        Err(e) => println!("Failed to load the storage layout: {}", e),
+                    }
+                }
+
+                for account in &accounts {
+                    println!(">>>>>>>>>>>>>>>>>>>>>>>>><<<<<<<<<<<<<<<<<<<<<<<<<<<<<<");
+                    print!("{}", account);
+                }
+                let slots: usize = accounts.iter().map(|account| account.storage.len()).sum();
+                Self::display_label_and_value("accounts changed", accounts.len().to_string().as_str());
+                Self::display_label_and_value("storage slots changed", slots.to_string().as_str());
+                Ok(())
+            },
+            Option::GasProfile => {
+                println!("Gas profile of a transaction...");
+                let tx_hash = Self::user_input("Tx Hash: ");
+                Self::loading();
+                let tx = self.provider.get_tx(tx_hash.as_str()).await?;
+                if tx.is_pending() {
+                    println!("{}", "Pending transactions can't be traced".red());
+                    return Ok(())
+                }
+                let trace = match self.provider.trace_struct_logs(tx.hash.as_str()).await {
+                    Ok(trace) => trace,
+                    Err(e) => {
+                        println!("{}", format!("Tracing failed: {}", e).red());
+                        return Ok(())
+                    }
+                };
+                let profile = GasProfile::new(&trace, tx.to.as_deref());
+                print!("{}", profile);
+
+                let path = Self::user_input("Save folded stacks for flamegraph to (empty to skip): ");
+                if !path.is_empty() {
+                    match std::fs::write(&path, profile.folded()) {
+                        Ok(()) => println!("Folded stacks saved to {}", path),
+                        Err(e) => println!("{}", format!("Failed to save the folded stacks: {}", e).red()),
+                    }
+                }
                 Ok(())
             },
             _ => {
@@ -181,8 +838,107 @@ impl CommandLine {
         }
     }
 
+    async fn display_calldata(&self, tx: &Transaction) {
+        let selector = match tx.selector() {
+            Some(val) => val,
+            None => return,
+        };
+
+        // Calls to a proxy are decoded against the logic contract behind it.
+        if let Some(to) = &tx.to {
+            match proxy::resolve(&self.provider, to.as_str(), None).await {
+                Ok(hops) if !hops.is_empty() => {
+                    for hop in &hops {
+                        print!("{}", format_label_and_value("Proxy", hop.to_string().as_str()));
+                    }
+                    println!("Decoding against implementation {}", proxy::implementation(to, &hops));
+                }
+                Ok(_) => {}
+                Err(e) => println!("Could not check for a proxy: {}", e),
+            }
+        }
+
+        let abi_path = Self::user_input("ABI file (leave empty to use signature db): ");
+        let candidates = if abi_path.is_empty() {
+            SignatureDb::load().candidates(&selector)
+        } else {
+            match Abi::from_file(&abi_path) {
+                Ok(abi) => abi.function_by_selector(&selector).into_iter().cloned().collect(),
+                Err(e) => {
+                    println!("Failed to load ABI: {}", e);
+                    return;
+                }
+            }
+        };
+
+        match DecodedCall::decode(&tx.input, &candidates) {
+            Some(call) => print!("{}", call),
+            None => println!("Unknown function selector {}", selector),
+        }
+    }
+
+    /// Replays a failed transaction to find out why it reverted.
+    async fn display_failure(&self, tx: &Transaction) {
+        println!("{}", "Transaction failed, replaying it on its parent block...".red());
+        let revert = match revert::replay(&self.provider, tx).await {
+            Ok(Some(revert)) => revert,
+            Ok(None) => {
+                println!("The replay succeeded, an earlier transaction of the block made it fail");
+                return;
+            }
+            Err(e) => {
+                println!("{}", format!("Replay failed: {}", e).red());
+                return;
+            }
+        };
+
+        let mut reason = RevertReason::decode(&revert, &[]);
+        if let RevertReason::Unknown(_) = reason {
+            let selector = RevertReason::selector(&revert).unwrap_or_default();
+            let abi_path = Self::user_input("ABI with custom errors (leave empty to use signature db): ");
+            let candidates = if abi_path.is_empty() {
+                SignatureDb::load().candidates(&selector)
+            } else {
+                match Abi::from_file(&abi_path) {
+                    Ok(abi) => abi.error_by_selector(&selector).into_iter().cloned().collect(),
+                    Err(e) => {
+                        println!("Failed to load ABI: {}", e);
+                        vec![]
+                    }
+                }
+            };
+            reason = RevertReason::decode(&revert, &candidates);
+        }
+        print!("{}", reason);
+    }
+
+    fn save_trace(trace: &CallFrame) {
+        let path = Self::user_input("Save the trace as JSON to (empty to skip): ");
+        if path.is_empty() {
+            return;
+        }
+        let json = serde_json::to_string_pretty(trace).expect("call frames serialize");
+        match std::fs::write(&path, json) {
+            Ok(()) => println!("Trace saved to {}", path),
+            Err(e) => println!("{}", format!("Failed to save the trace: {}", e).red()),
+        }
+    }
+
+    /// Resolves the slot of `base[keys...][index]`, keys being applied in order.
+    fn storage_slot(base: &str, keys: &str, index: &str) -> Result<[u8; 32], String> {
+        let mut slot = account::parse_word(base)?;
+        for key in keys.split(',').filter(|key| !key.trim().is_empty()) {
+            slot = account::mapping_slot(&slot, key)?;
+        }
+        if !index.is_empty() {
+            let index = index.parse::<u64>().map_err(|e| e.to_string())?;
+            slot = account::array_slot(&slot, index, 1);
+        }
+        Ok(slot)
+    }
+
     fn loading() {
-        println!("{}", format!("Fetching...").bold().green())
+        println!("{}", "Fetching...".bold().green())
     }
 
     fn display_label_and_value(label: &str, value: &str) {
@@ -190,6 +946,14 @@ impl CommandLine {
     }
 }
 
+/// Decodes a raw signed transaction, without any RPC call.
+pub fn decode_tx(raw: &str) {
+    match decode_hex(raw.trim()).and_then(|encoded| TransactionHex::decode(&encoded)).and_then(|tx| tx.try_parse()) {
+        Ok(tx) => println!("{}", tx),
+        Err(e) => println!("{}", format!("Can't decode the transaction: {}", e).red()),
+    }
+}
+
 // addr1 = 0xf1a9e8f520b3427b6326356731a5cb4389337516
 // addr2 = 0x4d684f86ed2084484c6547975533151128b0c8bd
 // Block number 12710481
\ No newline at end of file
@@ -0,0 +1,151 @@
+use crate::ratelimit::RateLimit;
+use crate::utils::data_dir;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, env, fs, path::PathBuf, sync::OnceLock};
+
+const DEFAULT_RPC: &str = "https://rpc.ankr.com/eth_ropsten";
+const DEFAULT_PROFILE: &str = "default";
+const CONFIG_FILE: &str = "config.json";
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Strategy {
+    /// Use the first endpoint, moving to the next one on error.
+    #[default]
+    Failover,
+    /// Send to every endpoint and keep the first successful response.
+    Fastest,
+    /// Reads must be answered identically by `quorum` endpoints.
+    Quorum,
+}
+
+impl Strategy {
+    pub fn value(&self) -> &'static str {
+        match self {
+            Strategy::Failover => "failover",
+            Strategy::Fastest => "fastest",
+            Strategy::Quorum => "quorum",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default)]
+    pub rpc: String,
+    /// Additional endpoints, tried after `rpc` according to `strategy`.
+    #[serde(default)]
+    pub endpoints: Vec<String>,
+    #[serde(default)]
+    pub strategy: Strategy,
+    /// Number of endpoints that must agree with the quorum strategy, defaults to a majority.
+    pub quorum: Option<usize>,
+    /// Chain id the endpoint is expected to serve, checked against the node at startup.
+    #[serde(rename = "chainId")]
+    pub chain_id: Option<u64>,
+    /// Rate limit applied to every endpoint without an entry in `rate_limits`.
+    #[serde(rename = "rateLimit")]
+    pub rate_limit: Option<RateLimit>,
+    #[serde(rename = "rateLimits", default)]
+    pub rate_limits: HashMap<String, RateLimit>,
+}
+
+impl Profile {
+    pub fn endpoints(&self) -> Vec<String> {
+        let mut endpoints = vec![];
+        for url in std::iter::once(&self.rpc).chain(self.endpoints.iter()) {
+            if !url.is_empty() && !endpoints.contains(url) {
+                endpoints.push(url.clone());
+            }
+        }
+        endpoints
+    }
+
+    pub fn quorum(&self) -> usize {
+        let total = self.endpoints().len();
+        self.quorum.unwrap_or(total / 2 + 1).clamp(1, total.max(1))
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ConfigFile {
+    default: Option<String>,
+    #[serde(default)]
+    profiles: HashMap<String, Profile>,
+}
+
+/// Active configuration, read once from `config.json` in the data dir.
+///
+/// The profile is picked with `BLOCKCHAIN_CLI_PROFILE` (or the file's `default`),
+/// and `BLOCKCHAIN_CLI_RPC` (a comma separated list) overrides its endpoints.
+#[derive(Debug)]
+pub struct Config {
+    pub profile_name: String,
+    pub profile: Profile,
+}
+
+impl Config {
+    pub fn global() -> &'static Config {
+        CONFIG.get_or_init(Config::load)
+    }
+
+    pub fn path() -> PathBuf {
+        data_dir().join(CONFIG_FILE)
+    }
+
+    fn load() -> Config {
+        let file: ConfigFile = match fs::read_to_string(Config::path()) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                println!(
+                    "Ignoring invalid config {}: {}",
+                    Config::path().display(),
+                    e
+                );
+                ConfigFile::default()
+            }),
+            Err(_) => ConfigFile::default(),
+        };
+
+        let profile_name = env::var("BLOCKCHAIN_CLI_PROFILE")
+            .ok()
+            .or(file.default.clone())
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+
+        let mut profile = match file.profiles.get(&profile_name) {
+            Some(profile) => profile.clone(),
+            None => {
+                if profile_name != DEFAULT_PROFILE {
+                    println!(
+                        "Unknown profile {}, using the default endpoint",
+                        profile_name
+                    );
+                }
+                Profile {
+                    rpc: DEFAULT_RPC.to_string(),
+                    endpoints: vec![],
+                    strategy: Strategy::default(),
+                    quorum: None,
+                    chain_id: None,
+                    rate_limit: None,
+                    rate_limits: HashMap::new(),
+                }
+            }
+        };
+
+        if let Ok(rpc) = env::var("BLOCKCHAIN_CLI_RPC") {
+            let mut urls = rpc.split(',').map(|url| url.trim().to_string());
+            profile.rpc = urls.next().unwrap_or_default();
+            profile.endpoints = urls.collect();
+        }
+        if profile.endpoints().is_empty() {
+            profile.rpc = DEFAULT_RPC.to_string();
+        }
+
+        Config {
+            profile_name,
+            profile,
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::chains::ChainInfo;
+use crate::providers::{Provider, ProviderError};
+use crate::types::{Receipt, Transaction};
+use crate::utils::{format_label_and_value, format_units, hex_to_decimals};
+use std::fmt;
+
+/// Decimals of gwei, the unit gas prices are quoted in.
+const GWEI: u32 = 9;
+
+/// What a mined transaction paid, from its receipt and the base fee of its block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxCost {
+    pub gas_limit: i128,
+    pub gas_used: i128,
+    pub effective_gas_price: i128,
+    /// `None` before London, when the whole fee went to the miner.
+    pub base_fee: Option<i128>,
+    pub blob_gas_used: Option<i128>,
+    pub blob_gas_price: Option<i128>,
+    pub l1_fee: Option<i128>,
+    pub value: i128,
+}
+
+impl TxCost {
+    pub fn new(tx: &Transaction, receipt: &Receipt, base_fee: Option<i128>) -> TxCost {
+        // Receipts from before London have no effective price, the gas price is what was paid.
+        let effective_gas_price = receipt.effective_gas_price.or(tx.gas_price).unwrap_or(0);
+
+        TxCost {
+            gas_limit: tx.gas,
+            gas_used: receipt.gas_used,
+            effective_gas_price,
+            base_fee,
+            blob_gas_used: receipt.blob_gas_used,
+            blob_gas_price: receipt.blob_gas_price,
+            l1_fee: receipt.l1_fee,
+            value: tx.value,
+        }
+    }
+
+    /// Looks up the base fee of the block `tx` was mined in.
+    pub async fn fetch<P: Provider>(
+        provider: &P,
+        tx: &Transaction,
+        receipt: &Receipt,
+    ) -> Result<TxCost, ProviderError> {
+        let header = provider.get_header(None, tx.block_number).await?;
+        let base_fee = header
+            .base_fee_per_gas
+            .as_ref()
+            .map(|fee| hex_to_decimals(fee, true));
+
+        Ok(TxCost::new(tx, receipt, base_fee))
+    }
+
+    pub fn gas_usage(&self) -> f64 {
+        match self.gas_limit {
+            0 => 0.0,
+            limit => self.gas_used as f64 * 100.0 / limit as f64,
+        }
+    }
+
+    /// Part of the gas price above the base fee, paid to the fee recipient.
+    pub fn priority_fee_per_gas(&self) -> i128 {
+        (self.effective_gas_price - self.base_fee.unwrap_or(0)).max(0)
+    }
+
+    pub fn burnt(&self) -> i128 {
+        self.base_fee.unwrap_or(0).min(self.effective_gas_price) * self.gas_used
+    }
+
+    pub fn tip(&self) -> i128 {
+        self.priority_fee_per_gas() * self.gas_used
+    }
+
+    /// Blob gas is priced separately and burnt entirely.
+    pub fn blob_fee(&self) -> Option<i128> {
+        match (self.blob_gas_used, self.blob_gas_price) {
+            (Some(used), Some(price)) => Some(used * price),
+            _ => None,
+        }
+    }
+
+    /// Everything the sender paid besides the value sent.
+    pub fn total_fee(&self) -> i128 {
+        self.gas_used * self.effective_gas_price
+            + self.blob_fee().unwrap_or(0)
+            + self.l1_fee.unwrap_or(0)
+    }
+
+    pub fn total_cost(&self) -> i128 {
+        self.total_fee() + self.value
+    }
+}
+
+impl fmt::Display for TxCost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chain = ChainInfo::current();
+        let native = |wei: i128| format!("{} {}", format_units(wei, chain.decimals), chain.symbol);
+        let gwei = |wei: i128| format!("{} gwei", format_units(wei, GWEI));
+
+        let mut lines = vec![
+            (
+                "Gas Used",
+                format!(
+                    "{} of {} ({:.1}%)",
+                    self.gas_used,
+                    self.gas_limit,
+                    self.gas_usage()
+                ),
+            ),
+            ("Effective Gas Price", gwei(self.effective_gas_price)),
+        ];
+        if let Some(base_fee) = self.base_fee {
+            lines.push((
+                "Base Fee",
+                format!("{}, {} burnt", gwei(base_fee), native(self.burnt())),
+            ));
+        }
+        lines.push((
+            "Priority Tip",
+            format!(
+                "{}, {} to the fee recipient",
+                gwei(self.priority_fee_per_gas()),
+                native(self.tip())
+            ),
+        ));
+        if let (Some(fee), Some(used), Some(price)) =
+            (self.blob_fee(), self.blob_gas_used, self.blob_gas_price)
+        {
+            lines.push((
+                "Blob Fee",
+                format!("{} blob gas at {} wei, {} burnt", used, price, native(fee)),
+            ));
+        }
+        if let Some(l1_fee) = self.l1_fee {
+            lines.push(("L1 Data Fee", native(l1_fee)));
+        }
+        lines.push(("Total Fee", native(self.total_fee())));
+        lines.push((
+            "Total Cost",
+            format!("{} (fee and value)", native(self.total_cost())),
+        ));
+
+        for (label, value) in lines {
+            write!(f, "{}", format_label_and_value(label, &value))?;
+        }
+
+        write!(f, "")
+    }
+}
@@ -0,0 +1,253 @@
+use crate::rlp::Rlp;
+use crate::utils::{decode_hex, encode_hex, hex_to_decimals, keccak256};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Header fields of a block as returned by `eth_getBlockBy*`, enough to recompute its hash.
+///
+/// Fields added by later forks are optional, their presence decides what gets encoded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Header {
+    pub hash: String,
+    pub parent_hash: String,
+    pub sha3_uncles: String,
+    pub miner: String,
+    pub state_root: String,
+    pub transactions_root: String,
+    pub receipts_root: String,
+    pub logs_bloom: String,
+    pub difficulty: String,
+    pub number: String,
+    pub gas_limit: String,
+    pub gas_used: String,
+    pub timestamp: String,
+    pub extra_data: String,
+    pub mix_hash: String,
+    pub nonce: String,
+    /// London
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_fee_per_gas: Option<String>,
+    /// Shanghai
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub withdrawals_root: Option<String>,
+    /// Cancun
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blob_gas_used: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub excess_blob_gas: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_beacon_block_root: Option<String>,
+    /// Prague
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fork {
+    Frontier,
+    London,
+    Shanghai,
+    Cancun,
+    Prague,
+}
+
+impl fmt::Display for Fork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Fork::Frontier => "pre-London",
+            Fork::London => "London",
+            Fork::Shanghai => "Shanghai",
+            Fork::Cancun => "Cancun",
+            Fork::Prague => "Prague",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Something wrong with a header, or with how it links to the previous one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeaderIssue {
+    Malformed {
+        number: i128,
+        message: String,
+    },
+    HashMismatch {
+        number: i128,
+        reported: String,
+        computed: String,
+    },
+    BrokenLink {
+        number: i128,
+        parent_hash: String,
+        previous_hash: String,
+    },
+}
+
+impl HeaderIssue {
+    /// Block the issue was found in.
+    pub fn number(&self) -> i128 {
+        match self {
+            HeaderIssue::Malformed { number, .. }
+            | HeaderIssue::HashMismatch { number, .. }
+            | HeaderIssue::BrokenLink { number, .. } => *number,
+        }
+    }
+}
+
+impl fmt::Display for HeaderIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeaderIssue::Malformed { number, message } => {
+                write!(f, "Block {}: malformed header, {}", number, message)
+            }
+            HeaderIssue::HashMismatch {
+                number,
+                reported,
+                computed,
+            } => write!(
+                f,
+                "Block {}: reported hash {} but the header hashes to {}",
+                number, reported, computed
+            ),
+            HeaderIssue::BrokenLink {
+                number,
+                parent_hash,
+                previous_hash,
+            } => write!(
+                f,
+                "Block {}: parent hash {} doesn't match block {} hash {}",
+                number,
+                parent_hash,
+                number - 1,
+                previous_hash
+            ),
+        }
+    }
+}
+
+impl Header {
+    pub fn number(&self) -> i128 {
+        hex_to_decimals(&self.number, true)
+    }
+
+    /// Latest fork whose header fields are present.
+    pub fn fork(&self) -> Fork {
+        if self.requests_hash.is_some() {
+            Fork::Prague
+        } else if self.parent_beacon_block_root.is_some() {
+            Fork::Cancun
+        } else if self.withdrawals_root.is_some() {
+            Fork::Shanghai
+        } else if self.base_fee_per_gas.is_some() {
+            Fork::London
+        } else {
+            Fork::Frontier
+        }
+    }
+
+    fn malformed(&self, message: String) -> HeaderIssue {
+        HeaderIssue::Malformed {
+            number: self.number(),
+            message,
+        }
+    }
+
+    fn bytes(&self, name: &str, value: &str) -> Result<Rlp, HeaderIssue> {
+        decode_hex(value)
+            .map(Rlp::Bytes)
+            .map_err(|e| self.malformed(format!("{} {}: {}", name, value, e)))
+    }
+
+    fn quantity(&self, name: &str, value: &str) -> Result<Rlp, HeaderIssue> {
+        decode_hex(value)
+            .map(|bytes| Rlp::uint(&bytes))
+            .map_err(|e| self.malformed(format!("{} {}: {}", name, value, e)))
+    }
+
+    /// RLP list of the header fields, in consensus order.
+    pub fn rlp(&self) -> Result<Rlp, HeaderIssue> {
+        let mut fields = vec![
+            self.bytes("parentHash", &self.parent_hash)?,
+            self.bytes("sha3Uncles", &self.sha3_uncles)?,
+            self.bytes("miner", &self.miner)?,
+            self.bytes("stateRoot", &self.state_root)?,
+            self.bytes("transactionsRoot", &self.transactions_root)?,
+            self.bytes("receiptsRoot", &self.receipts_root)?,
+            self.bytes("logsBloom", &self.logs_bloom)?,
+            self.quantity("difficulty", &self.difficulty)?,
+            self.quantity("number", &self.number)?,
+            self.quantity("gasLimit", &self.gas_limit)?,
+            self.quantity("gasUsed", &self.gas_used)?,
+            self.quantity("timestamp", &self.timestamp)?,
+            self.bytes("extraData", &self.extra_data)?,
+            self.bytes("mixHash", &self.mix_hash)?,
+            self.bytes("nonce", &self.nonce)?,
+        ];
+
+        // Each fork appends to the previous field set, a gap means the node left out a field.
+        let optional = [
+            ("baseFeePerGas", &self.base_fee_per_gas, true),
+            ("withdrawalsRoot", &self.withdrawals_root, false),
+            ("blobGasUsed", &self.blob_gas_used, true),
+            ("excessBlobGas", &self.excess_blob_gas, true),
+            (
+                "parentBeaconBlockRoot",
+                &self.parent_beacon_block_root,
+                false,
+            ),
+            ("requestsHash", &self.requests_hash, false),
+        ];
+        let mut missing: Option<&str> = None;
+        for (name, value, is_quantity) in optional {
+            match (value, missing) {
+                (None, _) => missing = missing.or(Some(name)),
+                (Some(_), Some(gap)) => {
+                    return Err(self.malformed(format!("{} without {}", name, gap)))
+                }
+                (Some(value), None) if is_quantity => fields.push(self.quantity(name, value)?),
+                (Some(value), None) => fields.push(self.bytes(name, value)?),
+            }
+        }
+
+        Ok(Rlp::List(fields))
+    }
+
+    pub fn compute_hash(&self) -> Result<[u8; 32], HeaderIssue> {
+        Ok(keccak256(&self.rlp()?.encode()))
+    }
+
+    /// Recomputes the hash and compares it with the one the node reported.
+    pub fn verify(&self) -> Result<(), HeaderIssue> {
+        let computed = encode_hex(&self.compute_hash()?);
+        match computed == self.hash.to_lowercase() {
+            true => Ok(()),
+            false => Err(HeaderIssue::HashMismatch {
+                number: self.number(),
+                reported: self.hash.clone(),
+                computed,
+            }),
+        }
+    }
+}
+
+/// Verifies every header and that each one points at the previous one.
+/// `headers` must be consecutive and in ascending order.
+pub fn verify_chain(headers: &[Header]) -> Vec<HeaderIssue> {
+    let mut issues = vec![];
+    for (idx, header) in headers.iter().enumerate() {
+        if let Err(issue) = header.verify() {
+            issues.push(issue);
+        }
+        if let Some(previous) = idx.checked_sub(1).map(|idx| &headers[idx]) {
+            if header.parent_hash.to_lowercase() != previous.hash.to_lowercase() {
+                issues.push(HeaderIssue::BrokenLink {
+                    number: header.number(),
+                    parent_hash: header.parent_hash.clone(),
+                    previous_hash: previous.hash.clone(),
+                });
+            }
+        }
+    }
+    issues
+}
@@ -0,0 +1,522 @@
+use crate::chains::ChainInfo;
+use crate::providers::{Provider, ProviderError};
+use crate::types::{Block, Receipt};
+use crate::utils::{data_dir, format_label_and_value, format_units, hex_to_decimals};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::{fmt, path::PathBuf};
+
+const DB_FILE: &str = "index.sqlite";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS blocks (
+    number INTEGER PRIMARY KEY,
+    hash TEXT NOT NULL UNIQUE,
+    parent_hash TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    miner TEXT NOT NULL,
+    gas_used TEXT NOT NULL,
+    gas_limit TEXT NOT NULL,
+    base_fee TEXT NOT NULL,
+    tx_count INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS blocks_timestamp ON blocks (timestamp);
+
+CREATE TABLE IF NOT EXISTS transactions (
+    hash TEXT PRIMARY KEY,
+    block_number INTEGER NOT NULL,
+    tx_index INTEGER NOT NULL,
+    tx_type INTEGER NOT NULL,
+    from_addr TEXT NOT NULL,
+    to_addr TEXT,
+    value TEXT NOT NULL,
+    gas TEXT NOT NULL,
+    gas_price TEXT,
+    nonce INTEGER NOT NULL,
+    input TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS transactions_block ON transactions (block_number);
+CREATE INDEX IF NOT EXISTS transactions_from ON transactions (from_addr);
+CREATE INDEX IF NOT EXISTS transactions_to ON transactions (to_addr);
+
+CREATE TABLE IF NOT EXISTS receipts (
+    tx_hash TEXT PRIMARY KEY,
+    block_number INTEGER NOT NULL,
+    status INTEGER,
+    gas_used TEXT NOT NULL,
+    cumulative_gas_used TEXT NOT NULL,
+    effective_gas_price TEXT,
+    contract_address TEXT
+);
+CREATE INDEX IF NOT EXISTS receipts_block ON receipts (block_number);
+
+CREATE TABLE IF NOT EXISTS logs (
+    tx_hash TEXT NOT NULL,
+    block_number INTEGER NOT NULL,
+    log_index INTEGER NOT NULL,
+    address TEXT NOT NULL,
+    topics TEXT NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (tx_hash, log_index)
+);
+CREATE INDEX IF NOT EXISTS logs_block ON logs (block_number);
+CREATE INDEX IF NOT EXISTS logs_address ON logs (address);
+";
+
+#[derive(Debug)]
+pub enum IndexError {
+    Rpc(ProviderError),
+    Db(rusqlite::Error),
+    /// The index was built from another chain.
+    WrongChain {
+        indexed: u64,
+        node: u64,
+    },
+    /// None of the indexed blocks down to `oldest` is on the canonical chain anymore.
+    DeepReorg {
+        oldest: i32,
+    },
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexError::Rpc(e) => write!(f, "{}", e),
+            IndexError::Db(e) => write!(f, "Database error: {}", e),
+            IndexError::WrongChain { indexed, node } => write!(
+                f,
+                "the index holds chain {} but the node serves chain {}",
+                indexed, node
+            ),
+            IndexError::DeepReorg { oldest } => write!(
+                f,
+                "reorg goes below the oldest indexed block {}, sync again from that block or earlier",
+                oldest
+            ),
+        }
+    }
+}
+
+impl From<ProviderError> for IndexError {
+    fn from(e: ProviderError) -> Self {
+        IndexError::Rpc(e)
+    }
+}
+
+impl From<rusqlite::Error> for IndexError {
+    fn from(e: rusqlite::Error) -> Self {
+        IndexError::Db(e)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub indexed: usize,
+    pub rolled_back: usize,
+    pub head: Option<i32>,
+}
+
+/// A transaction row as stored in the local index.
+#[derive(Debug)]
+pub struct IndexedTx {
+    pub hash: String,
+    pub block_number: i32,
+    pub timestamp: i64,
+    pub tx_index: i32,
+    pub from: String,
+    pub to: Option<String>,
+    pub value: i128,
+    pub gas: i128,
+    pub status: Option<u8>,
+    pub gas_used: Option<i128>,
+}
+
+impl IndexedTx {
+    fn from_row(row: &Row) -> rusqlite::Result<IndexedTx> {
+        let number = |idx: usize| -> rusqlite::Result<i128> {
+            Ok(row.get::<_, String>(idx)?.parse::<i128>().unwrap_or(0))
+        };
+
+        Ok(IndexedTx {
+            hash: row.get(0)?,
+            block_number: row.get(1)?,
+            timestamp: row.get(2)?,
+            tx_index: row.get(3)?,
+            from: row.get(4)?,
+            to: row.get(5)?,
+            value: number(6)?,
+            gas: number(7)?,
+            status: row.get(8)?,
+            gas_used: row
+                .get::<_, Option<String>>(9)?
+                .and_then(|val| val.parse::<i128>().ok()),
+        })
+    }
+}
+
+impl fmt::Display for IndexedTx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let or_unknown = |val: Option<String>| val.unwrap_or_else(|| "??".to_string());
+        let status = match self.status {
+            Some(1) => "Success".to_string(),
+            Some(_) => "Failed".to_string(),
+            None => "??".to_string(),
+        };
+
+        let value_label = format!("Value ({})", ChainInfo::current().symbol);
+        let lines = [
+            ("Tx", self.hash.clone()),
+            ("Block", self.block_number.to_string()),
+            ("Timestamp", self.timestamp.to_string()),
+            ("Tx Idx", self.tx_index.to_string()),
+            ("From", self.from.clone()),
+            ("To", or_unknown(self.to.clone())),
+            (
+                value_label.as_str(),
+                format_units(self.value, ChainInfo::current().decimals),
+            ),
+            ("Gas", self.gas.to_string()),
+            (
+                "Gas Used",
+                or_unknown(self.gas_used.map(|val| val.to_string())),
+            ),
+            ("Status", status),
+        ];
+
+        for (label, value) in lines {
+            write!(f, "{}", format_label_and_value(label, &value))?;
+        }
+
+        write!(f, "")
+    }
+}
+
+const TX_QUERY: &str = "
+SELECT t.hash, t.block_number, b.timestamp, t.tx_index, t.from_addr, t.to_addr,
+       t.value, t.gas, r.status, r.gas_used
+FROM transactions t
+JOIN blocks b ON b.number = t.block_number
+LEFT JOIN receipts r ON r.tx_hash = t.hash";
+
+/// Local SQLite index of blocks, transactions, receipts and logs.
+pub struct Indexer {
+    conn: Connection,
+}
+
+impl Indexer {
+    pub fn path() -> PathBuf {
+        data_dir().join(DB_FILE)
+    }
+
+    pub fn open(path: PathBuf) -> Result<Indexer, IndexError> {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Indexer { conn })
+    }
+
+    pub fn head(&self) -> Result<Option<i32>, IndexError> {
+        let head = self
+            .conn
+            .query_row("SELECT MAX(number) FROM blocks", [], |row| row.get(0))?;
+        Ok(head)
+    }
+
+    pub fn block_hash(&self, number: i32) -> Result<Option<String>, IndexError> {
+        let hash = self
+            .conn
+            .query_row(
+                "SELECT hash FROM blocks WHERE number = ?1",
+                [number],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(hash)
+    }
+
+    fn parent_hash(&self, number: i32) -> Result<Option<String>, IndexError> {
+        let hash = self
+            .conn
+            .query_row(
+                "SELECT parent_hash FROM blocks WHERE number = ?1",
+                [number],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(hash)
+    }
+
+    /// Chain the index was built from, `None` for an empty index.
+    pub fn chain_id(&self) -> Result<Option<u64>, IndexError> {
+        let id = self
+            .conn
+            .query_row("SELECT value FROM meta WHERE key = 'chain_id'", [], |row| {
+                row.get::<_, String>(0)
+            })
+            .optional()?;
+        Ok(id.and_then(|id| id.parse::<u64>().ok()))
+    }
+
+    /// Binds the index to the chain `provider` serves, or rejects it when the index
+    /// was built from another one.
+    async fn check_chain<P: Provider>(&self, provider: &P) -> Result<(), IndexError> {
+        let node = match provider.chain_id().await? {
+            Some(id) => Some(id),
+            None => provider.net_version().await?,
+        };
+        match (self.chain_id()?, node) {
+            (Some(indexed), Some(node)) if indexed != node => {
+                Err(IndexError::WrongChain { indexed, node })
+            }
+            (None, Some(node)) => {
+                self.conn.execute(
+                    "INSERT INTO meta (key, value) VALUES ('chain_id', ?1)",
+                    [node.to_string()],
+                )?;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Indexes blocks up to `to` (inclusive), starting at `from` or after the current head.
+    /// Orphaned blocks are rolled back whenever the chain no longer links to the stored head.
+    pub async fn sync<P: Provider>(
+        &mut self,
+        provider: &P,
+        from: Option<i32>,
+        to: i32,
+    ) -> Result<SyncReport, IndexError> {
+        self.check_chain(provider).await?;
+
+        let mut report = SyncReport::default();
+        let mut next = match (self.head()?, from) {
+            (Some(head), None) => {
+                let ancestor = self.reorg_ancestor(provider, head).await?;
+                report.rolled_back += self.rollback_after(ancestor)?;
+                ancestor + 1
+            }
+            // Only `from..=to` is indexed again, what's below is checked when the first
+            // block is linked to its parent and what's above once the range is done.
+            (Some(_), Some(from)) => {
+                report.rolled_back += self.rollback_range(from, to)?;
+                from
+            }
+            (None, Some(from)) => from,
+            (None, None) => to,
+        };
+
+        while next <= to {
+            let block = provider.get_block(None, Some(next)).await?;
+
+            if let Some(parent) = self.block_hash(next - 1)? {
+                if parent != block.parent_hash {
+                    let ancestor = self.reorg_ancestor(provider, next - 1).await?;
+                    report.rolled_back += self.rollback_after(ancestor)?;
+                    next = ancestor + 1;
+                    continue;
+                }
+            }
+
+            let receipts = self.fetch_receipts(provider, &block).await?;
+            self.insert_block(&block, &receipts)?;
+            report.indexed += 1;
+            next += 1;
+        }
+
+        // Blocks kept above a re-indexed range must still build on it.
+        if let (Some(parent), Some(hash)) = (self.parent_hash(to + 1)?, self.block_hash(to)?) {
+            if parent != hash {
+                report.rolled_back += self.rollback_after(to)?;
+            }
+        }
+
+        report.head = self.head()?;
+        Ok(report)
+    }
+
+    async fn fetch_receipts<P: Provider>(
+        &self,
+        provider: &P,
+        block: &Block,
+    ) -> Result<Vec<Receipt>, IndexError> {
+        if let Some(receipts) = provider.get_block_receipts(Some(block.number)).await? {
+            return Ok(receipts);
+        }
+
+        let mut receipts = vec![];
+        for tx in &block.transactions {
+            if let Some(receipt) = provider.get_tx_receipt(&tx.hash).await? {
+                receipts.push(receipt);
+            }
+        }
+        Ok(receipts)
+    }
+
+    /// Walks back from `number` until the stored hash matches the canonical chain.
+    async fn find_common_ancestor<P: Provider>(
+        &self,
+        provider: &P,
+        number: i32,
+    ) -> Result<Option<i32>, IndexError> {
+        let mut number = number;
+        while let Some(stored) = self.block_hash(number)? {
+            let canonical = provider.get_block(None, Some(number)).await?;
+            if canonical.hash == stored {
+                return Ok(Some(number));
+            }
+            number -= 1;
+        }
+        Ok(None)
+    }
+
+    /// Common ancestor of the index and the chain, an error when the reorg goes deeper
+    /// than the indexed blocks.
+    async fn reorg_ancestor<P: Provider>(
+        &self,
+        provider: &P,
+        number: i32,
+    ) -> Result<i32, IndexError> {
+        match self.find_common_ancestor(provider, number).await? {
+            Some(ancestor) => Ok(ancestor),
+            None => {
+                let oldest = self
+                    .conn
+                    .query_row("SELECT MIN(number) FROM blocks", [], |row| row.get(0))?;
+                Err(IndexError::DeepReorg { oldest })
+            }
+        }
+    }
+
+    /// Deletes everything indexed above `above`.
+    fn rollback_after(&mut self, above: i32) -> Result<usize, IndexError> {
+        self.rollback_range(above.saturating_add(1), i32::MAX)
+    }
+
+    /// Deletes everything indexed from `from` to `to`, both included.
+    fn rollback_range(&mut self, from: i32, to: i32) -> Result<usize, IndexError> {
+        let tx = self.conn.transaction()?;
+        let range = [from, to];
+        tx.execute("DELETE FROM logs WHERE block_number BETWEEN ?1 AND ?2", range)?;
+        tx.execute("DELETE FROM receipts WHERE block_number BETWEEN ?1 AND ?2", range)?;
+        tx.execute("DELETE FROM transactions WHERE block_number BETWEEN ?1 AND ?2", range)?;
+        let removed = tx.execute("DELETE FROM blocks WHERE number BETWEEN ?1 AND ?2", range)?;
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    fn insert_block(&mut self, block: &Block, receipts: &[Receipt]) -> Result<(), IndexError> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO blocks
+             (number, hash, parent_hash, timestamp, miner, gas_used, gas_limit, base_fee, tx_count)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                block.number,
+                block.hash,
+                block.parent_hash,
+                block.timestamp,
+                block.miner,
+                block.gas_used.to_string(),
+                block.gas_limit.to_string(),
+                block.base_gas_fee.unwrap_or(0).to_string(),
+                block.transactions.len(),
+            ],
+        )?;
+
+        for item in &block.transactions {
+            tx.execute(
+                "INSERT OR REPLACE INTO transactions
+                 (hash, block_number, tx_index, tx_type, from_addr, to_addr, value, gas, gas_price, nonce, input)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    item.hash,
+                    block.number,
+                    item.tx_idx.unwrap_or(0),
+                    item.tx_type.byte(),
+                    item.from.to_lowercase(),
+                    item.to.as_ref().map(|addr| addr.to_lowercase()),
+                    item.value.to_string(),
+                    item.gas.to_string(),
+                    item.gas_price.map(|val| val.to_string()),
+                    item.nonce,
+                    item.input,
+                ],
+            )?;
+        }
+
+        for receipt in receipts {
+            tx.execute(
+                "INSERT OR REPLACE INTO receipts
+                 (tx_hash, block_number, status, gas_used, cumulative_gas_used, effective_gas_price, contract_address)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    receipt.tx_hash,
+                    block.number,
+                    receipt.status,
+                    receipt.gas_used.to_string(),
+                    receipt.cumulative_gas_used.to_string(),
+                    receipt.effective_gas_price.map(|val| val.to_string()),
+                    receipt.contract_address,
+                ],
+            )?;
+
+            for (idx, log) in receipt.logs.iter().enumerate() {
+                tx.execute(
+                    "INSERT OR REPLACE INTO logs (tx_hash, block_number, log_index, address, topics, data)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        receipt.tx_hash,
+                        block.number,
+                        log.log_index
+                            .as_ref()
+                            .map(|val| hex_to_decimals(val, true) as i64)
+                            .unwrap_or(idx as i64),
+                        log.address.to_lowercase(),
+                        log.topics.join(","),
+                        log.data,
+                    ],
+                )?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn query_txs(
+        &self,
+        filter: &str,
+        params: &[&dyn rusqlite::ToSql],
+    ) -> Result<Vec<IndexedTx>, IndexError> {
+        let sql = format!(
+            "{} WHERE {} ORDER BY t.block_number, t.tx_index",
+            TX_QUERY, filter
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt
+            .query_map(params, IndexedTx::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    pub fn txs_by_address(&self, addr: &str) -> Result<Vec<IndexedTx>, IndexError> {
+        let addr = addr.trim().to_lowercase();
+        self.query_txs("t.from_addr = ?1 OR t.to_addr = ?1", &[&addr])
+    }
+
+    pub fn txs_by_block(&self, number: i32) -> Result<Vec<IndexedTx>, IndexError> {
+        self.query_txs("t.block_number = ?1", &[&number])
+    }
+
+    /// Transactions in blocks whose timestamp is within `from..=to` (unix seconds).
+    pub fn txs_by_time(&self, from: i64, to: i64) -> Result<Vec<IndexedTx>, IndexError> {
+        self.query_txs("b.timestamp BETWEEN ?1 AND ?2", &[&from, &to])
+    }
+}
@@ -0,0 +1,26 @@
+pub mod abi;
+pub mod account;
+pub mod body;
+pub mod cache;
+pub mod chains;
+pub mod cli;
+pub mod config;
+pub mod cost;
+pub mod header;
+pub mod indexer;
+pub mod node;
+pub mod profile;
+pub mod proof;
+pub mod providers;
+pub mod proxy;
+pub mod ratelimit;
+pub mod revert;
+pub mod rlp;
+pub mod scanner;
+pub mod sender;
+pub mod signatures;
+pub mod statediff;
+pub mod trace;
+pub mod trie;
+pub mod types;
+pub mod utils;
@@ -1,18 +1,66 @@
-mod cli;
-mod rpc_calls;
-mod types;
-mod utils;
-use cli::CommandLine;
+use clap::{Arg, Command};
+use colored::Colorize;
+use ethereum::{
+    cli::{self, CommandLine},
+    config::Config,
+    providers::{self, Provider, ProviderError, RecordingProvider, ReplayProvider},
+};
 
 #[tokio::main]
-async fn main() -> Result<(), reqwest::Error> {
-    loop {
-        let should_continue = CommandLine::select_option().await.unwrap();
+async fn main() -> Result<(), ProviderError> {
+    let matches = Command::new("blockchain-cli")
+        .about("Interactive Ethereum JSON-RPC client")
+        .arg(
+            Arg::new("record")
+                .long("record")
+                .value_name("FILE")
+                .takes_value(true)
+                .conflicts_with("replay")
+                .help("Log every JSON-RPC request and response to an NDJSON cassette"),
+        )
+        .arg(
+            Arg::new("replay")
+                .long("replay")
+                .value_name("FILE")
+                .takes_value(true)
+                .help("Serve responses from a cassette, without network access"),
+        )
+        .subcommand(
+            Command::new("decode-tx")
+                .about("Decode a raw signed transaction and recover its sender, without a node")
+                .arg(
+                    Arg::new("raw")
+                        .required(true)
+                        .help("Signed transaction hex"),
+                ),
+        )
+        .get_matches();
 
-        if should_continue == false {
-            break;
-        }
+    if let Some(("decode-tx", args)) = matches.subcommand() {
+        cli::decode_tx(args.get_one::<String>("raw").unwrap());
+        return Ok(());
     }
 
+    let provider: Box<dyn Provider> = match (
+        matches.get_one::<String>("record"),
+        matches.get_one::<String>("replay"),
+    ) {
+        (_, Some(path)) => {
+            println!("{}", format!("Replaying {}", path).bold().green());
+            Box::new(ReplayProvider::from_file(path)?)
+        }
+        (Some(path), None) => {
+            println!("{}", format!("Recording to {}", path).bold().green());
+            let provider = providers::from_profile(&Config::global().profile)?;
+            Box::new(RecordingProvider::create(path, provider)?)
+        }
+        (None, None) => providers::from_profile(&Config::global().profile)?,
+    };
+
+    let cli = CommandLine::new(provider);
+    cli.connect().await;
+
+    while cli.select_option().await {}
+
     Ok(())
 }
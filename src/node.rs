@@ -0,0 +1,206 @@
+use crate::chains::ChainInfo;
+use crate::providers::{Provider, ProviderError};
+use crate::types::Method;
+use crate::utils::{format_label_and_value, hex_to_decimals};
+use serde_json::{json, Value};
+use std::{
+    fmt,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+const LATENCY_SAMPLES: u32 = 3;
+const SYNC_SAMPLE_DELAY: Duration = Duration::from_secs(3);
+
+#[derive(Debug)]
+pub struct SyncStatus {
+    pub starting: i128,
+    pub current: i128,
+    pub highest: i128,
+    /// Blocks per second measured between two `eth_syncing` samples.
+    pub rate: Option<f64>,
+}
+
+impl SyncStatus {
+    fn from_value(value: &Value) -> Option<SyncStatus> {
+        let field = |name: &str| {
+            value
+                .get(name)
+                .and_then(|v| v.as_str())
+                .map(|v| hex_to_decimals(v, true))
+        };
+
+        Some(SyncStatus {
+            starting: field("startingBlock").unwrap_or(0),
+            current: field("currentBlock")?,
+            highest: field("highestBlock")?,
+            rate: None,
+        })
+    }
+
+    pub fn progress(&self) -> f64 {
+        if self.highest == 0 {
+            return 0.0;
+        }
+        self.current as f64 * 100.0 / self.highest as f64
+    }
+
+    pub fn eta(&self) -> Option<Duration> {
+        let rate = self.rate.filter(|rate| *rate > 0.0)?;
+        let remaining = (self.highest - self.current).max(0) as f64;
+        Some(Duration::from_secs_f64(remaining / rate))
+    }
+}
+
+#[derive(Debug)]
+pub struct NodeInfo {
+    pub client_version: Option<String>,
+    pub chain: Option<ChainInfo>,
+    pub peer_count: Option<i128>,
+    pub listening: Option<bool>,
+    /// `None` when the node reports it is fully synced.
+    pub syncing: Option<SyncStatus>,
+    pub latest_block: Option<i128>,
+    pub latest_block_age: Option<Duration>,
+    pub latency: Duration,
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, (secs % 3600) / 60),
+    }
+}
+
+impl NodeInfo {
+    pub async fn fetch<P: Provider>(provider: &P) -> Result<NodeInfo, ProviderError> {
+        let mut latency = Duration::ZERO;
+        for _ in 0..LATENCY_SAMPLES {
+            let start = Instant::now();
+            provider.request(Method::BlockNumber, vec![]).await?;
+            latency += start.elapsed();
+        }
+
+        let client_version = provider.request(Method::ClientVersion, vec![])
+            .await?
+            .and_then(|v| v.as_str().map(|v| v.to_string()));
+        let peer_count = provider.request(Method::PeerCount, vec![])
+            .await?
+            .and_then(|v| v.as_str().map(|v| hex_to_decimals(v, true)));
+        let listening = provider.request(Method::Listening, vec![])
+            .await?
+            .and_then(|v| v.as_bool());
+        let chain = provider.chain_id().await?.map(ChainInfo::by_id);
+
+        let (latest_block, latest_block_age) = NodeInfo::latest_block(provider).await?;
+        let syncing = NodeInfo::sync_status(provider).await?;
+
+        Ok(NodeInfo {
+            client_version,
+            chain,
+            peer_count,
+            listening,
+            syncing,
+            latest_block,
+            latest_block_age,
+            latency: latency / LATENCY_SAMPLES,
+        })
+    }
+
+    async fn latest_block<P: Provider>(
+        provider: &P,
+    ) -> Result<(Option<i128>, Option<Duration>), ProviderError> {
+        let params = vec![json!("latest"), json!(false)];
+        let block = match provider.request(Method::GetBlockByNumber, params).await? {
+            Some(block) if block.is_object() => block,
+            _ => return Ok((None, None)),
+        };
+
+        let field = |name: &str| {
+            block
+                .get(name)
+                .and_then(|v| v.as_str())
+                .map(|v| hex_to_decimals(v, true))
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i128)
+            .unwrap_or(0);
+        let age = field("timestamp").map(|ts| Duration::from_secs((now - ts).max(0) as u64));
+
+        Ok((field("number"), age))
+    }
+
+    /// Samples `eth_syncing` twice to estimate the sync speed.
+    async fn sync_status<P: Provider>(provider: &P) -> Result<Option<SyncStatus>, ProviderError> {
+        let first = provider.request(Method::Syncing, vec![]).await?;
+        let mut status = match first.as_ref().and_then(SyncStatus::from_value) {
+            Some(status) => status,
+            None => return Ok(None),
+        };
+
+        tokio::time::sleep(SYNC_SAMPLE_DELAY).await;
+        let second = provider.request(Method::Syncing, vec![]).await?;
+        if let Some(next) = second.as_ref().and_then(SyncStatus::from_value) {
+            let synced = (next.current - status.current) as f64;
+            status.rate = Some(synced / SYNC_SAMPLE_DELAY.as_secs_f64());
+            status.current = next.current;
+            status.highest = next.highest;
+        }
+
+        Ok(Some(status))
+    }
+}
+
+impl fmt::Display for NodeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let or_unknown = |val: Option<String>| val.unwrap_or_else(|| "??".to_string());
+
+        let mut lines = vec![
+            ("Client", or_unknown(self.client_version.clone())),
+            (
+                "Chain",
+                or_unknown(
+                    self.chain
+                        .map(|chain| format!("{} ({})", chain.name, chain.id)),
+                ),
+            ),
+            (
+                "Peers",
+                or_unknown(self.peer_count.map(|val| val.to_string())),
+            ),
+            (
+                "Listening",
+                or_unknown(self.listening.map(|val| val.to_string())),
+            ),
+            (
+                "Latest Block",
+                or_unknown(self.latest_block.map(|val| val.to_string())),
+            ),
+            (
+                "Latest Block Age",
+                or_unknown(self.latest_block_age.map(format_duration)),
+            ),
+            ("RPC Latency", format!("{} ms", self.latency.as_millis())),
+        ];
+
+        match &self.syncing {
+            None => lines.push(("Syncing", "No (synced)".to_string())),
+            Some(status) => {
+                lines.push(("Syncing", "Yes".to_string()));
+                lines.push(("Starting Block", status.starting.to_string()));
+                lines.push(("Current Block", status.current.to_string()));
+                lines.push(("Highest Block", status.highest.to_string()));
+                lines.push(("Progress", format!("{:.2}%", status.progress())));
+                lines.push(("ETA", or_unknown(status.eta().map(format_duration))));
+            }
+        }
+
+        for (label, value) in lines {
+            write!(f, "{}", format_label_and_value(label, &value))?;
+        }
+
+        write!(f, "")
+    }
+}
@@ -0,0 +1,266 @@
+use crate::utils::format_label_and_value;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Rows shown per section of the profile.
+const TOP: usize = 10;
+
+/// One step of geth's default struct logger.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLog {
+    pub pc: u64,
+    pub op: String,
+    pub gas: u64,
+    pub gas_cost: u64,
+    pub depth: usize,
+    #[serde(default)]
+    pub stack: Vec<String>,
+}
+
+/// Result of `debug_traceTransaction` without a tracer.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructTrace {
+    pub gas: u64,
+    #[serde(default)]
+    pub failed: bool,
+    pub struct_logs: Vec<StructLog>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GasStats {
+    pub count: usize,
+    pub gas: u64,
+}
+
+impl GasStats {
+    fn add(&mut self, gas: u64) {
+        self.count += 1;
+        self.gas += gas;
+    }
+}
+
+/// A step with the gas it spent itself, calls and creates excluding what their
+/// callee consumed, and the contracts on the call stack when it ran.
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub pc: u64,
+    pub op: String,
+    pub depth: usize,
+    pub gas: u64,
+    /// Code being run from the top level call down, `CREATE` for init code.
+    pub contracts: Vec<String>,
+}
+
+impl Step {
+    pub fn contract(&self) -> &str {
+        self.contracts
+            .last()
+            .map(|contract| contract.as_str())
+            .unwrap_or("??")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GasProfile {
+    /// Gas used by the transaction, including intrinsic gas and refunds.
+    pub gas_used: u64,
+    pub failed: bool,
+    pub steps: Vec<Step>,
+}
+
+/// Callee address of a call opcode, second from the top of the stack.
+fn call_target(log: &StructLog) -> String {
+    let word = match log.stack.len() {
+        0 | 1 => return "??".to_string(),
+        len => log.stack[len - 2].trim_start_matches("0x"),
+    };
+    let address = format!("{:0>40}", word);
+    format!("0x{}", &address[address.len() - 40..]).to_lowercase()
+}
+
+fn is_call(op: &str) -> bool {
+    matches!(op, "CALL" | "CALLCODE" | "DELEGATECALL" | "STATICCALL")
+}
+
+impl GasProfile {
+    /// Attributes gas to every step of `trace`. `to` is the contract the transaction
+    /// called, `None` for a deployment.
+    pub fn new(trace: &StructTrace, to: Option<&str>) -> GasProfile {
+        let logs = &trace.struct_logs;
+        let mut steps: Vec<Step> = Vec::with_capacity(logs.len());
+        let top = to
+            .map(|to| to.to_lowercase())
+            .unwrap_or_else(|| "CREATE".to_string());
+        let mut contracts = vec![top];
+        // Calls waiting for their callee to return: step index and gas spent below them.
+        let mut pending: Vec<(usize, u64)> = vec![];
+
+        for (idx, log) in logs.iter().enumerate() {
+            // Back in a caller, the call step is charged what it cost minus the callee.
+            while contracts.len() > log.depth && contracts.len() > 1 {
+                contracts.pop();
+                if let Some((call, below)) = pending.pop() {
+                    let spent = logs[call].gas.saturating_sub(log.gas);
+                    steps[call].gas = spent.saturating_sub(below);
+                    let total = steps[call].gas + below;
+                    if let Some((_, parent)) = pending.last_mut() {
+                        *parent += total;
+                    }
+                }
+            }
+
+            // The cost geth reports for a call includes the gas it forwards, what's left
+            // at the next step tells what it really took.
+            let next = logs.get(idx + 1);
+            let gas = match next {
+                Some(next) if next.depth == log.depth => log.gas.saturating_sub(next.gas),
+                _ => log.gas_cost,
+            };
+            steps.push(Step {
+                pc: log.pc,
+                op: log.op.clone(),
+                depth: log.depth,
+                gas,
+                contracts: contracts.clone(),
+            });
+
+            if next.is_some_and(|next| next.depth > log.depth) {
+                contracts.push(match is_call(&log.op) {
+                    true => call_target(log),
+                    false => "CREATE".to_string(),
+                });
+                pending.push((idx, 0));
+            } else if let Some((_, below)) = pending.last_mut() {
+                *below += gas;
+            }
+        }
+
+        GasProfile {
+            gas_used: trace.gas,
+            failed: trace.failed,
+            steps,
+        }
+    }
+
+    /// Gas spent running code, without intrinsic gas and refunds.
+    pub fn execution_gas(&self) -> u64 {
+        self.steps.iter().map(|step| step.gas).sum()
+    }
+
+    /// Highest gas first, ties in key order so the output is stable.
+    fn sorted<K: Ord>(stats: HashMap<K, GasStats>) -> Vec<(K, GasStats)> {
+        let mut stats: Vec<(K, GasStats)> = stats.into_iter().collect();
+        stats.sort_by(|a, b| b.1.gas.cmp(&a.1.gas).then(a.0.cmp(&b.0)));
+        stats
+    }
+
+    /// Gas per opcode, highest first.
+    pub fn by_opcode(&self) -> Vec<(String, GasStats)> {
+        let mut stats: HashMap<String, GasStats> = HashMap::new();
+        for step in &self.steps {
+            stats.entry(step.op.clone()).or_default().add(step.gas);
+        }
+        GasProfile::sorted(stats)
+    }
+
+    /// Gas per call depth, 1 being the contract the transaction called.
+    pub fn by_depth(&self) -> Vec<(usize, GasStats)> {
+        let mut stats: HashMap<usize, GasStats> = HashMap::new();
+        for step in &self.steps {
+            stats.entry(step.depth).or_default().add(step.gas);
+        }
+        let mut stats: Vec<(usize, GasStats)> = stats.into_iter().collect();
+        stats.sort_by_key(|(depth, _)| *depth);
+        stats
+    }
+
+    /// Most expensive instructions as (contract, pc, opcode), highest first.
+    pub fn hotspots(&self, count: usize) -> Vec<((String, u64, String), GasStats)> {
+        let mut stats: HashMap<(String, u64, String), GasStats> = HashMap::new();
+        for step in &self.steps {
+            let key = (step.contract().to_string(), step.pc, step.op.clone());
+            stats.entry(key).or_default().add(step.gas);
+        }
+        let mut stats = GasProfile::sorted(stats);
+        stats.truncate(count);
+        stats
+    }
+
+    /// Folded stacks for `flamegraph.pl` or inferno: the contracts on the call stack
+    /// then the opcode, separated by `;`, followed by the gas spent there.
+    pub fn folded(&self) -> String {
+        let mut stacks: HashMap<String, u64> = HashMap::new();
+        for step in &self.steps {
+            let stack = format!("{};{}", step.contracts.join(";"), step.op);
+            *stacks.entry(stack).or_default() += step.gas;
+        }
+        let mut stacks: Vec<(String, u64)> =
+            stacks.into_iter().filter(|(_, gas)| *gas > 0).collect();
+        stacks.sort();
+        stacks
+            .iter()
+            .map(|(stack, gas)| format!("{} {}\n", stack, gas))
+            .collect()
+    }
+}
+
+fn share(gas: u64, total: u64) -> String {
+    match total {
+        0 => "0.0%".to_string(),
+        total => format!("{:.1}%", gas as f64 * 100.0 / total as f64),
+    }
+}
+
+impl fmt::Display for GasProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let execution = self.execution_gas();
+        let lines = [
+            ("Gas Used", self.gas_used.to_string()),
+            ("Execution Gas", execution.to_string()),
+            (
+                "Intrinsic and Refunds",
+                (self.gas_used as i128 - execution as i128).to_string(),
+            ),
+            ("Steps", self.steps.len().to_string()),
+            ("Failed", self.failed.to_string()),
+        ];
+        for (label, value) in lines {
+            write!(f, "{}", format_label_and_value(label, &value))?;
+        }
+
+        writeln!(f, "By opcode:")?;
+        for (op, stats) in self.by_opcode().iter().take(TOP) {
+            let value = format!(
+                "{} gas, {} steps, {}",
+                stats.gas,
+                stats.count,
+                share(stats.gas, execution)
+            );
+            write!(f, "{}", format_label_and_value(op, &value))?;
+        }
+
+        writeln!(f, "By depth:")?;
+        for (depth, stats) in self.by_depth() {
+            let value = format!("{} gas, {}", stats.gas, share(stats.gas, execution));
+            write!(f, "{}", format_label_and_value(&depth.to_string(), &value))?;
+        }
+
+        writeln!(f, "Hotspots:")?;
+        for ((contract, pc, op), stats) in self.hotspots(TOP) {
+            let label = format!("{} pc {}", contract, pc);
+            let value = format!(
+                "{} {} gas, {} steps, {}",
+                op,
+                stats.gas,
+                stats.count,
+                share(stats.gas, execution)
+            );
+            write!(f, "{}", format_label_and_value(&label, &value))?;
+        }
+
+        write!(f, "")
+    }
+}
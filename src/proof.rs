@@ -0,0 +1,107 @@
+use crate::account::parse_word;
+use crate::rlp::Rlp;
+use crate::trie::{verify_proof, ProofError, EMPTY_ROOT};
+use crate::utils::{decode_hex, encode_hex, keccak256};
+use serde::{Deserialize, Serialize};
+
+/// Response of `eth_getProof`: an account, its storage slots and their Merkle proofs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountProof {
+    pub address: String,
+    pub balance: String,
+    pub code_hash: String,
+    pub nonce: String,
+    pub storage_hash: String,
+    pub account_proof: Vec<String>,
+    pub storage_proof: Vec<StorageProof>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageProof {
+    pub key: String,
+    pub value: String,
+    pub proof: Vec<String>,
+}
+
+fn bytes(value: &str, field: &str) -> Result<Vec<u8>, ProofError> {
+    decode_hex(value).map_err(|e| ProofError::Malformed(format!("{} {}: {}", field, value, e)))
+}
+
+fn hash(value: &str, field: &str) -> Result<[u8; 32], ProofError> {
+    bytes(value, field)?
+        .try_into()
+        .map_err(|_| ProofError::Malformed(format!("{} {} is not 32 bytes", field, value)))
+}
+
+/// A quantity as minimal big endian bytes, the way it is stored in the trie.
+fn quantity(value: &str, field: &str) -> Result<Vec<u8>, ProofError> {
+    let value = bytes(value, field)?;
+    let start = value.iter().position(|b| *b != 0).unwrap_or(value.len());
+    Ok(value[start..].to_vec())
+}
+
+fn nodes(proof: &[String]) -> Result<Vec<Vec<u8>>, ProofError> {
+    proof.iter().map(|node| bytes(node, "proof node")).collect()
+}
+
+fn compare(field: &str, claimed: &[u8], proven: &[u8]) -> Result<(), ProofError> {
+    if claimed == proven {
+        return Ok(());
+    }
+    let hex = |value: &[u8]| match value.is_empty() {
+        true => "0x0".to_string(),
+        false => encode_hex(value),
+    };
+    Err(ProofError::Mismatch {
+        field: field.to_string(),
+        claimed: hex(claimed),
+        proven: hex(proven),
+    })
+}
+
+impl AccountProof {
+    /// Checks the account fields against `state_root` and every storage slot against
+    /// the proven storage root. Accounts absent from the state must be claimed empty.
+    pub fn verify(&self, state_root: &str) -> Result<(), ProofError> {
+        let root = hash(state_root, "stateRoot")?;
+        let address = bytes(&self.address, "address")?;
+        let proven = verify_proof(&root, &keccak256(&address), &nodes(&self.account_proof)?)?;
+
+        let (nonce, balance, storage_hash, code_hash) = match proven {
+            Some(value) => match Rlp::decode(&value)? {
+                Rlp::List(items) if items.len() == 4 => (
+                    items[0].as_bytes()?.to_vec(),
+                    items[1].as_bytes()?.to_vec(),
+                    items[2].as_bytes()?.to_vec(),
+                    items[3].as_bytes()?.to_vec(),
+                ),
+                _ => {
+                    return Err(ProofError::InvalidNode(
+                        "account is not a 4 item list".to_string(),
+                    ))
+                }
+            },
+            None => (vec![], vec![], EMPTY_ROOT.to_vec(), keccak256(&[]).to_vec()),
+        };
+
+        compare("nonce", &quantity(&self.nonce, "nonce")?, &nonce)?;
+        compare("balance", &quantity(&self.balance, "balance")?, &balance)?;
+        let storage_root = hash(&self.storage_hash, "storageHash")?;
+        compare("storageHash", &storage_root, &storage_hash)?;
+        compare("codeHash", &hash(&self.code_hash, "codeHash")?, &code_hash)?;
+
+        for slot in &self.storage_proof {
+            let key = parse_word(&slot.key).map_err(ProofError::Malformed)?;
+            let proven = verify_proof(&storage_root, &keccak256(&key), &nodes(&slot.proof)?)?;
+            let value = match proven {
+                Some(value) => Rlp::decode(&value)?.as_bytes()?.to_vec(),
+                None => vec![],
+            };
+            let field = format!("storage slot {}", slot.key);
+            compare(&field, &quantity(&slot.value, &field)?, &value)?;
+        }
+
+        Ok(())
+    }
+}
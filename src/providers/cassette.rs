@@ -0,0 +1,167 @@
+use super::{Provider, ProviderError};
+use crate::types::{Body, Method};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::Write,
+    sync::Mutex,
+};
+
+/// One line of a cassette: a request and either the response or the transport error.
+#[derive(Debug, Serialize, Deserialize)]
+struct Interaction {
+    request: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    response: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn key(request: &Value) -> String {
+    format!(
+        "{}:{}",
+        request["method"].as_str().unwrap_or(""),
+        request.get("params").cloned().unwrap_or(Value::Null)
+    )
+}
+
+/// Logs every call made through the wrapped provider to an NDJSON cassette.
+pub struct RecordingProvider<P: Provider> {
+    inner: P,
+    path: String,
+    file: Mutex<File>,
+}
+
+impl<P: Provider> RecordingProvider<P> {
+    /// Starts a new cassette at `path`, replacing any previous one.
+    pub fn create(path: &str, inner: P) -> Result<RecordingProvider<P>, ProviderError> {
+        Ok(RecordingProvider {
+            inner,
+            path: path.to_string(),
+            file: Mutex::new(File::create(path)?),
+        })
+    }
+
+    fn record(&self, interaction: &Interaction) {
+        let line = match serde_json::to_string(interaction) {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+        if let Ok(mut file) = self.file.lock() {
+            if let Err(e) = writeln!(file, "{}", line).and_then(|_| file.flush()) {
+                println!("Failed to write cassette {}: {}", self.path, e);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<P: Provider> Provider for RecordingProvider<P> {
+    fn endpoint(&self) -> String {
+        self.inner.endpoint()
+    }
+
+    async fn send(&self, request: &Value) -> Result<Value, ProviderError> {
+        self.inner.send(request).await
+    }
+
+    /// Records at the `call` level so responses served by the cache end up in the cassette too.
+    async fn call(&self, method: Method, params: Vec<Value>) -> Result<Value, ProviderError> {
+        let request = serde_json::to_value(Body::new(method, params.clone()))?;
+        let result = self.inner.call(method, params).await;
+
+        self.record(&Interaction {
+            request,
+            response: result.as_ref().ok().cloned(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+        result
+    }
+}
+
+/// Serves responses from a cassette without any network access.
+///
+/// Requests are matched on method and params. Repeated requests get the recorded
+/// responses in order, then the last one again; anything not in the cassette fails.
+pub struct ReplayProvider {
+    path: String,
+    interactions: Mutex<HashMap<String, (Vec<Interaction>, usize)>>,
+}
+
+impl ReplayProvider {
+    pub fn from_file(path: &str) -> Result<ReplayProvider, ProviderError> {
+        let content = fs::read_to_string(path)?;
+        let mut interactions: HashMap<String, (Vec<Interaction>, usize)> = HashMap::new();
+        for (idx, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let interaction: Interaction = serde_json::from_str(line)
+                .map_err(|e| ProviderError::Invalid(format!("{} line {}: {}", path, idx + 1, e)))?;
+            interactions
+                .entry(key(&interaction.request))
+                .or_default()
+                .0
+                .push(interaction);
+        }
+
+        Ok(ReplayProvider {
+            path: path.to_string(),
+            interactions: Mutex::new(interactions),
+        })
+    }
+
+    fn replay(&self, request: &Value) -> Result<Value, ProviderError> {
+        let mut interactions = self
+            .interactions
+            .lock()
+            .map_err(|_| ProviderError::Invalid("cassette lock poisoned".to_string()))?;
+
+        let (recorded, next) = match interactions.get_mut(&key(request)) {
+            Some(entry) => entry,
+            None => {
+                return Err(ProviderError::Replay(format!(
+                    "{} has no recorded response for {} {}",
+                    self.path,
+                    request["method"].as_str().unwrap_or(""),
+                    request.get("params").cloned().unwrap_or(Value::Null)
+                )))
+            }
+        };
+        let interaction = &recorded[(*next).min(recorded.len() - 1)];
+        *next += 1;
+
+        match (&interaction.response, &interaction.error) {
+            (Some(response), _) => {
+                let mut response = response.clone();
+                response["id"] = request.get("id").cloned().unwrap_or(Value::Null);
+                Ok(response)
+            }
+            (None, Some(error)) => Err(ProviderError::Replay(format!("recorded error: {}", error))),
+            (None, None) => Err(ProviderError::Invalid(format!(
+                "{}: interaction without response",
+                self.path
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for ReplayProvider {
+    fn endpoint(&self) -> String {
+        format!("replay://{}", self.path)
+    }
+
+    async fn send(&self, request: &Value) -> Result<Value, ProviderError> {
+        self.replay(request)
+    }
+
+    /// Skips the response cache, every answer must come from the cassette.
+    async fn call(&self, method: Method, params: Vec<Value>) -> Result<Value, ProviderError> {
+        let request = serde_json::to_value(Body::new(method, params))?;
+        self.replay(&request)
+    }
+}
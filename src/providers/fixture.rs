@@ -0,0 +1,75 @@
+use super::{Provider, ProviderError};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::fs;
+
+/// Serves recorded responses from a fixture file, without any network access.
+///
+/// The file maps methods to a list of `{ "params": [...], "result": ... }` entries
+/// (or `"error"` instead of `"result"`). The first entry whose params match, or that
+/// has no `params`, answers; unknown requests get a "method not found" error.
+pub struct FixtureProvider {
+    name: String,
+    fixtures: Value,
+}
+
+impl FixtureProvider {
+    pub fn new(fixtures: Value) -> FixtureProvider {
+        FixtureProvider {
+            name: "fixture".to_string(),
+            fixtures,
+        }
+    }
+
+    pub fn from_file(path: &str) -> Result<FixtureProvider, ProviderError> {
+        let content = fs::read_to_string(path)?;
+        let fixtures: Value = serde_json::from_str(&content)?;
+        if !fixtures.is_object() {
+            return Err(ProviderError::Invalid(format!(
+                "{}: fixtures must be an object keyed by method",
+                path
+            )));
+        }
+
+        Ok(FixtureProvider {
+            name: format!("fixture://{}", path),
+            fixtures,
+        })
+    }
+}
+
+#[async_trait]
+impl Provider for FixtureProvider {
+    fn endpoint(&self) -> String {
+        self.name.clone()
+    }
+
+    async fn send(&self, request: &Value) -> Result<Value, ProviderError> {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let method = request.get("method").and_then(|m| m.as_str()).unwrap_or("");
+        let params = request.get("params").cloned().unwrap_or(json!([]));
+
+        let entry = self
+            .fixtures
+            .get(method)
+            .and_then(|entries| entries.as_array())
+            .and_then(|entries| {
+                entries
+                    .iter()
+                    .find(|entry| entry.get("params").is_none_or(|p| *p == params))
+            });
+
+        let response = match entry {
+            Some(entry) if entry.get("error").is_some() => {
+                json!({ "jsonrpc": "2.0", "id": id, "error": entry["error"] })
+            }
+            Some(entry) => json!({ "jsonrpc": "2.0", "id": id, "result": entry["result"] }),
+            None => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32601, "message": format!("No fixture for {} {}", method, params) }
+            }),
+        };
+        Ok(response)
+    }
+}
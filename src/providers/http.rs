@@ -0,0 +1,49 @@
+use super::{Provider, ProviderError};
+use crate::ratelimit;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+fn client() -> &'static reqwest::Client {
+    CLIENT.get_or_init(reqwest::Client::new)
+}
+
+/// JSON-RPC over HTTP POST, sharing one connection pool across endpoints.
+pub struct HttpProvider {
+    url: String,
+}
+
+impl HttpProvider {
+    pub fn new(url: &str) -> HttpProvider {
+        HttpProvider {
+            url: url.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for HttpProvider {
+    fn endpoint(&self) -> String {
+        self.url.clone()
+    }
+
+    async fn send(&self, request: &Value) -> Result<Value, ProviderError> {
+        let method = request.get("method").and_then(|m| m.as_str()).unwrap_or("");
+        ratelimit::acquire(&self.url, method).await;
+
+        let res = client()
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .json(request)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())?;
+        let body = res.text().await?;
+
+        serde_json::from_str(&body).map_err(|e| {
+            ProviderError::Invalid(format!("Invalid response from {}: {}", self.url, e))
+        })
+    }
+}
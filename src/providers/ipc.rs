@@ -0,0 +1,119 @@
+use super::{Provider, ProviderError};
+use crate::ratelimit;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::UnixStream,
+    sync::Mutex,
+};
+
+struct Connection {
+    stream: UnixStream,
+    /// Bytes read past the last complete response.
+    buffer: Vec<u8>,
+}
+
+/// JSON-RPC over a node's unix socket (e.g. `geth.ipc`). Responses are not framed,
+/// so they're split by parsing consecutive JSON values off the stream.
+pub struct IpcProvider {
+    /// Endpoint as configured, `ipc://<path>` or a bare path. Rate limits are keyed by it.
+    url: String,
+    path: PathBuf,
+    connection: Mutex<Option<Connection>>,
+    next_id: AtomicU64,
+}
+
+impl IpcProvider {
+    pub fn new(url: &str) -> IpcProvider {
+        IpcProvider {
+            url: url.to_string(),
+            path: PathBuf::from(url.strip_prefix("ipc://").unwrap_or(url)),
+            connection: Mutex::new(None),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Next complete JSON value in the buffer, `None` when more bytes are needed.
+    fn next_value(buffer: &mut Vec<u8>) -> Result<Option<Value>, ProviderError> {
+        let mut values = serde_json::Deserializer::from_slice(buffer).into_iter::<Value>();
+        match values.next() {
+            Some(Ok(value)) => {
+                let consumed = values.byte_offset();
+                buffer.drain(..consumed);
+                Ok(Some(value))
+            }
+            Some(Err(e)) if e.is_eof() => Ok(None),
+            Some(Err(e)) => Err(e.into()),
+            None => Ok(None),
+        }
+    }
+
+    async fn exchange(
+        connection: &mut Connection,
+        request: &Value,
+        id: u64,
+    ) -> Result<Value, ProviderError> {
+        let mut payload = request.to_string().into_bytes();
+        payload.push(b'\n');
+        connection.stream.write_all(&payload).await?;
+
+        let mut chunk = [0; 8192];
+        loop {
+            while let Some(response) = IpcProvider::next_value(&mut connection.buffer)? {
+                if response.get("id") == Some(&json!(id)) {
+                    return Ok(response);
+                }
+            }
+
+            let read = connection.stream.read(&mut chunk).await?;
+            if read == 0 {
+                return Err(ProviderError::Invalid(
+                    "IPC socket closed before answering".to_string(),
+                ));
+            }
+            connection.buffer.extend_from_slice(&chunk[..read]);
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for IpcProvider {
+    fn endpoint(&self) -> String {
+        self.url.clone()
+    }
+
+    async fn send(&self, request: &Value) -> Result<Value, ProviderError> {
+        let method = request.get("method").and_then(|m| m.as_str()).unwrap_or("");
+        ratelimit::acquire(&self.url, method).await;
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut request = request.clone();
+        let original_id = request.get("id").cloned().unwrap_or(Value::Null);
+        request["id"] = json!(id);
+
+        let mut guard = self.connection.lock().await;
+        let connection = match guard.as_mut() {
+            Some(connection) => connection,
+            None => guard.insert(Connection {
+                stream: UnixStream::connect(&self.path).await?,
+                buffer: vec![],
+            }),
+        };
+
+        match IpcProvider::exchange(connection, &request, id).await {
+            Ok(mut response) => {
+                response["id"] = original_id;
+                Ok(response)
+            }
+            Err(e) => {
+                *guard = None;
+                Err(e)
+            }
+        }
+    }
+}
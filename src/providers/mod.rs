@@ -0,0 +1,522 @@
+mod cassette;
+mod fixture;
+mod http;
+#[cfg(unix)]
+mod ipc;
+mod multi;
+mod ws;
+
+pub use cassette::{RecordingProvider, ReplayProvider};
+pub use fixture::FixtureProvider;
+pub use http::HttpProvider;
+#[cfg(unix)]
+pub use ipc::IpcProvider;
+pub use multi::{disagreements, Disagreement, MultiProvider};
+pub use ws::WsProvider;
+
+use crate::cache::Cache;
+use crate::config::Profile;
+use crate::header::Header;
+use crate::proof::AccountProof;
+use crate::profile::StructTrace;
+use crate::statediff::StateDiff;
+use crate::trace::{BlockTrace, CallFrame, ParityTrace};
+use crate::types::{
+    Block, BlockHex, Body, Method, RPCResponse, Receipt, ReceiptHex, Transaction, TransactionHex,
+};
+use crate::utils::hex_to_decimals;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Mutex, OnceLock},
+};
+
+/// Block tag as expected by the JSON-RPC API: a hex quantity or `latest`.
+pub fn block_param(block: Option<i32>) -> String {
+    match block {
+        Some(val) => format!("0x{:x}", val),
+        None => String::from("latest"),
+    }
+}
+
+#[derive(Debug)]
+pub enum ProviderError {
+    Http(reqwest::Error),
+    WebSocket(Box<tokio_tungstenite::tungstenite::Error>),
+    Io(std::io::Error),
+    /// The node answered with a JSON-RPC error.
+    Rpc {
+        code: i64,
+        message: String,
+    },
+    /// The node returned `null` for something that must exist.
+    NotFound(String),
+    /// The response could not be parsed, or the endpoint is unusable.
+    Invalid(String),
+    /// The request is not in the cassette being replayed (or was recorded as failed).
+    Replay(String),
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProviderError::Http(e) => write!(f, "HTTP error: {}", e),
+            ProviderError::WebSocket(e) => write!(f, "WebSocket error: {}", e),
+            ProviderError::Io(e) => write!(f, "IO error: {}", e),
+            ProviderError::Rpc { code, message } => write!(f, "RPC error {}: {}", code, message),
+            ProviderError::NotFound(what) => write!(f, "{} not found", what),
+            ProviderError::Invalid(message) => write!(f, "Invalid response: {}", message),
+            ProviderError::Replay(message) => write!(f, "Replay: {}", message),
+        }
+    }
+}
+
+impl ProviderError {
+    /// The node doesn't serve the method, e.g. a missing `debug_` or `trace_` namespace.
+    pub fn is_unsupported_method(&self) -> bool {
+        match self {
+            ProviderError::Rpc { code: -32601, .. } => true,
+            ProviderError::Rpc { message, .. } => {
+                let message = message.to_lowercase();
+                ["does not exist", "not available", "not supported", "unsupported"]
+                    .iter()
+                    .any(|hint| message.contains(hint))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ProviderError {
+    fn from(e: reqwest::Error) -> Self {
+        ProviderError::Http(e)
+    }
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for ProviderError {
+    fn from(e: tokio_tungstenite::tungstenite::Error) -> Self {
+        ProviderError::WebSocket(Box::new(e))
+    }
+}
+
+impl From<std::io::Error> for ProviderError {
+    fn from(e: std::io::Error) -> Self {
+        ProviderError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ProviderError {
+    fn from(e: serde_json::Error) -> Self {
+        ProviderError::Invalid(e.to_string())
+    }
+}
+
+fn decode<T: DeserializeOwned>(value: Value) -> Result<T, ProviderError> {
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Chain served by each endpoint, as answered to `eth_chainId`.
+static CHAIN_IDS: OnceLock<Mutex<HashMap<String, u64>>> = OnceLock::new();
+
+fn known_chain_id(endpoint: &str) -> Option<u64> {
+    let ids = CHAIN_IDS.get_or_init(Default::default).lock().unwrap();
+    ids.get(endpoint).copied()
+}
+
+fn remember_chain_id(endpoint: &str, id: u64) {
+    let mut ids = CHAIN_IDS.get_or_init(Default::default).lock().unwrap();
+    ids.insert(endpoint.to_string(), id);
+}
+
+/// Turns a JSON-RPC error response into a `ProviderError::Rpc`.
+fn checked(response: Value) -> Result<Value, ProviderError> {
+    match response.get("error") {
+        Some(error) => Err(ProviderError::Rpc {
+            code: error.get("code").and_then(|c| c.as_i64()).unwrap_or(0),
+            message: error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("")
+                .to_string(),
+        }),
+        None => Ok(response),
+    }
+}
+
+/// A JSON-RPC backend. Implementations only move requests around, every typed call
+/// (and the response cache) is built on top of `send`.
+#[async_trait]
+pub trait Provider: Send + Sync {
+    /// Where requests go, shown in messages.
+    fn endpoint(&self) -> String;
+
+    /// Sends a JSON-RPC request and returns the raw response, which may carry an `error`.
+    async fn send(&self, request: &Value) -> Result<Value, ProviderError>;
+
+    /// Full response of `method`, served from the response cache when possible.
+    ///
+    /// Responses are only cached once the chain served by the endpoint is known, the
+    /// first cacheable call asks for it.
+    async fn call(&self, method: Method, params: Vec<Value>) -> Result<Value, ProviderError> {
+        let request = serde_json::to_value(Body::new(method, params))?;
+        let cache = match (Cache::global(), method) {
+            (_, Method::ChainId) => {
+                let res = self.send(&request).await?;
+                if let Some(id) = res.get("result").and_then(|result| result.as_str()) {
+                    remember_chain_id(&self.endpoint(), hex_to_decimals(id, true) as u64);
+                }
+                return Ok(res);
+            }
+            (Some(cache), _) => cache,
+            (None, _) => return self.send(&request).await,
+        };
+
+        let chain_id = match known_chain_id(&self.endpoint()) {
+            Some(id) => Some(id),
+            None => self.chain_id().await.ok().flatten(),
+        };
+        let namespace = match chain_id {
+            Some(id) => Cache::namespace(id, &self.endpoint()),
+            None => return self.send(&request).await,
+        };
+        if let Some(cached) = cache.get(&namespace, &request) {
+            return Ok(cached);
+        }
+
+        let res = self.send(&request).await?;
+        cache.store(&namespace, &request, &res);
+        Ok(res)
+    }
+
+    /// Raw `result` of a call, `None` when the node returned an error or null.
+    async fn request(
+        &self,
+        method: Method,
+        params: Vec<Value>,
+    ) -> Result<Option<Value>, ProviderError> {
+        let res = self.call(method, params).await?;
+        let result = match res.get("result") {
+            Some(Value::Null) | None => None,
+            Some(result) => Some(result.clone()),
+        };
+        Ok(result)
+    }
+
+    /// Like `call`, but JSON-RPC errors become `ProviderError::Rpc`.
+    async fn fetch(&self, method: Method, params: Vec<Value>) -> Result<Value, ProviderError> {
+        checked(self.call(method, params).await?)
+    }
+
+    async fn get_accounts(&self) -> Result<Vec<String>, ProviderError> {
+        let res = self.fetch(Method::Accounts, vec![]).await?;
+        decode(res["result"].clone())
+    }
+
+    async fn gas_price(&self) -> Result<RPCResponse, ProviderError> {
+        decode(self.fetch(Method::GasPrice, vec![]).await?)
+    }
+
+    async fn block_number(&self) -> Result<RPCResponse, ProviderError> {
+        decode(self.fetch(Method::BlockNumber, vec![]).await?)
+    }
+
+    async fn get_balance(
+        &self,
+        addr: &str,
+        block: Option<i32>,
+    ) -> Result<RPCResponse, ProviderError> {
+        let params = vec![json!(addr), json!(block_param(block))];
+        decode(self.fetch(Method::GetBalance, params).await?)
+    }
+
+    async fn get_tx_count(
+        &self,
+        addr: &str,
+        block: Option<i32>,
+    ) -> Result<RPCResponse, ProviderError> {
+        let params = vec![json!(addr), json!(block_param(block))];
+        decode(self.fetch(Method::TxCount, params).await?)
+    }
+
+    /// Deployed code at `addr`, `0x` for accounts without code.
+    async fn get_code(&self, addr: &str, block: Option<i32>) -> Result<RPCResponse, ProviderError> {
+        let params = vec![json!(addr), json!(block_param(block))];
+        decode(self.fetch(Method::GetCode, params).await?)
+    }
+
+    /// Raw 32 byte word stored at `slot` (a hex quantity) of `addr`.
+    async fn get_storage_at(
+        &self,
+        addr: &str,
+        slot: &str,
+        block: Option<i32>,
+    ) -> Result<RPCResponse, ProviderError> {
+        let params = vec![json!(addr), json!(slot), json!(block_param(block))];
+        decode(self.fetch(Method::GetStorageAt, params).await?)
+    }
+
+    /// Executes a read only call of `data` against `to`, reverts become `ProviderError::Rpc`.
+    async fn eth_call(
+        &self,
+        to: &str,
+        data: &str,
+        block: Option<i32>,
+    ) -> Result<RPCResponse, ProviderError> {
+        let params = vec![json!({ "to": to, "data": data }), json!(block_param(block))];
+        decode(self.fetch(Method::Call, params).await?)
+    }
+
+    /// Account and storage proofs of `addr` for the given slots (hex quantities).
+    async fn get_proof(
+        &self,
+        addr: &str,
+        slots: &[String],
+        block: Option<i32>,
+    ) -> Result<AccountProof, ProviderError> {
+        let params = vec![json!(addr), json!(slots), json!(block_param(block))];
+        let res = self.fetch(Method::GetProof, params).await?;
+        match &res["result"] {
+            Value::Null => Err(ProviderError::NotFound(format!("Proof of {}", addr))),
+            result => decode(result.clone()),
+        }
+    }
+
+    async fn block_tx_count(
+        &self,
+        block_hash: Option<&str>,
+        block_number: Option<i32>,
+    ) -> Result<RPCResponse, ProviderError> {
+        let res = match block_hash {
+            Some(val) => {
+                self.fetch(Method::BlockTxCountByHash, vec![json!(val)])
+                    .await?
+            }
+            None => {
+                let params = vec![json!(block_param(block_number))];
+                self.fetch(Method::BlockTxCountByNumber, params).await?
+            }
+        };
+        decode(res)
+    }
+
+    async fn get_block(
+        &self,
+        block_hash: Option<&str>,
+        block_number: Option<i32>,
+    ) -> Result<Block, ProviderError> {
+        let res = match block_hash {
+            Some(val) => {
+                let params = vec![json!(val), json!(true)];
+                self.fetch(Method::GetBlockByHash, params).await?
+            }
+            None => {
+                let params = vec![json!(block_param(block_number)), json!(true)];
+                self.fetch(Method::GetBlockByNumber, params).await?
+            }
+        };
+        let block_hex: BlockHex = match &res["result"] {
+            Value::Null => return Err(ProviderError::NotFound("Block".to_string())),
+            result => decode(result.clone())?,
+        };
+        block_hex.parse().map_err(ProviderError::Invalid)
+    }
+
+    /// Header of a block, without fetching its transactions.
+    async fn get_header(
+        &self,
+        block_hash: Option<&str>,
+        block_number: Option<i32>,
+    ) -> Result<Header, ProviderError> {
+        let res = match block_hash {
+            Some(val) => {
+                let params = vec![json!(val), json!(false)];
+                self.fetch(Method::GetBlockByHash, params).await?
+            }
+            None => {
+                let params = vec![json!(block_param(block_number)), json!(false)];
+                self.fetch(Method::GetBlockByNumber, params).await?
+            }
+        };
+        match &res["result"] {
+            Value::Null => Err(ProviderError::NotFound("Block".to_string())),
+            result => decode(result.clone()),
+        }
+    }
+
+    async fn get_tx(&self, hash: &str) -> Result<Transaction, ProviderError> {
+        let res = self.fetch(Method::GetTxByHash, vec![json!(hash)]).await?;
+        let tx: TransactionHex = match &res["result"] {
+            Value::Null => return Err(ProviderError::NotFound(format!("Transaction {}", hash))),
+            result => decode(result.clone())?,
+        };
+        tx.try_parse().map_err(ProviderError::Invalid)
+    }
+
+    /// Call tree of a mined transaction from geth's `callTracer`.
+    async fn trace_calls(&self, hash: &str) -> Result<CallFrame, ProviderError> {
+        let params = vec![json!(hash), json!({ "tracer": "callTracer" })];
+        let res = self.fetch(Method::TraceTransaction, params).await?;
+        match &res["result"] {
+            Value::Null => Err(ProviderError::NotFound(format!("Trace of {}", hash))),
+            result => decode(result.clone()),
+        }
+    }
+
+    /// Every opcode a mined transaction ran, from the default struct logger. Memory and
+    /// storage are left out, the stack is kept to follow calls.
+    async fn trace_struct_logs(&self, hash: &str) -> Result<StructTrace, ProviderError> {
+        let config = json!({ "disableStorage": true, "enableMemory": false, "enableReturnData": false });
+        let res = self.fetch(Method::TraceTransaction, vec![json!(hash), config]).await?;
+        match &res["result"] {
+            Value::Null => Err(ProviderError::NotFound(format!("Trace of {}", hash))),
+            result => decode(result.clone()),
+        }
+    }
+
+    /// Accounts a mined transaction changed, before and after, from the `prestateTracer`.
+    async fn trace_state_diff(&self, hash: &str) -> Result<StateDiff, ProviderError> {
+        let tracer = json!({ "tracer": "prestateTracer", "tracerConfig": { "diffMode": true } });
+        let res = self.fetch(Method::TraceTransaction, vec![json!(hash), tracer]).await?;
+        match &res["result"] {
+            Value::Null => Err(ProviderError::NotFound(format!("State diff of {}", hash))),
+            result => decode(result.clone()),
+        }
+    }
+
+    /// `callTracer` traces of every transaction in a block.
+    async fn trace_block_calls(&self, number: i32) -> Result<Vec<BlockTrace>, ProviderError> {
+        let params = vec![json!(block_param(Some(number))), json!({ "tracer": "callTracer" })];
+        let res = self.fetch(Method::TraceBlockByNumber, params).await?;
+        decode(res["result"].clone())
+    }
+
+    /// Flat traces of a transaction from the `trace_` namespace.
+    async fn parity_trace_transaction(&self, hash: &str) -> Result<Vec<ParityTrace>, ProviderError> {
+        let res = self.fetch(Method::ParityTraceTransaction, vec![json!(hash)]).await?;
+        match &res["result"] {
+            Value::Null => Err(ProviderError::NotFound(format!("Trace of {}", hash))),
+            result => decode(result.clone()),
+        }
+    }
+
+    async fn parity_trace_block(&self, number: i32) -> Result<Vec<ParityTrace>, ProviderError> {
+        let params = vec![json!(block_param(Some(number)))];
+        let res = self.fetch(Method::ParityTraceBlock, params).await?;
+        decode(res["result"].clone())
+    }
+
+    async fn get_tx_receipt(&self, hash: &str) -> Result<Option<Receipt>, ProviderError> {
+        let res = self.fetch(Method::GetTxReceipt, vec![json!(hash)]).await?;
+        let receipt = match &res["result"] {
+            Value::Null => None,
+            result => Some(decode::<ReceiptHex>(result.clone())?.parse()),
+        };
+        Ok(receipt)
+    }
+
+    /// Receipts of every transaction in a block, `None` when the node doesn't
+    /// support `eth_getBlockReceipts`.
+    async fn get_block_receipts(
+        &self,
+        block_number: Option<i32>,
+    ) -> Result<Option<Vec<Receipt>>, ProviderError> {
+        let params = vec![json!(block_param(block_number))];
+        let receipts = match self.request(Method::GetBlockReceipts, params).await? {
+            None => None,
+            Some(result) => {
+                let receipts: Vec<ReceiptHex> = decode(result)?;
+                Some(receipts.iter().map(|receipt| receipt.parse()).collect())
+            }
+        };
+        Ok(receipts)
+    }
+
+    /// `None` when the node doesn't support `eth_chainId`.
+    async fn chain_id(&self) -> Result<Option<u64>, ProviderError> {
+        let id = self
+            .request(Method::ChainId, vec![])
+            .await?
+            .and_then(|result| {
+                result
+                    .as_str()
+                    .map(|result| hex_to_decimals(result, true) as u64)
+            });
+        Ok(id)
+    }
+
+    /// Network id as a decimal string, which matches the chain id on most networks.
+    async fn net_version(&self) -> Result<Option<u64>, ProviderError> {
+        let id = self
+            .request(Method::NetVersion, vec![])
+            .await?
+            .and_then(|result| {
+                result
+                    .as_str()
+                    .and_then(|result| result.parse::<u64>().ok())
+            });
+        Ok(id)
+    }
+}
+
+#[async_trait]
+impl<P: Provider + ?Sized> Provider for Box<P> {
+    fn endpoint(&self) -> String {
+        (**self).endpoint()
+    }
+
+    async fn send(&self, request: &Value) -> Result<Value, ProviderError> {
+        (**self).send(request).await
+    }
+
+    async fn call(&self, method: Method, params: Vec<Value>) -> Result<Value, ProviderError> {
+        (**self).call(method, params).await
+    }
+}
+
+/// Picks the transport from the endpoint: `http(s)://`, `ws(s)://`, `ipc://<path>`
+/// (or any path ending in `.ipc`) and `fixture://<path>` for recorded responses.
+pub fn connect(url: &str) -> Result<Box<dyn Provider>, ProviderError> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return Ok(Box::new(HttpProvider::new(url)));
+    }
+    if url.starts_with("ws://") || url.starts_with("wss://") {
+        return Ok(Box::new(WsProvider::new(url)));
+    }
+    if let Some(path) = url.strip_prefix("fixture://") {
+        return Ok(Box::new(FixtureProvider::from_file(path)?));
+    }
+    if url.starts_with("ipc://") || url.ends_with(".ipc") {
+        #[cfg(unix)]
+        return Ok(Box::new(IpcProvider::new(url)));
+        #[cfg(not(unix))]
+        return Err(ProviderError::Invalid(format!(
+            "IPC endpoints are only supported on unix: {}",
+            url
+        )));
+    }
+    Err(ProviderError::Invalid(format!(
+        "Unsupported endpoint {}",
+        url
+    )))
+}
+
+/// Provider for a profile: its only endpoint, or all of them behind its strategy.
+pub fn from_profile(profile: &Profile) -> Result<Box<dyn Provider>, ProviderError> {
+    let mut endpoints = profile
+        .endpoints()
+        .iter()
+        .map(|url| connect(url))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match endpoints.len() {
+        1 => Ok(endpoints.remove(0)),
+        _ => Ok(Box::new(MultiProvider::new(
+            endpoints,
+            profile.strategy,
+            profile.quorum(),
+        ))),
+    }
+}
@@ -0,0 +1,226 @@
+use super::{Provider, ProviderError};
+use crate::config::Strategy;
+use async_trait::async_trait;
+use colored::Colorize;
+use futures::future::{join_all, select_ok};
+use serde_json::{json, Value};
+use std::{fmt, sync::Mutex};
+
+static DISAGREEMENTS: Mutex<Vec<Disagreement>> = Mutex::new(vec![]);
+
+/// Read methods whose results are compared across endpoints with the quorum strategy.
+const QUORUM_METHODS: &[&str] = &[
+    "eth_chainId",
+    "eth_getBalance",
+    "eth_getTransactionCount",
+    "eth_getBlockByHash",
+    "eth_getBlockByNumber",
+    "eth_getBlockTransactionCountByHash",
+    "eth_getBlockTransactionCountByNumber",
+    "eth_getBlockReceipts",
+    "eth_getCode",
+    "eth_getStorageAt",
+    "eth_call",
+    "eth_getProof",
+    "eth_getTransactionByHash",
+    "eth_getTransactionReceipt",
+];
+
+#[derive(Debug)]
+enum SendError {
+    Transport(ProviderError),
+    /// The endpoint answered with a JSON-RPC error.
+    Rpc(Value),
+}
+
+/// Endpoints that answered a quorum read differently.
+#[derive(Debug, Clone)]
+pub struct Disagreement {
+    pub method: String,
+    pub params: Value,
+    /// Distinct answers along with the endpoints that returned them.
+    pub answers: Vec<(Value, Vec<String>)>,
+    pub quorum_reached: bool,
+}
+
+impl fmt::Display for Disagreement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} {}", self.method, self.params)?;
+        for (answer, endpoints) in &self.answers {
+            let mut answer = answer.to_string();
+            if answer.chars().count() > 80 {
+                answer = format!("{}...", answer.chars().take(80).collect::<String>());
+            }
+            writeln!(f, "  {} <- {}", answer, endpoints.join(", "))?;
+        }
+        match self.quorum_reached {
+            true => write!(f, "  quorum reached"),
+            false => write!(f, "  quorum NOT reached"),
+        }
+    }
+}
+
+/// Disagreements recorded during this session.
+pub fn disagreements() -> Vec<Disagreement> {
+    DISAGREEMENTS
+        .lock()
+        .map(|list| list.clone())
+        .unwrap_or_default()
+}
+
+fn rpc_error(message: String) -> Value {
+    json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": -32000, "message": message } })
+}
+
+async fn send_one(provider: &dyn Provider, request: &Value) -> Result<Value, SendError> {
+    let response = provider.send(request).await.map_err(SendError::Transport)?;
+    if response.get("error").is_some() {
+        return Err(SendError::Rpc(response));
+    }
+    Ok(response)
+}
+
+fn finish(result: Result<Value, SendError>) -> Result<Value, ProviderError> {
+    match result {
+        Ok(response) | Err(SendError::Rpc(response)) => Ok(response),
+        Err(SendError::Transport(e)) => Err(e),
+    }
+}
+
+/// Several endpoints behind one of the profile strategies.
+pub struct MultiProvider {
+    endpoints: Vec<Box<dyn Provider>>,
+    strategy: Strategy,
+    quorum: usize,
+}
+
+impl MultiProvider {
+    pub fn new(
+        endpoints: Vec<Box<dyn Provider>>,
+        strategy: Strategy,
+        quorum: usize,
+    ) -> MultiProvider {
+        MultiProvider {
+            endpoints,
+            strategy,
+            quorum,
+        }
+    }
+
+    async fn failover(&self, request: &Value) -> Result<Value, ProviderError> {
+        let mut last = Err(SendError::Rpc(rpc_error(
+            "No endpoint configured".to_string(),
+        )));
+        for provider in &self.endpoints {
+            last = send_one(provider.as_ref(), request).await;
+            if last.is_ok() {
+                break;
+            }
+        }
+        finish(last)
+    }
+
+    async fn fastest(&self, request: &Value) -> Result<Value, ProviderError> {
+        let requests = self
+            .endpoints
+            .iter()
+            .map(|provider| Box::pin(send_one(provider.as_ref(), request)));
+        finish(select_ok(requests).await.map(|(response, _)| response))
+    }
+
+    async fn quorum(&self, request: &Value) -> Result<Value, ProviderError> {
+        let quorum = self.quorum;
+        let responses = join_all(
+            self.endpoints
+                .iter()
+                .map(|provider| send_one(provider.as_ref(), request)),
+        )
+        .await;
+
+        let mut answers: Vec<(Value, Vec<String>)> = vec![];
+        let mut last_error = None;
+        let mut first_response = None;
+        for (provider, response) in self.endpoints.iter().zip(responses) {
+            let response = match response {
+                Ok(response) => response,
+                Err(e) => {
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+            let url = provider.endpoint();
+            let result = response.get("result").cloned().unwrap_or(Value::Null);
+            match answers.iter_mut().find(|(answer, _)| *answer == result) {
+                Some((_, urls)) => urls.push(url),
+                None => answers.push((result, vec![url])),
+            }
+            first_response.get_or_insert(response);
+        }
+
+        answers.sort_by_key(|(_, urls)| std::cmp::Reverse(urls.len()));
+        let quorum_reached = answers
+            .first()
+            .map(|(_, urls)| urls.len() >= quorum)
+            .unwrap_or(false);
+
+        if answers.len() > 1 || (!quorum_reached && !answers.is_empty()) {
+            let disagreement = Disagreement {
+                method: request["method"].as_str().unwrap_or("").to_string(),
+                params: request.get("params").cloned().unwrap_or(Value::Null),
+                answers: answers.clone(),
+                quorum_reached,
+            };
+            println!(
+                "{}",
+                format!("Warning: endpoints disagree on {}", disagreement)
+                    .bold()
+                    .yellow()
+            );
+            if let Ok(mut list) = DISAGREEMENTS.lock() {
+                list.push(disagreement);
+            }
+        }
+
+        match answers.into_iter().next() {
+            Some((result, urls)) if urls.len() >= quorum => {
+                let mut response = first_response.unwrap_or_else(|| json!({}));
+                response["result"] = result;
+                Ok(response)
+            }
+            Some((_, urls)) => Ok(rpc_error(format!(
+                "Quorum not reached: {} of {} required endpoints agreed",
+                urls.len(),
+                quorum
+            ))),
+            None => finish(Err(last_error.unwrap_or(SendError::Rpc(rpc_error(
+                "No endpoint answered".to_string(),
+            ))))),
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for MultiProvider {
+    fn endpoint(&self) -> String {
+        self.endpoints
+            .iter()
+            .map(|provider| provider.endpoint())
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    /// Sends the request to the endpoints according to the strategy.
+    async fn send(&self, request: &Value) -> Result<Value, ProviderError> {
+        let method = request.get("method").and_then(|m| m.as_str()).unwrap_or("");
+
+        match self.strategy {
+            _ if self.endpoints.len() == 1 => {
+                finish(send_one(self.endpoints[0].as_ref(), request).await)
+            }
+            Strategy::Failover => self.failover(request).await,
+            Strategy::Fastest => self.fastest(request).await,
+            Strategy::Quorum if QUORUM_METHODS.contains(&method) => self.quorum(request).await,
+            Strategy::Quorum => self.failover(request).await,
+        }
+    }
+}
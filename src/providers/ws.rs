@@ -0,0 +1,92 @@
+use super::{Provider, ProviderError};
+use crate::ratelimit;
+use async_trait::async_trait;
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::{net::TcpStream, sync::Mutex};
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// JSON-RPC over a WebSocket, connected lazily and reconnected after errors.
+///
+/// Requests get a connection wide unique id so stray messages (subscription
+/// notifications, late answers) can be skipped; one request is in flight at a time.
+pub struct WsProvider {
+    url: String,
+    socket: Mutex<Option<Socket>>,
+    next_id: AtomicU64,
+}
+
+impl WsProvider {
+    pub fn new(url: &str) -> WsProvider {
+        WsProvider {
+            url: url.to_string(),
+            socket: Mutex::new(None),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    async fn exchange(
+        socket: &mut Socket,
+        request: &Value,
+        id: u64,
+    ) -> Result<Value, ProviderError> {
+        socket.send(Message::Text(request.to_string())).await?;
+
+        while let Some(message) = socket.next().await {
+            let text = match message? {
+                Message::Text(text) => text,
+                Message::Binary(bytes) => String::from_utf8_lossy(&bytes).to_string(),
+                Message::Close(_) => break,
+                _ => continue,
+            };
+            let response: Value = serde_json::from_str(&text)?;
+            if response.get("id") == Some(&json!(id)) {
+                return Ok(response);
+            }
+        }
+
+        Err(ProviderError::Invalid(
+            "WebSocket closed before answering".to_string(),
+        ))
+    }
+}
+
+#[async_trait]
+impl Provider for WsProvider {
+    fn endpoint(&self) -> String {
+        self.url.clone()
+    }
+
+    async fn send(&self, request: &Value) -> Result<Value, ProviderError> {
+        let method = request.get("method").and_then(|m| m.as_str()).unwrap_or("");
+        ratelimit::acquire(&self.url, method).await;
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut request = request.clone();
+        let original_id = request.get("id").cloned().unwrap_or(Value::Null);
+        request["id"] = json!(id);
+
+        let mut guard = self.socket.lock().await;
+        let socket = match guard.as_mut() {
+            Some(socket) => socket,
+            None => {
+                let (socket, _) = connect_async(self.url.as_str()).await?;
+                guard.insert(socket)
+            }
+        };
+
+        match WsProvider::exchange(socket, &request, id).await {
+            Ok(mut response) => {
+                response["id"] = original_id;
+                Ok(response)
+            }
+            Err(e) => {
+                *guard = None;
+                Err(e)
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use crate::providers::{Provider, ProviderError};
+use crate::types::ResultParser;
+use crate::utils::{decode_hex, encode_hex};
+use std::fmt;
+
+/// `bytes32(uint256(keccak256('eip1967.proxy.implementation')) - 1)`
+const EIP1967_IMPLEMENTATION_SLOT: &str =
+    "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+/// `bytes32(uint256(keccak256('eip1967.proxy.admin')) - 1)`
+const EIP1967_ADMIN_SLOT: &str =
+    "0xb53127684a568b3173ae13b9f8a6016e243e63b6e8ee1178d6a717850b5d6103";
+/// `bytes32(uint256(keccak256('eip1967.proxy.beacon')) - 1)`
+const EIP1967_BEACON_SLOT: &str =
+    "0xa3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50";
+/// `keccak256('PROXIABLE')`
+const EIP1822_SLOT: &str = "0xc5f16f0fcc639fa48a6947836d9850f504798523bf8c9a3a87d5876cf622bcf7";
+/// `implementation()` of an EIP-1967 beacon.
+const BEACON_IMPLEMENTATION: &str = "0x5c60da1b";
+
+/// Runtime code of an EIP-1167 minimal proxy, around the 20 byte implementation address.
+const EIP1167_PREFIX: &str = "363d3d373d3d3d363d73";
+const EIP1167_SUFFIX: &str = "5af43d82803e903d91602b57fd5bf3";
+
+/// Proxies pointing at proxies are followed this many times at most.
+const MAX_HOPS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProxyKind {
+    Eip1967,
+    Eip1967Beacon { beacon: String },
+    Eip1822,
+    Eip1167,
+}
+
+impl fmt::Display for ProxyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProxyKind::Eip1967 => write!(f, "EIP-1967"),
+            ProxyKind::Eip1967Beacon { beacon } => write!(f, "EIP-1967 beacon {}", beacon),
+            ProxyKind::Eip1822 => write!(f, "EIP-1822 (UUPS)"),
+            ProxyKind::Eip1167 => write!(f, "EIP-1167 minimal proxy"),
+        }
+    }
+}
+
+/// A proxy and the contract it delegates to.
+#[derive(Debug, Clone)]
+pub struct ProxyHop {
+    pub proxy: String,
+    pub kind: ProxyKind,
+    pub implementation: String,
+    pub admin: Option<String>,
+}
+
+impl fmt::Display for ProxyHop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}) -> {}",
+            self.proxy, self.kind, self.implementation
+        )?;
+        if let Some(admin) = &self.admin {
+            write!(f, ", admin {}", admin)?;
+        }
+        Ok(())
+    }
+}
+
+/// Address held in the low 20 bytes of a storage word, `None` when unset.
+fn word_to_address(word: &str) -> Option<String> {
+    let bytes = decode_hex(word).ok()?;
+    if bytes.len() < 20 || bytes.iter().all(|b| *b == 0) {
+        return None;
+    }
+    Some(encode_hex(&bytes[bytes.len() - 20..]))
+}
+
+fn minimal_proxy_target(code: &str) -> Option<String> {
+    let code = code.trim_start_matches("0x").to_lowercase();
+    let target = code
+        .strip_prefix(EIP1167_PREFIX)?
+        .strip_suffix(EIP1167_SUFFIX)?;
+    match target.len() {
+        40 => Some(format!("0x{}", target)),
+        _ => None,
+    }
+}
+
+async fn read_address<P: Provider>(
+    provider: &P,
+    addr: &str,
+    slot: &str,
+    block: Option<i32>,
+) -> Result<Option<String>, ProviderError> {
+    let word = provider.get_storage_at(addr, slot, block).await?;
+    Ok(word_to_address(&word.result()))
+}
+
+/// Checks whether `addr` is a proxy, looking at the EIP-1167 bytecode first and
+/// then at the EIP-1967 and EIP-1822 storage slots.
+pub async fn detect<P: Provider>(
+    provider: &P,
+    addr: &str,
+    block: Option<i32>,
+) -> Result<Option<ProxyHop>, ProviderError> {
+    let code = provider.get_code(addr, block).await?.result();
+    if code.trim_start_matches("0x").is_empty() {
+        return Ok(None);
+    }
+
+    let hop = |kind: ProxyKind, implementation: String, admin: Option<String>| ProxyHop {
+        proxy: addr.to_string(),
+        kind,
+        implementation,
+        admin,
+    };
+
+    if let Some(implementation) = minimal_proxy_target(&code) {
+        return Ok(Some(hop(ProxyKind::Eip1167, implementation, None)));
+    }
+
+    if let Some(implementation) =
+        read_address(provider, addr, EIP1967_IMPLEMENTATION_SLOT, block).await?
+    {
+        let admin = read_address(provider, addr, EIP1967_ADMIN_SLOT, block).await?;
+        return Ok(Some(hop(ProxyKind::Eip1967, implementation, admin)));
+    }
+
+    if let Some(beacon) = read_address(provider, addr, EIP1967_BEACON_SLOT, block).await? {
+        let implementation = provider
+            .eth_call(&beacon, BEACON_IMPLEMENTATION, block)
+            .await?;
+        if let Some(implementation) = word_to_address(&implementation.result()) {
+            let admin = read_address(provider, addr, EIP1967_ADMIN_SLOT, block).await?;
+            let kind = ProxyKind::Eip1967Beacon { beacon };
+            return Ok(Some(hop(kind, implementation, admin)));
+        }
+    }
+
+    if let Some(implementation) = read_address(provider, addr, EIP1822_SLOT, block).await? {
+        return Ok(Some(hop(ProxyKind::Eip1822, implementation, None)));
+    }
+
+    Ok(None)
+}
+
+/// Follows proxies from `addr` down to the logic contract. Empty when `addr` isn't a proxy.
+pub async fn resolve<P: Provider>(
+    provider: &P,
+    addr: &str,
+    block: Option<i32>,
+) -> Result<Vec<ProxyHop>, ProviderError> {
+    let mut hops: Vec<ProxyHop> = vec![];
+    let mut current = addr.to_lowercase();
+
+    while hops.len() < MAX_HOPS {
+        let hop = match detect(provider, &current, block).await? {
+            Some(hop) => hop,
+            None => break,
+        };
+        current = hop.implementation.to_lowercase();
+        hops.push(hop);
+        if hops.iter().any(|hop| hop.proxy == current) {
+            break;
+        }
+    }
+
+    Ok(hops)
+}
+
+/// Logic contract at the end of a chain of proxies, `addr` itself when there are none.
+pub fn implementation(addr: &str, hops: &[ProxyHop]) -> String {
+    hops.last()
+        .map(|hop| hop.implementation.clone())
+        .unwrap_or_else(|| addr.to_string())
+}
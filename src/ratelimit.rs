@@ -0,0 +1,178 @@
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+static LIMITERS: Mutex<Option<HashMap<String, Limiter>>> = Mutex::new(None);
+
+/// Compute units charged by providers for common methods, anything else costs `DEFAULT_COST`.
+const COMPUTE_UNITS: &[(&str, f64)] = &[
+    ("eth_chainId", 0.0),
+    ("net_version", 0.0),
+    ("eth_blockNumber", 10.0),
+    ("eth_gasPrice", 20.0),
+    ("eth_getBalance", 19.0),
+    ("eth_getTransactionCount", 26.0),
+    ("eth_getBlockByNumber", 16.0),
+    ("eth_getBlockByHash", 16.0),
+    ("eth_getTransactionByHash", 17.0),
+    ("eth_getTransactionReceipt", 15.0),
+    ("eth_getBlockReceipts", 500.0),
+    ("eth_call", 26.0),
+    ("eth_getLogs", 75.0),
+];
+const DEFAULT_COST: f64 = 20.0;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// Requests per second.
+    pub rps: Option<f64>,
+    /// Compute units per second.
+    #[serde(rename = "computeUnits")]
+    pub compute_units: Option<f64>,
+}
+
+fn cost(method: &str) -> f64 {
+    COMPUTE_UNITS
+        .iter()
+        .find(|(name, _)| *name == method)
+        .map(|(_, cost)| *cost)
+        .unwrap_or(DEFAULT_COST)
+}
+
+/// Token bucket refilled at `rate` tokens per second, holding at most one second of tokens.
+/// Tokens may go negative: callers reserve their share up front and wait it out,
+/// which keeps concurrent tasks in FIFO order.
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl Bucket {
+    fn new(rate: f64) -> Bucket {
+        Bucket {
+            rate,
+            tokens: rate,
+            last: Instant::now(),
+        }
+    }
+
+    /// Takes `amount` tokens and returns how long the caller must wait for them.
+    fn reserve(&mut self, amount: f64) -> Duration {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.last = now;
+        self.tokens -= amount;
+
+        match self.tokens < 0.0 {
+            true => Duration::from_secs_f64(-self.tokens / self.rate),
+            false => Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LimiterStats {
+    pub requests: u64,
+    pub delayed: u64,
+    pub total_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl fmt::Display for LimiterStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let avg = match self.delayed {
+            0 => Duration::ZERO,
+            delayed => self.total_delay / delayed as u32,
+        };
+        write!(
+            f,
+            "{} requests, {} delayed, {} ms total delay, {} ms avg, {} ms max",
+            self.requests,
+            self.delayed,
+            self.total_delay.as_millis(),
+            avg.as_millis(),
+            self.max_delay.as_millis()
+        )
+    }
+}
+
+#[derive(Debug)]
+struct Limiter {
+    requests: Option<Bucket>,
+    compute_units: Option<Bucket>,
+    stats: LimiterStats,
+}
+
+fn limit_for(url: &str) -> RateLimit {
+    let profile = &Config::global().profile;
+    profile
+        .rate_limits
+        .get(url)
+        .copied()
+        .or(profile.rate_limit)
+        .unwrap_or_default()
+}
+
+/// Waits until `url` has budget for `method`. Budgets are shared by every task in the process.
+pub async fn acquire(url: &str, method: &str) {
+    let delay = {
+        let mut limiters = match LIMITERS.lock() {
+            Ok(limiters) => limiters,
+            Err(_) => return,
+        };
+        let limiter = limiters
+            .get_or_insert_with(HashMap::new)
+            .entry(url.to_string())
+            .or_insert_with(|| {
+                let limit = limit_for(url);
+                Limiter {
+                    requests: limit.rps.filter(|rps| *rps > 0.0).map(Bucket::new),
+                    compute_units: limit.compute_units.filter(|cu| *cu > 0.0).map(Bucket::new),
+                    stats: LimiterStats::default(),
+                }
+            });
+
+        let mut delay = Duration::ZERO;
+        if let Some(bucket) = limiter.requests.as_mut() {
+            delay = delay.max(bucket.reserve(1.0));
+        }
+        if let Some(bucket) = limiter.compute_units.as_mut() {
+            delay = delay.max(bucket.reserve(cost(method)));
+        }
+
+        limiter.stats.requests += 1;
+        if !delay.is_zero() {
+            limiter.stats.delayed += 1;
+            limiter.stats.total_delay += delay;
+            limiter.stats.max_delay = limiter.stats.max_delay.max(delay);
+        }
+        delay
+    };
+
+    if !delay.is_zero() {
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Per endpoint limiter statistics for this session.
+pub fn stats() -> Vec<(String, LimiterStats)> {
+    let limiters = match LIMITERS.lock() {
+        Ok(limiters) => limiters,
+        Err(_) => return vec![],
+    };
+    let mut stats: Vec<(String, LimiterStats)> = limiters
+        .iter()
+        .flatten()
+        .map(|(url, limiter)| (url.clone(), limiter.stats.clone()))
+        .collect();
+    stats.sort_by(|a, b| a.0.cmp(&b.0));
+    stats
+}
@@ -0,0 +1,162 @@
+use crate::abi::{AbiItem, DecodedCall, Token};
+use crate::providers::{block_param, Provider, ProviderError};
+use crate::types::{Method, Transaction};
+use crate::utils::{decode_hex, encode_hex, format_label_and_value};
+use serde_json::{json, Map, Value};
+use std::fmt;
+
+/// Selector of `Error(string)`, what `require(cond, "reason")` and `revert("reason")` throw.
+pub const ERROR_SELECTOR: &str = "0x08c379a0";
+/// Selector of `Panic(uint256)`, thrown by failed asserts and checked arithmetic.
+pub const PANIC_SELECTOR: &str = "0x4e487b71";
+
+/// What a panic code means, as listed in the solidity docs.
+pub fn panic_meaning(code: u64) -> &'static str {
+    match code {
+        0x00 => "generic compiler inserted panic",
+        0x01 => "assert failed",
+        0x11 => "arithmetic overflow or underflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "conversion to an invalid enum value",
+        0x22 => "incorrectly encoded storage byte array",
+        0x31 => "pop() on an empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "too much memory allocated",
+        0x51 => "call to a zero initialized internal function",
+        _ => "unknown panic code",
+    }
+}
+
+/// Data and message of the error a replayed call failed with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Revert {
+    pub data: Vec<u8>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum RevertReason {
+    /// No revert data, e.g. `revert()` or running out of gas. Holds the node's message.
+    Empty(String),
+    Error(String),
+    Panic(u64),
+    Custom(DecodedCall),
+    /// A selector no ABI or known signature decodes.
+    Unknown(String),
+}
+
+impl RevertReason {
+    /// Decodes revert data, trying `candidates` (errors from an ABI or the signature
+    /// database) for custom errors.
+    pub fn decode(revert: &Revert, candidates: &[AbiItem]) -> RevertReason {
+        let data = &revert.data;
+        if data.len() < 4 {
+            return RevertReason::Empty(revert.message.clone());
+        }
+        let selector = encode_hex(&data[..4]);
+        let body = &data[4..];
+        match selector.as_str() {
+            ERROR_SELECTOR => {
+                let reason = AbiItem::from_signature("error", "Error(string)")
+                    .and_then(|item| item.decode(data));
+                let token = reason.ok().and_then(|call| call.params.into_iter().next());
+                if let Some((_, _, Token::String(reason))) = token {
+                    return RevertReason::Error(reason);
+                }
+            }
+            PANIC_SELECTOR if body.len() == 32 => {
+                // Codes above u64 don't exist, they show up as unknown.
+                let code = match body[..24].iter().all(|byte| *byte == 0) {
+                    true => u64::from_be_bytes(body[24..].try_into().unwrap()),
+                    false => u64::MAX,
+                };
+                return RevertReason::Panic(code);
+            }
+            _ => {}
+        }
+
+        let input = encode_hex(data);
+        match DecodedCall::decode(&input, candidates) {
+            Some(call) => RevertReason::Custom(call),
+            None => RevertReason::Unknown(input),
+        }
+    }
+
+    /// Selector of the revert data, used to look up custom errors.
+    pub fn selector(revert: &Revert) -> Option<String> {
+        revert.data.get(..4).map(encode_hex)
+    }
+}
+
+impl fmt::Display for RevertReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = "Revert Reason";
+        match self {
+            RevertReason::Empty(message) => {
+                let reason = format!("no revert data ({})", message);
+                write!(f, "{}", format_label_and_value(label, &reason))
+            }
+            RevertReason::Error(reason) => write!(f, "{}", format_label_and_value(label, reason)),
+            RevertReason::Panic(code) => {
+                let reason = format!("Panic(0x{:02x}): {}", code, panic_meaning(*code));
+                write!(f, "{}", format_label_and_value(label, &reason))
+            }
+            RevertReason::Custom(call) => {
+                let error = format!("{} [{}]", call.signature, call.selector);
+                write!(f, "{}", format_label_and_value(label, &error))?;
+                for (name, kind, token) in &call.params {
+                    let label = format!("  {} ({})", name, kind);
+                    write!(f, "{}", format_label_and_value(&label, &token.to_string()))?;
+                }
+                write!(f, "")
+            }
+            RevertReason::Unknown(data) => {
+                let reason = format!("unknown error {} (data {})", &data[..10], data);
+                write!(f, "{}", format_label_and_value(label, &reason))
+            }
+        }
+    }
+}
+
+/// Re-runs a mined transaction with `eth_call` on the state of its parent block, with
+/// its sender, recipient, value, calldata and gas limit. `None` when the call goes
+/// through, which happens when earlier transactions of the block caused the failure.
+pub async fn replay<P: Provider>(
+    provider: &P,
+    tx: &Transaction,
+) -> Result<Option<Revert>, ProviderError> {
+    let number = match tx.block_number {
+        Some(number) => number,
+        None => return Err(ProviderError::Invalid("transaction is pending".to_string())),
+    };
+    let mut call = Map::new();
+    call.insert("from".to_string(), json!(tx.from));
+    if let Some(to) = &tx.to {
+        call.insert("to".to_string(), json!(to));
+    }
+    call.insert("value".to_string(), json!(format!("0x{:x}", tx.value)));
+    call.insert("data".to_string(), json!(tx.input));
+    call.insert("gas".to_string(), json!(format!("0x{:x}", tx.gas)));
+    let params = vec![Value::Object(call), json!(block_param(Some(number - 1)))];
+
+    let res = provider.call(Method::Call, params).await?;
+    let error = match res.get("error") {
+        Some(error) => error,
+        None => return Ok(None),
+    };
+    // geth puts the data in `data`, some clients nest it one level down.
+    let data = match &error["data"] {
+        Value::String(data) => data.clone(),
+        Value::Object(data) => data
+            .get("data")
+            .and_then(|data| data.as_str())
+            .unwrap_or("")
+            .to_string(),
+        _ => String::new(),
+    };
+
+    Ok(Some(Revert {
+        data: decode_hex(&data).unwrap_or_default(),
+        message: error["message"].as_str().unwrap_or("").to_string(),
+    }))
+}
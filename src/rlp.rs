@@ -0,0 +1,147 @@
+use std::fmt;
+
+/// A decoded RLP item: a byte string or a list of items.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rlp {
+    Bytes(Vec<u8>),
+    List(Vec<Rlp>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RlpError {
+    UnexpectedEnd,
+    TrailingBytes,
+    /// A length or short string encoded in a longer form than needed.
+    NonCanonical,
+    ExpectedBytes,
+    ExpectedList,
+}
+
+impl fmt::Display for RlpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RlpError::UnexpectedEnd => write!(f, "RLP input ends early"),
+            RlpError::TrailingBytes => write!(f, "RLP input has trailing bytes"),
+            RlpError::NonCanonical => write!(f, "RLP item is not canonically encoded"),
+            RlpError::ExpectedBytes => write!(f, "expected an RLP string, got a list"),
+            RlpError::ExpectedList => write!(f, "expected an RLP list, got a string"),
+        }
+    }
+}
+
+fn encode_length(len: usize, offset: u8) -> Vec<u8> {
+    if len < 56 {
+        return vec![offset + len as u8];
+    }
+    let bytes = len.to_be_bytes();
+    let bytes = trim_leading_zeros(&bytes);
+    let mut out = vec![offset + 55 + bytes.len() as u8];
+    out.extend_from_slice(bytes);
+    out
+}
+
+fn trim_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+/// Reads the big endian length that follows a long form prefix.
+fn read_length(data: &[u8], len_of_len: usize) -> Result<usize, RlpError> {
+    let bytes = data.get(..len_of_len).ok_or(RlpError::UnexpectedEnd)?;
+    if bytes.first() == Some(&0) || len_of_len > std::mem::size_of::<usize>() {
+        return Err(RlpError::NonCanonical);
+    }
+    let len = bytes.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize);
+    if len < 56 {
+        return Err(RlpError::NonCanonical);
+    }
+    Ok(len)
+}
+
+impl Rlp {
+    /// An unsigned integer, big endian without leading zeros.
+    pub fn uint(value: &[u8]) -> Rlp {
+        Rlp::Bytes(trim_leading_zeros(value).to_vec())
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Rlp::Bytes(bytes) if bytes.len() == 1 && bytes[0] < 0x80 => bytes.clone(),
+            Rlp::Bytes(bytes) => {
+                let mut out = encode_length(bytes.len(), 0x80);
+                out.extend_from_slice(bytes);
+                out
+            }
+            Rlp::List(items) => {
+                let payload: Vec<u8> = items.iter().flat_map(|item| item.encode()).collect();
+                let mut out = encode_length(payload.len(), 0xc0);
+                out.extend(payload);
+                out
+            }
+        }
+    }
+
+    /// Decodes exactly one item spanning the whole input.
+    pub fn decode(data: &[u8]) -> Result<Rlp, RlpError> {
+        let (item, rest) = Rlp::decode_prefix(data)?;
+        match rest.is_empty() {
+            true => Ok(item),
+            false => Err(RlpError::TrailingBytes),
+        }
+    }
+
+    /// Decodes the first item of `data` and returns it with the remaining bytes.
+    fn decode_prefix(data: &[u8]) -> Result<(Rlp, &[u8]), RlpError> {
+        let prefix = *data.first().ok_or(RlpError::UnexpectedEnd)?;
+        let data = &data[1..];
+
+        let (is_list, len, data) = match prefix {
+            0x00..=0x7f => return Ok((Rlp::Bytes(vec![prefix]), data)),
+            0x80..=0xb7 => (false, (prefix - 0x80) as usize, data),
+            0xb8..=0xbf => {
+                let len_of_len = (prefix - 0xb7) as usize;
+                (false, read_length(data, len_of_len)?, &data[len_of_len..])
+            }
+            0xc0..=0xf7 => (true, (prefix - 0xc0) as usize, data),
+            0xf8..=0xff => {
+                let len_of_len = (prefix - 0xf7) as usize;
+                (true, read_length(data, len_of_len)?, &data[len_of_len..])
+            }
+        };
+
+        if data.len() < len {
+            return Err(RlpError::UnexpectedEnd);
+        }
+        let (payload, rest) = data.split_at(len);
+
+        if !is_list {
+            if len == 1 && payload[0] < 0x80 {
+                return Err(RlpError::NonCanonical);
+            }
+            return Ok((Rlp::Bytes(payload.to_vec()), rest));
+        }
+
+        let mut items = vec![];
+        let mut payload = payload;
+        while !payload.is_empty() {
+            let (item, next) = Rlp::decode_prefix(payload)?;
+            items.push(item);
+            payload = next;
+        }
+        Ok((Rlp::List(items), rest))
+    }
+
+    pub fn as_bytes(&self) -> Result<&[u8], RlpError> {
+        match self {
+            Rlp::Bytes(bytes) => Ok(bytes),
+            Rlp::List(_) => Err(RlpError::ExpectedBytes),
+        }
+    }
+
+    pub fn as_list(&self) -> Result<&[Rlp], RlpError> {
+        match self {
+            Rlp::List(items) => Ok(items),
+            Rlp::Bytes(_) => Err(RlpError::ExpectedList),
+        }
+    }
+}
@@ -0,0 +1,297 @@
+use crate::chains::ChainInfo;
+use crate::providers::{Provider, ProviderError};
+use crate::trace::{self, InternalTransfer};
+use crate::types::{Block, Transaction};
+use crate::utils::{data_dir, format_label_and_value, format_units};
+use futures::{stream, StreamExt};
+use std::{collections::HashMap, fmt, fs, path::PathBuf};
+
+const STATE_FILE: &str = "scan_state.json";
+const CHECKPOINT_EVERY: i32 = 25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    In,
+    Out,
+    SelfTransfer,
+}
+
+impl Direction {
+    pub fn value(&self) -> &'static str {
+        match self {
+            Direction::In => "IN",
+            Direction::Out => "OUT",
+            Direction::SelfTransfer => "SELF",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScanMatch {
+    pub block_number: i32,
+    pub timestamp: u32,
+    pub direction: Direction,
+    pub tx: Transaction,
+    /// Set when the address was touched by a value transfer inside `tx` rather than by `tx` itself.
+    pub internal: Option<InternalTransfer>,
+}
+
+impl fmt::Display for ScanMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (from, to, value) = match &self.internal {
+            Some(transfer) => (transfer.from.clone(), transfer.to.clone(), transfer.value),
+            None => (
+                self.tx.from.clone(),
+                self.tx
+                    .to
+                    .clone()
+                    .unwrap_or_else(|| "Contract creation".to_string()),
+                self.tx.value,
+            ),
+        };
+        let gas_price = match self.tx.gas_price {
+            Some(val) => val.to_string(),
+            None => "??".to_string(),
+        };
+
+        let value_label = format!("Value ({})", ChainInfo::current().symbol);
+        let mut lines = vec![
+            ("Block", self.block_number.to_string()),
+            ("Timestamp", self.timestamp.to_string()),
+            ("Direction", self.direction.value().to_string()),
+            ("Tx", self.tx.hash.clone()),
+            ("From", from),
+            ("To", to),
+            (
+                value_label.as_str(),
+                format_units(value, ChainInfo::current().decimals),
+            ),
+            ("Gas", self.tx.gas.to_string()),
+            ("Gas Price", gas_price),
+        ];
+        if let Some(transfer) = &self.internal {
+            lines.insert(
+                3,
+                ("Transfer", format!("internal ({})", transfer.call_type)),
+            );
+        }
+
+        for (label, value) in lines {
+            write!(f, "{}", format_label_and_value(label, &value))?;
+        }
+
+        write!(f, "")
+    }
+}
+
+#[derive(Debug)]
+pub struct ScanReport {
+    pub from_block: i32,
+    pub last_scanned: Option<i32>,
+    pub matches: Vec<ScanMatch>,
+}
+
+/// Walks a block range and collects every transaction touching one of `addresses`.
+pub struct Scanner {
+    addresses: Vec<String>,
+    concurrency: usize,
+    internal: bool,
+}
+
+impl Scanner {
+    pub fn new(addresses: Vec<String>, concurrency: usize) -> Scanner {
+        let mut addresses: Vec<String> = addresses
+            .iter()
+            .map(|addr| addr.trim().to_lowercase())
+            .filter(|addr| !addr.is_empty())
+            .collect();
+        addresses.sort();
+        addresses.dedup();
+
+        Scanner {
+            addresses,
+            concurrency: concurrency.max(1),
+            internal: false,
+        }
+    }
+
+    /// Also matches ETH moved by contracts, traced with `debug_traceBlockByNumber` or
+    /// `trace_block`. Every block in the range costs an extra trace call.
+    pub fn with_internal_transfers(mut self) -> Scanner {
+        self.internal = true;
+        self
+    }
+
+    async fn fetch_block<P: Provider>(
+        &self,
+        provider: &P,
+        number: i32,
+    ) -> Result<(Block, Vec<InternalTransfer>), ProviderError> {
+        let block = provider.get_block(None, Some(number)).await?;
+        let transfers = match self.internal {
+            true => trace::block_transfers(provider, &block).await?,
+            false => vec![],
+        };
+        Ok((block, transfers))
+    }
+
+    /// Scans `from..=to`, fetching up to `concurrency` blocks at a time. Blocks are
+    /// processed in order so the checkpoint always points to a fully scanned block.
+    pub async fn scan<P: Provider>(
+        &self,
+        provider: &P,
+        from: i32,
+        to: i32,
+    ) -> Result<ScanReport, ProviderError> {
+        let key = self.key(provider).await?;
+        let mut report = ScanReport {
+            from_block: from,
+            last_scanned: None,
+            matches: vec![],
+        };
+
+        let mut blocks = stream::iter(from..=to)
+            .map(|number| self.fetch_block(provider, number))
+            .buffered(self.concurrency);
+
+        while let Some(block) = blocks.next().await {
+            let (block, transfers) = match block {
+                Ok(block) => block,
+                // Without traces nothing more can be scanned, resuming wouldn't help.
+                Err(e) if self.internal && e.is_unsupported_method() => return Err(e),
+                Err(e) => {
+                    Scanner::save_checkpoint(&key, report.last_scanned);
+                    return Err(e);
+                }
+            };
+
+            report.matches.extend(self.matches_in_block(&block));
+            report
+                .matches
+                .extend(self.internal_matches(&block, &transfers));
+            report.last_scanned = Some(block.number);
+
+            if (block.number - from) % CHECKPOINT_EVERY == 0 {
+                Scanner::save_checkpoint(&key, report.last_scanned);
+            }
+        }
+
+        Scanner::save_checkpoint(&key, report.last_scanned);
+        Ok(report)
+    }
+
+    pub fn matches_in_block(&self, block: &Block) -> Vec<ScanMatch> {
+        block
+            .transactions
+            .iter()
+            .filter_map(|tx| {
+                let direction = self.direction(Some(&tx.from), tx.to.as_ref())?;
+
+                Some(ScanMatch {
+                    block_number: block.number,
+                    timestamp: block.timestamp,
+                    direction,
+                    tx: tx.clone(),
+                    internal: None,
+                })
+            })
+            .collect()
+    }
+
+    pub fn internal_matches(
+        &self,
+        block: &Block,
+        transfers: &[InternalTransfer],
+    ) -> Vec<ScanMatch> {
+        transfers
+            .iter()
+            .filter_map(|transfer| {
+                let direction = self.direction(Some(&transfer.from), Some(&transfer.to))?;
+                let tx = block
+                    .transactions
+                    .iter()
+                    .find(|tx| tx.hash.eq_ignore_ascii_case(&transfer.tx_hash))?;
+
+                Some(ScanMatch {
+                    block_number: block.number,
+                    timestamp: block.timestamp,
+                    direction,
+                    tx: tx.clone(),
+                    internal: Some(transfer.clone()),
+                })
+            })
+            .collect()
+    }
+
+    fn direction(&self, from: Option<&String>, to: Option<&String>) -> Option<Direction> {
+        match (self.is_watched(from), self.is_watched(to)) {
+            (true, true) => Some(Direction::SelfTransfer),
+            (true, false) => Some(Direction::Out),
+            (false, true) => Some(Direction::In),
+            (false, false) => None,
+        }
+    }
+
+    fn is_watched(&self, addr: Option<&String>) -> bool {
+        match addr {
+            Some(addr) => self.addresses.contains(&addr.to_lowercase()),
+            None => false,
+        }
+    }
+
+    /// Checkpoints are kept per chain, endpoint and address set, in any order.
+    async fn key<P: Provider>(&self, provider: &P) -> Result<String, ProviderError> {
+        let chain = match provider.chain_id().await? {
+            Some(id) => Some(id),
+            None => provider.net_version().await?,
+        };
+        let mut addresses = self.addresses.clone();
+        addresses.sort();
+
+        Ok(format!(
+            "{}@{}:{}",
+            chain.map_or("?".to_string(), |id| id.to_string()),
+            provider.endpoint(),
+            addresses.join(",")
+        ))
+    }
+
+    fn state_path() -> PathBuf {
+        data_dir().join(STATE_FILE)
+    }
+
+    fn load_state() -> HashMap<String, i32> {
+        fs::read_to_string(Scanner::state_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Last block fully scanned for this address set on `provider`, if a previous scan was
+    /// interrupted or finished.
+    pub async fn checkpoint<P: Provider>(
+        &self,
+        provider: &P,
+    ) -> Result<Option<i32>, ProviderError> {
+        let key = self.key(provider).await?;
+        Ok(Scanner::load_state().get(&key).copied())
+    }
+
+    fn save_checkpoint(key: &str, last_scanned: Option<i32>) {
+        let last_scanned = match last_scanned {
+            Some(val) => val,
+            None => return,
+        };
+
+        let mut state = Scanner::load_state();
+        state.insert(key.to_string(), last_scanned);
+
+        let path = Scanner::state_path();
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        if let Ok(content) = serde_json::to_string_pretty(&state) {
+            let _ = fs::write(path, content);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use crate::rlp::Rlp;
+use crate::utils::{encode_hex, format_label_and_value, keccak256};
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use std::fmt;
+
+/// Signature of a transaction, with the parity of the recovery point as 0 or 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxSignature {
+    pub y_parity: u8,
+    pub r: Vec<u8>,
+    pub s: Vec<u8>,
+}
+
+fn to_u64(bytes: &[u8]) -> Result<u64, String> {
+    if bytes.len() > 8 {
+        return Err(format!("v of {} bytes", bytes.len()));
+    }
+    Ok(bytes.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64))
+}
+
+/// Splits a signed transaction, as encoded on chain, into the hash its sender signed
+/// and the signature.
+///
+/// The signed fields are the encoded ones minus the trailing v, r and s. Legacy transactions
+/// with an EIP-155 `v` also sign the chain id followed by two empty fields.
+pub fn signing_hash(encoded: &[u8]) -> Result<([u8; 32], TxSignature), String> {
+    let (tx_type, payload) = match encoded.first() {
+        None => return Err("empty transaction".to_string()),
+        Some(byte) if *byte >= 0xc0 => (None, encoded),
+        Some(byte) if *byte <= 0x7f => (Some(*byte), &encoded[1..]),
+        Some(byte) => return Err(format!("unknown transaction envelope 0x{:02x}", byte)),
+    };
+    let rlp = Rlp::decode(payload).map_err(|e| e.to_string())?;
+    let items = rlp.as_list().map_err(|e| e.to_string())?;
+    if items.len() < 4 {
+        return Err(format!("transaction with {} fields", items.len()));
+    }
+
+    let (fields, signature) = items.split_at(items.len() - 3);
+    let mut fields = fields.to_vec();
+    let v = to_u64(signature[0].as_bytes().map_err(|e| e.to_string())?)?;
+    let y_parity = match (tx_type, v) {
+        (Some(_), 0 | 1) => v as u8,
+        (None, 27 | 28) => (v - 27) as u8,
+        (None, v) if v >= 35 => {
+            let chain_id = (v - 35) / 2;
+            fields.push(Rlp::uint(&chain_id.to_be_bytes()));
+            fields.push(Rlp::Bytes(vec![]));
+            fields.push(Rlp::Bytes(vec![]));
+            ((v - 35) % 2) as u8
+        }
+        (_, v) => return Err(format!("invalid v {}", v)),
+    };
+    let signature = TxSignature {
+        y_parity,
+        r: signature[1].as_bytes().map_err(|e| e.to_string())?.to_vec(),
+        s: signature[2].as_bytes().map_err(|e| e.to_string())?.to_vec(),
+    };
+
+    let mut preimage: Vec<u8> = tx_type.into_iter().collect();
+    preimage.extend(Rlp::List(fields).encode());
+    Ok((keccak256(&preimage), signature))
+}
+
+/// Address of the key that produced `signature` over `hash`, like the `ecrecover` precompile.
+pub fn recover(hash: &[u8; 32], signature: &TxSignature) -> Result<String, String> {
+    if signature.r.len() > 32 || signature.s.len() > 32 {
+        return Err("signature values over 32 bytes".to_string());
+    }
+    let mut bytes = [0u8; 64];
+    bytes[32 - signature.r.len()..32].copy_from_slice(&signature.r);
+    bytes[64 - signature.s.len()..].copy_from_slice(&signature.s);
+    let sig = Signature::from_slice(&bytes).map_err(|_| "invalid signature".to_string())?;
+
+    // Frontier transactions may have a high s, the same key recovers from its low s twin
+    // with the other parity.
+    let (sig, y_parity) = match sig.normalize_s() {
+        Some(normalized) => (normalized, signature.y_parity ^ 1),
+        None => (sig, signature.y_parity),
+    };
+    let recovery_id = RecoveryId::from_byte(y_parity).ok_or("invalid recovery id".to_string())?;
+    let key = VerifyingKey::recover_from_prehash(hash, &sig, recovery_id)
+        .map_err(|_| "signature doesn't recover a public key".to_string())?;
+
+    let point = key.to_encoded_point(false);
+    Ok(encode_hex(&keccak256(&point.as_bytes()[1..])[12..]))
+}
+
+/// Sender of a signed transaction.
+pub fn sender(encoded: &[u8]) -> Result<String, String> {
+    let (hash, signature) = signing_hash(encoded)?;
+    recover(&hash, &signature)
+}
+
+/// Hash and sender recomputed from a transaction, next to the ones the node reported.
+#[derive(Debug, Clone)]
+pub struct TxCheck {
+    pub reported_hash: String,
+    pub computed_hash: String,
+    pub reported_from: String,
+    pub recovered_from: Result<String, String>,
+}
+
+impl TxCheck {
+    pub fn new(hash: &str, from: &str, encoded: &[u8]) -> TxCheck {
+        TxCheck {
+            reported_hash: hash.to_string(),
+            computed_hash: encode_hex(&keccak256(encoded)),
+            reported_from: from.to_string(),
+            recovered_from: sender(encoded),
+        }
+    }
+
+    pub fn hash_matches(&self) -> bool {
+        self.computed_hash == self.reported_hash.to_lowercase()
+    }
+
+    pub fn sender_matches(&self) -> bool {
+        match &self.recovered_from {
+            Ok(from) => *from == self.reported_from.to_lowercase(),
+            Err(_) => false,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.hash_matches() && self.sender_matches()
+    }
+}
+
+impl fmt::Display for TxCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hash = match self.hash_matches() {
+            true => "ok".to_string(),
+            false => format!("MISMATCH, transaction hashes to {}", self.computed_hash),
+        };
+        let sender = match &self.recovered_from {
+            Ok(_) if self.sender_matches() => "ok".to_string(),
+            Ok(from) => format!("MISMATCH, signature recovers to {}", from),
+            Err(e) => format!("FAILED, {}", e),
+        };
+        write!(f, "{}", format_label_and_value("Computed Hash", &hash))?;
+        write!(f, "{}", format_label_and_value("Recovered Sender", &sender))
+    }
+}
@@ -0,0 +1,215 @@
+use crate::abi::AbiItem;
+use crate::utils::{data_dir, encode_hex, keccak256};
+use serde_json::Value;
+use std::{collections::HashMap, fs, path::PathBuf};
+
+/// Commonly used function signatures shipped with the CLI so decoding works out of the box.
+const BUNDLED_SIGNATURES: &[&str] = &[
+    "transfer(address,uint256)",
+    "transferFrom(address,address,uint256)",
+    "approve(address,uint256)",
+    "increaseAllowance(address,uint256)",
+    "decreaseAllowance(address,uint256)",
+    "permit(address,address,uint256,uint256,uint8,bytes32,bytes32)",
+    "mint(address,uint256)",
+    "burn(uint256)",
+    "burnFrom(address,uint256)",
+    "deposit()",
+    "deposit(uint256)",
+    "withdraw(uint256)",
+    "safeTransferFrom(address,address,uint256)",
+    "safeTransferFrom(address,address,uint256,bytes)",
+    "safeTransferFrom(address,address,uint256,uint256,bytes)",
+    "safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)",
+    "setApprovalForAll(address,bool)",
+    "transferOwnership(address)",
+    "renounceOwnership()",
+    "upgradeTo(address)",
+    "upgradeToAndCall(address,bytes)",
+    "multicall(bytes[])",
+    "multicall(uint256,bytes[])",
+    "aggregate((address,bytes)[])",
+    "execute(bytes,bytes[])",
+    "execute(bytes,bytes[],uint256)",
+    "swapExactTokensForTokens(uint256,uint256,address[],address,uint256)",
+    "swapTokensForExactTokens(uint256,uint256,address[],address,uint256)",
+    "swapExactETHForTokens(uint256,address[],address,uint256)",
+    "swapETHForExactTokens(uint256,address[],address,uint256)",
+    "swapExactTokensForETH(uint256,uint256,address[],address,uint256)",
+    "swapTokensForExactETH(uint256,uint256,address[],address,uint256)",
+    "addLiquidity(address,address,uint256,uint256,uint256,uint256,address,uint256)",
+    "addLiquidityETH(address,uint256,uint256,uint256,address,uint256)",
+    "removeLiquidity(address,address,uint256,uint256,uint256,address,uint256)",
+    "removeLiquidityETH(address,uint256,uint256,uint256,address,uint256)",
+    "exactInputSingle((address,address,uint24,address,uint256,uint256,uint256,uint160))",
+    "exactInput((bytes,address,uint256,uint256,uint256))",
+    "exactOutputSingle((address,address,uint24,address,uint256,uint256,uint256,uint160))",
+    "exactOutput((bytes,address,uint256,uint256,uint256))",
+    "claim()",
+    "stake(uint256)",
+    "unstake(uint256)",
+    "register(string,address,uint256,bytes32,address,bytes[],bool,uint16)",
+    "commit(bytes32)",
+    "setText(bytes32,string,string)",
+    "execTransaction(address,uint256,bytes,uint8,uint256,uint256,uint256,address,address,bytes)",
+];
+
+const DB_FILE: &str = "signatures.tsv";
+
+/// Offline function signature database, keyed by 4 byte selector (`0x` prefixed).
+pub struct SignatureDb {
+    signatures: HashMap<String, Vec<String>>,
+}
+
+impl SignatureDb {
+    /// Loads the bundled signatures plus everything previously imported into the local database.
+    pub fn load() -> SignatureDb {
+        let mut db = SignatureDb {
+            signatures: HashMap::new(),
+        };
+
+        for signature in BUNDLED_SIGNATURES {
+            db.insert(None, signature);
+        }
+
+        if let Ok(content) = fs::read_to_string(SignatureDb::path()) {
+            for line in content.lines() {
+                if let Some((selector, signature)) = line.split_once('\t') {
+                    db.insert(Some(selector), signature);
+                }
+            }
+        }
+
+        db
+    }
+
+    pub fn path() -> PathBuf {
+        data_dir().join(DB_FILE)
+    }
+
+    pub fn len(&self) -> usize {
+        self.signatures.values().map(|list| list.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    pub fn lookup(&self, selector: &str) -> Vec<String> {
+        self.signatures
+            .get(&selector.to_lowercase())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Candidate ABI items for `selector`, in the order they should be tried.
+    pub fn candidates(&self, selector: &str) -> Vec<AbiItem> {
+        self.lookup(selector)
+            .iter()
+            .filter_map(|signature| AbiItem::from_signature("function", signature).ok())
+            .collect()
+    }
+
+    /// Imports a 4byte dump into the local database. Supported formats:
+    /// the 4byte.directory JSON API (`results` with `hex_signature`/`text_signature`),
+    /// a JSON object mapping selectors to signatures, or a text file with one
+    /// `<selector> <signature>` (or just `<signature>`) per line.
+    /// Returns the number of new signatures.
+    pub fn import(&mut self, path: &str) -> Result<usize, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut entries: Vec<(Option<String>, String)> = vec![];
+
+        match serde_json::from_str::<Value>(&content) {
+            Ok(json) => {
+                let results = json.get("results").unwrap_or(&json);
+                match results {
+                    Value::Array(items) => {
+                        for item in items {
+                            if let Some(text) = item.get("text_signature").and_then(|v| v.as_str())
+                            {
+                                let selector = item
+                                    .get("hex_signature")
+                                    .and_then(|v| v.as_str())
+                                    .map(|v| v.to_string());
+                                entries.push((selector, text.to_string()));
+                            }
+                        }
+                    }
+                    Value::Object(map) => {
+                        for (selector, signatures) in map {
+                            let signatures = match signatures {
+                                Value::Array(list) => list.clone(),
+                                other => vec![other.clone()],
+                            };
+                            for signature in signatures.iter().filter_map(|v| v.as_str()) {
+                                entries.push((Some(selector.clone()), signature.to_string()));
+                            }
+                        }
+                    }
+                    _ => return Err("Unsupported JSON signature dump".to_string()),
+                }
+            }
+            Err(_) => {
+                for line in content.lines().map(|line| line.trim()) {
+                    if line.is_empty() || line.starts_with('#') {
+                        continue;
+                    }
+                    match line.split_once(|c: char| c.is_whitespace() || c == ',') {
+                        Some((selector, signature)) if selector.starts_with("0x") => {
+                            entries.push((Some(selector.to_string()), signature.trim().to_string()))
+                        }
+                        _ => entries.push((None, line.to_string())),
+                    }
+                }
+            }
+        }
+
+        let mut added = 0;
+        for (selector, signature) in entries {
+            if self.insert(selector.as_deref(), &signature) {
+                added += 1;
+            }
+        }
+
+        self.save()?;
+        Ok(added)
+    }
+
+    fn insert(&mut self, selector: Option<&str>, signature: &str) -> bool {
+        let signature = signature.trim().to_string();
+        let selector = match selector {
+            Some(val) => val.trim().to_lowercase(),
+            None => encode_hex(&keccak256(signature.as_bytes())[..4]),
+        };
+        if selector.len() != 10 || !signature.contains('(') {
+            return false;
+        }
+
+        let list = self.signatures.entry(selector).or_default();
+        if list.contains(&signature) {
+            return false;
+        }
+        list.push(signature);
+        true
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = SignatureDb::path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+
+        let bundled: Vec<&str> = BUNDLED_SIGNATURES.to_vec();
+        let mut lines = vec![];
+        for (selector, signatures) in &self.signatures {
+            for signature in signatures {
+                if !bundled.contains(&signature.as_str()) {
+                    lines.push(format!("{}\t{}", selector, signature));
+                }
+            }
+        }
+        lines.sort();
+
+        fs::write(&path, lines.join("\n")).map_err(|e| e.to_string())
+    }
+}
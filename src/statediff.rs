@@ -0,0 +1,387 @@
+use crate::account::{array_slot, mapping_slot, parse_word};
+use crate::chains::ChainInfo;
+use crate::types::Transaction;
+use crate::utils::{
+    decode_hex, encode_hex, format_label_and_value, format_units, hex_to_decimals, keccak256,
+};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::{fmt, fs};
+
+/// How far past `keccak256(slot)` array and string data is looked for when labelling.
+const MAX_DATA_SLOTS: u64 = 1 << 16;
+
+/// An account as the `prestateTracer` reports it. In diff mode `post` only has the
+/// fields that changed and leaves out slots that were set to zero.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AccountState {
+    pub balance: Option<String>,
+    pub nonce: Option<u64>,
+    pub code: Option<String>,
+    #[serde(default)]
+    pub storage: BTreeMap<String, String>,
+}
+
+/// Result of the `prestateTracer` with `diffMode` on.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StateDiff {
+    pub pre: BTreeMap<String, AccountState>,
+    pub post: BTreeMap<String, AccountState>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountStatus {
+    Created,
+    Modified,
+    /// Self destructed, or emptied and pruned.
+    Deleted,
+}
+
+impl AccountStatus {
+    pub fn value(&self) -> &'static str {
+        match self {
+            AccountStatus::Created => "created",
+            AccountStatus::Modified => "modified",
+            AccountStatus::Deleted => "deleted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotChange {
+    pub slot: String,
+    /// Variable stored in the slot, from a storage layout.
+    pub label: Option<String>,
+    pub before: String,
+    pub after: String,
+}
+
+/// What a transaction changed in one account. Fields left as `None` didn't change.
+#[derive(Debug, Clone)]
+pub struct AccountDiff {
+    pub address: String,
+    pub status: AccountStatus,
+    pub balance: Option<(i128, i128)>,
+    pub nonce: Option<(u64, u64)>,
+    /// Code before and after, as bytes.
+    pub code: Option<(Vec<u8>, Vec<u8>)>,
+    pub storage: Vec<SlotChange>,
+}
+
+/// Normalizes a storage key or value to a full 32 byte word.
+fn word(value: &str) -> String {
+    match parse_word(value) {
+        Ok(word) => encode_hex(&word),
+        Err(_) => value.to_lowercase(),
+    }
+}
+
+fn zero_word() -> String {
+    encode_hex(&[0u8; 32])
+}
+
+fn quantity(value: &Option<String>) -> i128 {
+    value
+        .as_ref()
+        .map(|value| hex_to_decimals(value, true))
+        .unwrap_or(0)
+}
+
+fn code(value: &Option<String>) -> Vec<u8> {
+    value
+        .as_ref()
+        .and_then(|code| decode_hex(code).ok())
+        .unwrap_or_default()
+}
+
+impl StateDiff {
+    /// Per account changes, sorted by address.
+    pub fn accounts(&self) -> Vec<AccountDiff> {
+        let addresses: BTreeSet<&String> = self.pre.keys().chain(self.post.keys()).collect();
+        let empty = AccountState::default();
+
+        addresses
+            .into_iter()
+            .map(|address| {
+                let (pre, post) = (self.pre.get(address), self.post.get(address));
+                let status = match (pre, post) {
+                    (None, _) => AccountStatus::Created,
+                    (Some(_), None) => AccountStatus::Deleted,
+                    (Some(_), Some(_)) => AccountStatus::Modified,
+                };
+                let pre = pre.unwrap_or(&empty);
+                let post = post.unwrap_or(&empty);
+                // Unchanged fields are left out of `post`, except for deleted accounts
+                // where everything went back to zero.
+                let changed = |after: bool| after || status == AccountStatus::Deleted;
+
+                let balance = match changed(post.balance.is_some()) {
+                    true => Some((quantity(&pre.balance), quantity(&post.balance))),
+                    false => None,
+                };
+                let nonce = match changed(post.nonce.is_some()) {
+                    true => Some((pre.nonce.unwrap_or(0), post.nonce.unwrap_or(0))),
+                    false => None,
+                };
+                let code = match changed(post.code.is_some()) {
+                    true => Some((code(&pre.code), code(&post.code))),
+                    false => None,
+                };
+
+                let slots: BTreeSet<String> = pre
+                    .storage
+                    .keys()
+                    .chain(post.storage.keys())
+                    .map(|slot| word(slot))
+                    .collect();
+                let value = |state: &AccountState, slot: &String| {
+                    state
+                        .storage
+                        .iter()
+                        .find(|(key, _)| word(key) == *slot)
+                        .map(|(_, value)| word(value))
+                        .unwrap_or_else(zero_word)
+                };
+                let storage = slots
+                    .into_iter()
+                    .map(|slot| SlotChange {
+                        before: value(pre, &slot),
+                        after: value(post, &slot),
+                        label: None,
+                        slot,
+                    })
+                    .filter(|change| change.before != change.after)
+                    .collect();
+
+                AccountDiff {
+                    address: address.to_lowercase(),
+                    status,
+                    balance: balance.filter(|(before, after)| before != after),
+                    nonce: nonce.filter(|(before, after)| before != after),
+                    code: code.filter(|(before, after)| before != after),
+                    storage,
+                }
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for AccountDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chain = ChainInfo::current();
+        let mut lines = vec![(
+            "Account",
+            format!("{} ({})", self.address, self.status.value()),
+        )];
+        if let Some((before, after)) = self.balance {
+            let sign = if after >= before { "+" } else { "-" };
+            lines.push((
+                "Balance",
+                format!(
+                    "{} -> {} {} ({}{})",
+                    format_units(before, chain.decimals),
+                    format_units(after, chain.decimals),
+                    chain.symbol,
+                    sign,
+                    format_units((after - before).abs(), chain.decimals)
+                ),
+            ));
+        }
+        if let Some((before, after)) = self.nonce {
+            lines.push(("Nonce", format!("{} -> {}", before, after)));
+        }
+        if let Some((before, after)) = &self.code {
+            let code = match (before.is_empty(), after.is_empty()) {
+                (true, _) => format!("deployed ({} bytes)", after.len()),
+                (_, true) => format!("removed ({} bytes)", before.len()),
+                _ => format!("{} -> {} bytes", before.len(), after.len()),
+            };
+            lines.push(("Code", code));
+        }
+        for change in &self.storage {
+            let slot = match &change.label {
+                Some(label) => format!("{} ({})", label, change.slot),
+                None => change.slot.clone(),
+            };
+            lines.push((
+                "Slot",
+                format!("{}: {} -> {}", slot, change.before, change.after),
+            ));
+        }
+
+        for (label, value) in lines {
+            write!(f, "{}", format_label_and_value(label, &value))?;
+        }
+
+        write!(f, "")
+    }
+}
+
+/// A state variable in solc's `storageLayout` output.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StorageVariable {
+    pub label: String,
+    pub offset: u32,
+    pub slot: String,
+    #[serde(rename = "type")]
+    pub type_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageType {
+    /// inplace, mapping, dynamic_array or bytes.
+    pub encoding: String,
+    pub number_of_bytes: String,
+    /// Element type of arrays.
+    pub base: Option<String>,
+    /// Value type of mappings.
+    pub value: Option<String>,
+}
+
+/// Storage layout of a contract, as emitted by `solc --storage-layout`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StorageLayout {
+    pub storage: Vec<StorageVariable>,
+    #[serde(default)]
+    pub types: HashMap<String, StorageType>,
+}
+
+/// Distance from `base` to `slot` when `slot` comes at most `max` slots after it.
+fn slot_offset(slot: &[u8; 32], base: &[u8; 32], max: u64) -> Option<u64> {
+    let mut diff = [0u8; 32];
+    let mut borrow = 0i16;
+    for i in (0..32).rev() {
+        let mut value = slot[i] as i16 - base[i] as i16 - borrow;
+        borrow = 0;
+        if value < 0 {
+            value += 256;
+            borrow = 1;
+        }
+        diff[i] = value as u8;
+    }
+    if borrow == 1 || diff[..24].iter().any(|byte| *byte != 0) {
+        return None;
+    }
+    let offset = u64::from_be_bytes(diff[24..].try_into().unwrap());
+    (offset < max).then_some(offset)
+}
+
+impl StorageLayout {
+    /// Reads a layout file, either the bare `storageLayout` object or a whole solc
+    /// output for a single contract (`{"storageLayout": {...}}`).
+    pub fn from_file(path: &str) -> Result<StorageLayout, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let json: serde_json::Value = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        let layout = match json.get("storageLayout") {
+            Some(layout) => layout.clone(),
+            None => json,
+        };
+        serde_json::from_value(layout).map_err(|e| format!("Invalid storage layout: {}", e))
+    }
+
+    fn slots_of(&self, type_id: &str) -> u64 {
+        let bytes = self
+            .types
+            .get(type_id)
+            .and_then(|ty| ty.number_of_bytes.parse::<u64>().ok())
+            .unwrap_or(32);
+        bytes.div_ceil(32).max(1)
+    }
+
+    /// Name of what `slot` holds, trying `keys` (usually the addresses the transaction
+    /// touched) as mapping keys since mapping slots can't be reversed.
+    pub fn label(&self, slot: &str, keys: &[String]) -> Option<String> {
+        let slot = parse_word(slot).ok()?;
+        let mut names = vec![];
+        for var in &self.storage {
+            let base = match parse_word(&var.slot) {
+                Ok(base) => base,
+                Err(_) => continue,
+            };
+            let ty = match self.types.get(&var.type_id) {
+                Some(ty) => ty,
+                None => {
+                    if base == slot {
+                        names.push(var.label.clone());
+                    }
+                    continue;
+                }
+            };
+            let name = match ty.encoding.as_str() {
+                "mapping" => {
+                    let value_slots = ty.value.as_deref().map_or(1, |value| self.slots_of(value));
+                    keys.iter().find_map(|key| {
+                        let entry = mapping_slot(&base, key).ok()?;
+                        match slot_offset(&slot, &entry, value_slots)? {
+                            0 => Some(format!("{}[{}]", var.label, key)),
+                            field => Some(format!("{}[{}] (+{})", var.label, key, field)),
+                        }
+                    })
+                }
+                "dynamic_array" if slot == base => Some(format!("{}.length", var.label)),
+                "dynamic_array" => {
+                    let data = array_slot(&base, 0, 1);
+                    slot_offset(&slot, &data, MAX_DATA_SLOTS).map(|offset| {
+                        let bytes = ty
+                            .base
+                            .as_deref()
+                            .and_then(|base| self.types.get(base))
+                            .and_then(|base| base.number_of_bytes.parse::<u64>().ok())
+                            .unwrap_or(32);
+                        // Small elements are packed several to a slot.
+                        let index = match bytes {
+                            0..=16 => offset * (32 / bytes.max(1)),
+                            _ => offset / bytes.div_ceil(32),
+                        };
+                        format!("{}[{}]", var.label, index)
+                    })
+                }
+                "bytes" if slot == base => Some(var.label.clone()),
+                "bytes" => {
+                    let data = keccak256(&base);
+                    slot_offset(&slot, &data, MAX_DATA_SLOTS)
+                        .map(|offset| format!("{} (data +{})", var.label, offset))
+                }
+                _ => match slot_offset(&slot, &base, self.slots_of(&var.type_id)) {
+                    Some(0) => Some(var.label.clone()),
+                    Some(field) => Some(format!("{} (+{})", var.label, field)),
+                    None => None,
+                },
+            };
+            names.extend(name);
+        }
+
+        match names.is_empty() {
+            true => None,
+            // Variables packed into the same slot.
+            false => Some(names.join(", ")),
+        }
+    }
+
+    /// Mapping keys worth trying for `tx`: the accounts it touched, its sender and
+    /// anything in its calldata that looks like an address.
+    pub fn mapping_keys(tx: &Transaction, accounts: &[AccountDiff]) -> Vec<String> {
+        let mut keys: Vec<String> = accounts
+            .iter()
+            .map(|account| account.address.clone())
+            .collect();
+        keys.push(tx.from.to_lowercase());
+        let input = decode_hex(&tx.input).unwrap_or_default();
+        for word in input.get(4..).unwrap_or_default().chunks_exact(32) {
+            if word[..12].iter().all(|byte| *byte == 0) && word[12..].iter().any(|byte| *byte != 0)
+            {
+                keys.push(encode_hex(&word[12..]));
+            }
+        }
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    pub fn label_slots(&self, account: &mut AccountDiff, keys: &[String]) {
+        for change in account.storage.iter_mut() {
+            change.label = self.label(&change.slot, keys);
+        }
+    }
+}
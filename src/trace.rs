@@ -0,0 +1,333 @@
+use crate::chains::ChainInfo;
+use crate::providers::{Provider, ProviderError};
+use crate::signatures::SignatureDb;
+use crate::types::Block;
+use crate::utils::{format_units, hex_to_decimals};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A call frame as returned by geth's `callTracer`, with the calls it made.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallFrame {
+    /// CALL, STATICCALL, DELEGATECALL, CALLCODE, CREATE, CREATE2 or SELFDESTRUCT.
+    #[serde(rename = "type")]
+    pub call_type: String,
+    pub from: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas_used: Option<String>,
+    #[serde(default)]
+    pub input: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<CallFrame>,
+}
+
+impl CallFrame {
+    /// Wei sent with the call, 0 when the tracer left it out (e.g. STATICCALL).
+    pub fn value(&self) -> i128 {
+        self.value
+            .as_ref()
+            .map(|value| hex_to_decimals(value, true))
+            .unwrap_or(0)
+    }
+
+    pub fn gas_used(&self) -> i128 {
+        self.gas_used
+            .as_ref()
+            .map(|gas| hex_to_decimals(gas, true))
+            .unwrap_or(0)
+    }
+
+    pub fn selector(&self) -> Option<String> {
+        match self.input.len() >= 10 && !self.call_type.starts_with("CREATE") {
+            true => Some(self.input[..10].to_lowercase()),
+            false => None,
+        }
+    }
+
+    pub fn failed(&self) -> bool {
+        self.error.is_some()
+    }
+
+    /// Every frame of the tree, depth first, with its depth (0 for the top level call).
+    pub fn frames(&self) -> Vec<(usize, &CallFrame)> {
+        let mut frames = vec![];
+        self.collect(0, &mut frames);
+        frames
+    }
+
+    fn collect<'a>(&'a self, depth: usize, frames: &mut Vec<(usize, &'a CallFrame)>) {
+        frames.push((depth, self));
+        for call in &self.calls {
+            call.collect(depth + 1, frames);
+        }
+    }
+
+    /// One line per frame, indented by depth. Selectors are named from `signatures`.
+    pub fn render(&self, signatures: &SignatureDb) -> String {
+        let chain = ChainInfo::current();
+        let mut out = String::new();
+        for (depth, frame) in self.frames() {
+            let to = frame.to.as_deref().unwrap_or("??");
+            let mut line = format!(
+                "{}{} {} -> {}",
+                "  ".repeat(depth),
+                frame.call_type,
+                frame.from,
+                to
+            );
+            if frame.value() > 0 {
+                let value = format_units(frame.value(), chain.decimals);
+                line.push_str(&format!(", {} {}", value, chain.symbol));
+            }
+            line.push_str(&format!(", gas used {}", frame.gas_used()));
+            if let Some(selector) = frame.selector() {
+                let name = signatures.lookup(&selector).into_iter().next();
+                line.push_str(&format!(", {}", name.unwrap_or(selector)));
+            }
+            if let Some(error) = &frame.error {
+                let reason = frame.revert_reason.as_deref().unwrap_or(error);
+                line.push_str(&format!(" [FAILED: {}]", reason));
+            }
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Trace of one transaction in a `debug_traceBlockByNumber` response. Older geth
+/// versions leave out the hash, transactions are then matched by position.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BlockTrace {
+    #[serde(rename = "txHash")]
+    pub tx_hash: Option<String>,
+    pub result: CallFrame,
+}
+
+/// ETH moved by a contract during a transaction, which `Transaction.value` doesn't show.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InternalTransfer {
+    pub tx_hash: String,
+    /// Frame that moved the value: CALL, CREATE, CREATE2 or SELFDESTRUCT.
+    pub call_type: String,
+    pub from: String,
+    pub to: String,
+    pub value: i128,
+    /// Path of the frame from the top level call, `[1, 0]` being the first call made
+    /// by the second subcall.
+    pub trace_address: Vec<usize>,
+}
+
+impl fmt::Display for InternalTransfer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chain = ChainInfo::current();
+        write!(
+            f,
+            "{} -> {}, {} {} ({} in {})",
+            self.from,
+            self.to,
+            format_units(self.value, chain.decimals),
+            chain.symbol,
+            self.call_type,
+            self.tx_hash
+        )
+    }
+}
+
+/// Frames running in the context of the caller move no value of their own.
+fn moves_value(call_type: &str) -> bool {
+    !matches!(call_type, "DELEGATECALL" | "STATICCALL" | "CALLCODE")
+}
+
+impl CallFrame {
+    /// Value moved by the subcalls of a transaction, leaving out anything a failed
+    /// frame reverted.
+    pub fn internal_transfers(&self, tx_hash: &str) -> Vec<InternalTransfer> {
+        let mut transfers = vec![];
+        if !self.failed() {
+            for (idx, call) in self.calls.iter().enumerate() {
+                call.collect_transfers(tx_hash, vec![idx], &mut transfers);
+            }
+        }
+        transfers
+    }
+
+    fn collect_transfers(
+        &self,
+        tx_hash: &str,
+        trace_address: Vec<usize>,
+        transfers: &mut Vec<InternalTransfer>,
+    ) {
+        if self.failed() {
+            return;
+        }
+        if let Some(to) = &self.to {
+            if self.value() > 0 && moves_value(&self.call_type) {
+                transfers.push(InternalTransfer {
+                    tx_hash: tx_hash.to_string(),
+                    call_type: self.call_type.clone(),
+                    from: self.from.clone(),
+                    to: to.clone(),
+                    value: self.value(),
+                    trace_address: trace_address.clone(),
+                });
+            }
+        }
+        for (idx, call) in self.calls.iter().enumerate() {
+            let mut address = trace_address.clone();
+            address.push(idx);
+            call.collect_transfers(tx_hash, address, transfers);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParityAction {
+    pub call_type: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub value: Option<String>,
+    /// Self destructs
+    pub address: Option<String>,
+    pub refund_address: Option<String>,
+    pub balance: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ParityResult {
+    /// Contract deployed by a create.
+    pub address: Option<String>,
+}
+
+/// A flat trace from the `trace_` namespace (OpenEthereum, Erigon, Nethermind, reth).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParityTrace {
+    /// call, create, suicide or reward.
+    #[serde(rename = "type")]
+    pub trace_type: String,
+    pub action: ParityAction,
+    pub result: Option<ParityResult>,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub trace_address: Vec<usize>,
+    pub transaction_hash: Option<String>,
+}
+
+impl ParityTrace {
+    fn transfer(&self) -> Option<InternalTransfer> {
+        let quantity = |value: &Option<String>| {
+            value
+                .as_ref()
+                .map(|value| hex_to_decimals(value, true))
+                .unwrap_or(0)
+        };
+        let (call_type, from, to, value) = match self.trace_type.as_str() {
+            "call" => {
+                let call_type = self.action.call_type.as_deref().unwrap_or("call");
+                (
+                    call_type.to_uppercase(),
+                    self.action.from.clone()?,
+                    self.action.to.clone()?,
+                    quantity(&self.action.value),
+                )
+            }
+            "create" => (
+                "CREATE".to_string(),
+                self.action.from.clone()?,
+                self.result.as_ref()?.address.clone()?,
+                quantity(&self.action.value),
+            ),
+            "suicide" => (
+                "SELFDESTRUCT".to_string(),
+                self.action.address.clone()?,
+                self.action.refund_address.clone()?,
+                quantity(&self.action.balance),
+            ),
+            _ => return None,
+        };
+        match value > 0 && moves_value(&call_type) {
+            true => Some(InternalTransfer {
+                tx_hash: self.transaction_hash.clone()?,
+                call_type,
+                from,
+                to,
+                value,
+                trace_address: self.trace_address.clone(),
+            }),
+            false => None,
+        }
+    }
+}
+
+/// Internal transfers in flat traces, skipping top level calls and frames reverted
+/// by themselves or by one of their callers.
+pub fn parity_transfers(traces: &[ParityTrace]) -> Vec<InternalTransfer> {
+    let failed: Vec<(&Option<String>, &[usize])> = traces
+        .iter()
+        .filter(|trace| trace.error.is_some())
+        .map(|trace| (&trace.transaction_hash, trace.trace_address.as_slice()))
+        .collect();
+    let reverted = |trace: &ParityTrace| {
+        failed.iter().any(|(hash, address)| {
+            **hash == trace.transaction_hash && trace.trace_address.starts_with(address)
+        })
+    };
+
+    traces
+        .iter()
+        .filter(|trace| !trace.trace_address.is_empty() && !reverted(trace))
+        .filter_map(|trace| trace.transfer())
+        .collect()
+}
+
+/// Internal transfers of a transaction, from `callTracer` or else `trace_transaction`.
+pub async fn tx_transfers<P: Provider>(
+    provider: &P,
+    hash: &str,
+) -> Result<Vec<InternalTransfer>, ProviderError> {
+    match provider.trace_calls(hash).await {
+        Ok(trace) => Ok(trace.internal_transfers(hash)),
+        Err(ProviderError::Rpc { .. }) => {
+            let traces = provider.parity_trace_transaction(hash).await?;
+            Ok(parity_transfers(&traces))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Internal transfers of every transaction in `block`, from `callTracer` or else `trace_block`.
+pub async fn block_transfers<P: Provider>(
+    provider: &P,
+    block: &Block,
+) -> Result<Vec<InternalTransfer>, ProviderError> {
+    match provider.trace_block_calls(block.number).await {
+        Ok(traces) => Ok(traces
+            .iter()
+            .zip(&block.transactions)
+            .flat_map(|(trace, tx)| {
+                let hash = trace.tx_hash.as_deref().unwrap_or(&tx.hash);
+                trace.result.internal_transfers(hash)
+            })
+            .collect()),
+        Err(ProviderError::Rpc { .. }) => {
+            let traces = provider.parity_trace_block(block.number).await?;
+            Ok(parity_transfers(&traces))
+        }
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,243 @@
+use crate::rlp::{Rlp, RlpError};
+use crate::utils::keccak256;
+use std::fmt;
+
+/// Root of an empty trie, `keccak256(rlp(""))`.
+pub const EMPTY_ROOT: [u8; 32] = [
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+];
+
+#[derive(Debug)]
+pub enum ProofError {
+    Rlp(RlpError),
+    /// Proof node `index` doesn't hash to the reference found in its parent.
+    HashMismatch(usize),
+    /// The proof ends before reaching the key.
+    MissingNode,
+    InvalidNode(String),
+    /// A hash or quantity in the response could not be parsed.
+    Malformed(String),
+    /// The proven value differs from the one claimed by the node.
+    Mismatch {
+        field: String,
+        claimed: String,
+        proven: String,
+    },
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofError::Rlp(e) => write!(f, "{}", e),
+            ProofError::HashMismatch(idx) => write!(f, "proof node {} has the wrong hash", idx),
+            ProofError::MissingNode => write!(f, "proof is missing nodes"),
+            ProofError::InvalidNode(message) => write!(f, "invalid trie node: {}", message),
+            ProofError::Malformed(message) => write!(f, "malformed proof: {}", message),
+            ProofError::Mismatch {
+                field,
+                claimed,
+                proven,
+            } => write!(f, "{} is {} but the proof says {}", field, claimed, proven),
+        }
+    }
+}
+
+impl From<RlpError> for ProofError {
+    fn from(e: RlpError) -> Self {
+        ProofError::Rlp(e)
+    }
+}
+
+fn to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect()
+}
+
+/// Decodes a hex prefix encoded path into its nibbles and whether it ends in a leaf.
+fn decode_path(encoded: &[u8]) -> Result<(Vec<u8>, bool), ProofError> {
+    let first = *encoded
+        .first()
+        .ok_or_else(|| ProofError::InvalidNode("empty path".to_string()))?;
+    let flag = first >> 4;
+    if flag > 3 {
+        return Err(ProofError::InvalidNode(format!("path flag {}", flag)));
+    }
+
+    let mut nibbles = match flag & 1 {
+        1 => vec![first & 0x0f],
+        _ => vec![],
+    };
+    nibbles.extend(to_nibbles(&encoded[1..]));
+    Ok((nibbles, flag & 2 == 2))
+}
+
+/// How a node refers to a child: by hash, or embedded when its encoding is under 32 bytes.
+enum Child {
+    Hash(Vec<u8>),
+    Inline(Rlp),
+}
+
+fn child(item: &Rlp) -> Result<Option<Child>, ProofError> {
+    match item {
+        Rlp::Bytes(bytes) if bytes.is_empty() => Ok(None),
+        Rlp::Bytes(bytes) if bytes.len() == 32 => Ok(Some(Child::Hash(bytes.clone()))),
+        Rlp::Bytes(bytes) => Err(ProofError::InvalidNode(format!(
+            "child reference of {} bytes",
+            bytes.len()
+        ))),
+        Rlp::List(_) => Ok(Some(Child::Inline(item.clone()))),
+    }
+}
+
+/// Walks a Merkle-Patricia proof for `key` from `root`.
+///
+/// Returns the value stored under `key`, or `None` when the proof shows the key is absent.
+/// Secure tries (state and storage) are keyed by `keccak256(key)`, callers hash it.
+pub fn verify_proof(
+    root: &[u8; 32],
+    key: &[u8],
+    proof: &[Vec<u8>],
+) -> Result<Option<Vec<u8>>, ProofError> {
+    if *root == EMPTY_ROOT && proof.is_empty() {
+        return Ok(None);
+    }
+
+    let path = to_nibbles(key);
+    let mut path = path.as_slice();
+    let mut nodes = proof.iter().enumerate();
+    let mut next = Child::Hash(root.to_vec());
+
+    loop {
+        let node = match next {
+            Child::Hash(hash) => {
+                let (idx, encoded) = nodes.next().ok_or(ProofError::MissingNode)?;
+                if keccak256(encoded).as_slice() != hash.as_slice() {
+                    return Err(ProofError::HashMismatch(idx));
+                }
+                Rlp::decode(encoded)?
+            }
+            Child::Inline(node) => node,
+        };
+        let items = node.as_list()?;
+
+        next = match items.len() {
+            17 => match path.split_first() {
+                None => {
+                    let value = items[16].as_bytes()?;
+                    return Ok((!value.is_empty()).then(|| value.to_vec()));
+                }
+                Some((nibble, rest)) => {
+                    path = rest;
+                    match child(&items[*nibble as usize])? {
+                        Some(next) => next,
+                        None => return Ok(None),
+                    }
+                }
+            },
+            2 => {
+                let (nibbles, is_leaf) = decode_path(items[0].as_bytes()?)?;
+                if is_leaf {
+                    let value = items[1].as_bytes()?;
+                    return Ok((path == nibbles.as_slice()).then(|| value.to_vec()));
+                }
+                if !path.starts_with(&nibbles) {
+                    return Ok(None);
+                }
+                path = &path[nibbles.len()..];
+                child(&items[1])?
+                    .ok_or_else(|| ProofError::InvalidNode("extension without child".to_string()))?
+            }
+            len => return Err(ProofError::InvalidNode(format!("node with {} items", len))),
+        };
+    }
+}
+
+/// Hex prefix encoding of a path, flagging whether it ends in a leaf.
+fn encode_path(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+    let flag = if is_leaf { 2 } else { 0 };
+    let (mut out, rest) = match nibbles.len() % 2 {
+        1 => (vec![((flag + 1) << 4) | nibbles[0]], &nibbles[1..]),
+        _ => (vec![flag << 4], nibbles),
+    };
+    out.extend(rest.chunks(2).map(|pair| (pair[0] << 4) | pair[1]));
+    out
+}
+
+/// Embeds small nodes in their parent and refers to the others by hash.
+fn reference(node: Rlp) -> Rlp {
+    let encoded = node.encode();
+    match encoded.len() < 32 {
+        true => node,
+        false => Rlp::Bytes(keccak256(&encoded).to_vec()),
+    }
+}
+
+/// Builds the node holding `items`, whose keys (as nibbles) share their first `depth` nibbles.
+fn build(items: &[(Vec<u8>, Vec<u8>)], depth: usize) -> Rlp {
+    if let [(key, value)] = items {
+        return Rlp::List(vec![
+            Rlp::Bytes(encode_path(&key[depth..], true)),
+            Rlp::Bytes(value.clone()),
+        ]);
+    }
+
+    let first = &items[0].0;
+    let mut shared = 0;
+    while items
+        .iter()
+        .all(|(key, _)| key.len() > depth + shared && key[depth + shared] == first[depth + shared])
+    {
+        shared += 1;
+    }
+    if shared > 0 {
+        return Rlp::List(vec![
+            Rlp::Bytes(encode_path(&first[depth..depth + shared], false)),
+            reference(build(items, depth + shared)),
+        ]);
+    }
+
+    let mut children = vec![Rlp::Bytes(vec![]); 17];
+    for nibble in 0..16u8 {
+        let branch: Vec<(Vec<u8>, Vec<u8>)> = items
+            .iter()
+            .filter(|(key, _)| key.get(depth) == Some(&nibble))
+            .cloned()
+            .collect();
+        if !branch.is_empty() {
+            children[nibble as usize] = reference(build(&branch, depth + 1));
+        }
+    }
+    if let Some((_, value)) = items.iter().find(|(key, _)| key.len() == depth) {
+        children[16] = Rlp::Bytes(value.clone());
+    }
+    Rlp::List(children)
+}
+
+/// Root hash of a trie holding `items` as `(key, value)` pairs. Later duplicates win.
+pub fn root(items: &[(Vec<u8>, Vec<u8>)]) -> [u8; 32] {
+    let mut entries: Vec<(Vec<u8>, Vec<u8>)> = vec![];
+    for (key, value) in items {
+        let key = to_nibbles(key);
+        entries.retain(|(existing, _)| *existing != key);
+        entries.push((key, value.clone()));
+    }
+    if entries.is_empty() {
+        return EMPTY_ROOT;
+    }
+    entries.sort();
+    keccak256(&build(&entries, 0).encode())
+}
+
+/// Root of the trie keyed by the RLP encoded index of each value, as used for the
+/// transactions, receipts and withdrawals of a block.
+pub fn ordered_root(values: &[Vec<u8>]) -> [u8; 32] {
+    let items: Vec<(Vec<u8>, Vec<u8>)> = values
+        .iter()
+        .enumerate()
+        .map(|(idx, value)| {
+            let key = Rlp::uint(&(idx as u64).to_be_bytes()).encode();
+            (key, value.clone())
+        })
+        .collect();
+    root(&items)
+}
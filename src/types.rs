@@ -1,8 +1,12 @@
 use std::fmt;
-use crate::utils::{hex_to_decimals, format_label_and_value};
+use crate::chains::ChainInfo;
+use crate::rlp::Rlp;
+use crate::sender::{self, TxCheck};
+use crate::utils::{checked_hex_to_decimals, decode_hex, encode_hex, format_label_and_value, format_units, hex_to_decimals, keccak256};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum Method {
     Accounts,
     GasPrice,
@@ -16,6 +20,21 @@ pub enum Method {
     GetBlockByNumber,
     GetTxByHash,
     GetTxReceipt,
+    GetBlockReceipts,
+    GetCode,
+    GetStorageAt,
+    Call,
+    GetProof,
+    TraceTransaction,
+    TraceBlockByNumber,
+    ParityTraceTransaction,
+    ParityTraceBlock,
+    ChainId,
+    NetVersion,
+    ClientVersion,
+    PeerCount,
+    Listening,
+    Syncing,
 }
 
 impl Method {
@@ -33,6 +52,21 @@ impl Method {
             Method::GetBlockByNumber => "eth_getBlockByNumber",
             Method::GetTxByHash => "eth_getTransactionByHash",
             Method::GetTxReceipt => "eth_getTransactionReceipt",
+            Method::GetBlockReceipts => "eth_getBlockReceipts",
+            Method::GetCode => "eth_getCode",
+            Method::GetStorageAt => "eth_getStorageAt",
+            Method::Call => "eth_call",
+            Method::GetProof => "eth_getProof",
+            Method::TraceTransaction => "debug_traceTransaction",
+            Method::TraceBlockByNumber => "debug_traceBlockByNumber",
+            Method::ParityTraceTransaction => "trace_transaction",
+            Method::ParityTraceBlock => "trace_block",
+            Method::ChainId => "eth_chainId",
+            Method::NetVersion => "net_version",
+            Method::ClientVersion => "web3_clientVersion",
+            Method::PeerCount => "net_peerCount",
+            Method::Listening => "net_listening",
+            Method::Syncing => "eth_syncing",
         }
     }
 }
@@ -41,14 +75,14 @@ impl Method {
 pub struct Body {
     pub jsonrpc: &'static str,
     pub method: &'static str,
-    pub params: Vec<String>,
+    pub params: Vec<Value>,
     pub id: u32,
 }
 
 impl Body {
-    pub fn new(method: Method, params: Vec<String>) -> Body {
+    pub fn new(method: Method, params: Vec<Value>) -> Body {
         Body {
-            jsonrpc: "2.0".into(),
+            jsonrpc: "2.0",
             method: method.value(),
             params,
             id: 1,
@@ -56,24 +90,15 @@ impl Body {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct BlockRequest {
-    pub jsonrpc: &'static str,
-    pub method: &'static str,
-    pub params: (String, bool),
-    pub id: u32,
-}
-
 pub trait ResultParser {
     fn result(&self) -> String;
     fn to_decimals(&self) -> i128 {
         hex_to_decimals(&self.result(), true)
     }
 
-    fn from_wei(&self) -> i128 {
-        let base: i128 = 10;
-        let num_wei_in_eth: i128 = base.pow(18);
-        self.to_decimals() / num_wei_in_eth
+    /// Value in the native currency of the detected chain (e.g. wei to ETH).
+    fn to_native(&self) -> String {
+        format_units(self.to_decimals(), ChainInfo::current().decimals)
     }
 }
 
@@ -98,7 +123,7 @@ pub struct BlockResponse {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BlockHex {
     #[serde(rename = "baseFeePerGas")]
-    base_gas_fee: String,
+    base_gas_fee: Option<String>,
     difficulty: String,
     #[serde(rename = "gasLimit")]
     gas_limit: String,
@@ -113,16 +138,18 @@ pub struct BlockHex {
     #[serde(rename = "parentHash")]
     parent_hash: String,
     size: String,
+    #[serde(rename = "stateRoot")]
+    state_root: String,
     timestamp: String,
     #[serde(rename = "totalDifficulty")]
-    total_difficulty: String,
+    total_difficulty: Option<String>,
     pub transactions: Vec<TransactionHex>,
 }
 
 impl BlockHex {
-    pub fn parse(&self) -> Block {
-        Block {
-            base_gas_fee: hex_to_decimals(&self.base_gas_fee, true),
+    pub fn parse(&self) -> Result<Block, String> {
+        Ok(Block {
+            base_gas_fee: self.base_gas_fee.as_ref().map(|fee| hex_to_decimals(fee, true)),
             difficulty: hex_to_decimals(&self.difficulty, true) as i32,
             gas_limit: hex_to_decimals(&self.gas_limit, true),
             gas_used: hex_to_decimals(&self.gas_used, true),
@@ -133,45 +160,52 @@ impl BlockHex {
             number: hex_to_decimals(&self.number, true) as i32,
             parent_hash: self.parent_hash.clone(),
             size: hex_to_decimals(&self.size, true) as i32,
+            state_root: self.state_root.clone(),
             timestamp: hex_to_decimals(&self.timestamp, true) as u32,
-            total_difficulty: hex_to_decimals(&self.total_difficulty, true) as u32,
+            total_difficulty: self
+                .total_difficulty
+                .as_ref()
+                .map(|difficulty| hex_to_decimals(difficulty, true)),
             transactions: self
                 .transactions
                 .clone()
                 .into_iter()
-                .map(|tx| tx.prase())
-                .collect(),
-        }
+                .map(|tx| tx.try_parse())
+                .collect::<Result<_, _>>()?,
+        })
     }
 }
 
 #[derive(Debug)]
 pub struct Block {
-    base_gas_fee: i128,
+    /// `None` before London.
+    pub base_gas_fee: Option<i128>,
     difficulty: i32,
-    gas_limit: i128,
-    gas_used: i128,
-    hash: String,
-    miner: String,
+    pub gas_limit: i128,
+    pub gas_used: i128,
+    pub hash: String,
+    pub miner: String,
     mix_hash: String,
     nonce: i32,
-    number: i32,
-    parent_hash: String,
+    pub number: i32,
+    pub parent_hash: String,
     size: i32,
-    timestamp: u32,
-    total_difficulty: u32,
-    transactions: Vec<Transaction>,
+    pub state_root: String,
+    pub timestamp: u32,
+    /// Dropped by nodes after the merge.
+    total_difficulty: Option<i128>,
+    pub transactions: Vec<Transaction>,
 }
 
-
 impl fmt::Display for Block {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let or_none = |val: Option<i128>| val.map(|val| val.to_string()).unwrap_or_else(|| "-".to_string());
         let lines = [
             ("Hash", self.hash.clone()),
             ("Block Number", self.number.to_string()),
             ("Transactions Count", self.transactions.len().to_string()),
             ("Miner", self.miner.clone()),
-            ("Base Gase Fee", self.base_gas_fee.to_string()),
+            ("Base Gase Fee", or_none(self.base_gas_fee)),
             ("Difficulty", self.difficulty.to_string()),
             ("Gas Limit", self.gas_limit.to_string()),
             ("Gas Used", self.gas_used.to_string()),
@@ -179,18 +213,19 @@ impl fmt::Display for Block {
             ("Nonce", self.nonce.to_string()),
             ("Parent Hash", self.parent_hash.to_string()),
             ("Size", self.size.to_string()),
+            ("State Root", self.state_root.clone()),
             ("Timestamp", self.timestamp.to_string()),
-            ("Total Difficulty", self.total_difficulty.to_string())
+            ("Total Difficulty", or_none(self.total_difficulty))
         ];
 
         for (label, value) in lines {
             write!(f, "{}", format_label_and_value(label, &value))?;
         }
 
-        println!("Block Transaction: ");
+        writeln!(f, "Block Transaction: ")?;
         for tx in &self.transactions {
-            println!(">>>>>>>>>>>>>>>>>>>>>>>>><<<<<<<<<<<<<<<<<<<<<<<<<<<<<<");
-            println!("{}", tx);
+            writeln!(f, ">>>>>>>>>>>>>>>>>>>>>>>>><<<<<<<<<<<<<<<<<<<<<<<<<<<<<<")?;
+            writeln!(f, "{}", tx)?;
         }
 
         write!(f, "")
@@ -198,107 +233,731 @@ impl fmt::Display for Block {
 }
 
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxType {
+    Legacy,
+    AccessList,
+    DynamicFee,
+    Blob,
+    SetCode,
+    Unknown(u8),
+}
+
+impl TxType {
+    pub fn from_byte(byte: u8) -> TxType {
+        match byte {
+            0 => TxType::Legacy,
+            1 => TxType::AccessList,
+            2 => TxType::DynamicFee,
+            3 => TxType::Blob,
+            4 => TxType::SetCode,
+            other => TxType::Unknown(other),
+        }
+    }
+
+    pub fn byte(&self) -> u8 {
+        match self {
+            TxType::Legacy => 0,
+            TxType::AccessList => 1,
+            TxType::DynamicFee => 2,
+            TxType::Blob => 3,
+            TxType::SetCode => 4,
+            TxType::Unknown(other) => *other,
+        }
+    }
+
+    pub fn value(&self) -> &'static str {
+        match self {
+            TxType::Legacy => "Legacy",
+            TxType::AccessList => "EIP-2930 (access list)",
+            TxType::DynamicFee => "EIP-1559 (dynamic fee)",
+            TxType::Blob => "EIP-4844 (blob)",
+            TxType::SetCode => "EIP-7702 (set code)",
+            TxType::Unknown(_) => "Unknown",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccessListItem {
+    pub address: String,
+    #[serde(rename = "storageKeys")]
+    pub storage_keys: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuthorizationHex {
+    #[serde(rename = "chainId")]
+    chain_id: String,
+    address: String,
+    nonce: String,
+    #[serde(rename = "yParity", alias = "v")]
+    y_parity: String,
+    r: String,
+    s: String,
+}
+
+/// Hex data field as an RLP string.
+fn rlp_bytes(value: &str) -> Result<Rlp, String> {
+    decode_hex(value).map(Rlp::Bytes)
+}
+
+/// Hex quantity as an RLP integer.
+fn rlp_uint(value: &str) -> Result<Rlp, String> {
+    decode_hex(value).map(|bytes| Rlp::uint(&bytes))
+}
+
+fn required<'a>(value: &'a Option<String>, name: &str) -> Result<&'a str, String> {
+    value.as_deref().ok_or_else(|| format!("missing {}", name))
+}
+
+/// RLP integer as a hex quantity.
+fn quantity_hex(item: &Rlp) -> Result<String, String> {
+    let digits = hex::encode(item.as_bytes().map_err(|e| e.to_string())?);
+    match digits.trim_start_matches('0') {
+        "" => Ok("0x0".to_string()),
+        digits => Ok(format!("0x{}", digits)),
+    }
+}
+
+/// Checked hex quantity, `name` says which field overflowed.
+fn quantity(name: &str, value: &str) -> Result<i128, String> {
+    checked_hex_to_decimals(value).map_err(|e| format!("{}: {}", name, e))
+}
+
+/// Checked hex quantity for the fields stored in narrower integers.
+fn narrow<T: TryFrom<i128>>(name: &str, value: &str) -> Result<T, String> {
+    T::try_from(quantity(name, value)?).map_err(|_| format!("{}: {} is too large", name, value))
+}
+
+fn narrow_opt<T: TryFrom<i128>>(name: &str, value: &Option<String>) -> Result<Option<T>, String> {
+    value.as_ref().map(|v| narrow(name, v)).transpose()
+}
+
+fn bytes_hex(item: &Rlp) -> Result<String, String> {
+    item.as_bytes().map(encode_hex).map_err(|e| e.to_string())
+}
+
+fn rlp_list(item: &Rlp) -> Result<&[Rlp], String> {
+    item.as_list().map_err(|e| e.to_string())
+}
+
+impl AccessListItem {
+    fn rlp(&self) -> Result<Rlp, String> {
+        let keys = self.storage_keys.iter().map(|key| rlp_bytes(key)).collect::<Result<_, _>>()?;
+        Ok(Rlp::List(vec![rlp_bytes(&self.address)?, Rlp::List(keys)]))
+    }
+
+    fn from_rlp(item: &Rlp) -> Result<AccessListItem, String> {
+        match rlp_list(item)? {
+            [address, keys] => Ok(AccessListItem {
+                address: bytes_hex(address)?,
+                storage_keys: rlp_list(keys)?.iter().map(bytes_hex).collect::<Result<_, _>>()?,
+            }),
+            items => Err(format!("access list entry with {} fields", items.len())),
+        }
+    }
+}
+
+impl AuthorizationHex {
+    fn rlp(&self) -> Result<Rlp, String> {
+        Ok(Rlp::List(vec![
+            rlp_uint(&self.chain_id)?,
+            rlp_bytes(&self.address)?,
+            rlp_uint(&self.nonce)?,
+            rlp_uint(&self.y_parity)?,
+            rlp_uint(&self.r)?,
+            rlp_uint(&self.s)?,
+        ]))
+    }
+
+    fn from_rlp(item: &Rlp) -> Result<AuthorizationHex, String> {
+        match rlp_list(item)? {
+            [chain_id, address, nonce, y_parity, r, s] => Ok(AuthorizationHex {
+                chain_id: quantity_hex(chain_id)?,
+                address: bytes_hex(address)?,
+                nonce: quantity_hex(nonce)?,
+                y_parity: quantity_hex(y_parity)?,
+                r: quantity_hex(r)?,
+                s: quantity_hex(s)?,
+            }),
+            items => Err(format!("authorization with {} fields", items.len())),
+        }
+    }
+
+    pub fn parse(&self) -> Result<Authorization, String> {
+        Ok(Authorization {
+            chain_id: quantity("authorization chainId", &self.chain_id)?,
+            address: self.address.clone(),
+            nonce: quantity("authorization nonce", &self.nonce)?,
+            y_parity: narrow("authorization yParity", &self.y_parity)?,
+            r: self.r.clone(),
+            s: self.s.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Authorization {
+    pub chain_id: i128,
+    pub address: String,
+    pub nonce: i128,
+    pub y_parity: u8,
+    pub r: String,
+    pub s: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TransactionHex {
+    #[serde(rename = "type")]
+    tx_type: Option<String>,
     #[serde(rename = "blockHash")]
-    block_hash: String,
+    block_hash: Option<String>,
     #[serde(rename = "blockNumber")]
-    block_number: String,
+    block_number: Option<String>,
+    #[serde(rename = "chainId")]
+    chain_id: Option<String>,
     from: String,
     gas: String,
     #[serde(rename = "gasPrice")]
-    gas_price: String,
+    gas_price: Option<String>,
     #[serde(rename = "maxPriorityFeePerGas")]
     max_priority_fee_per_gas: Option<String>,
     #[serde(rename = "maxFeePerGas")]
     max_fee_per_gas: Option<String>,
+    #[serde(rename = "maxFeePerBlobGas")]
+    max_fee_per_blob_gas: Option<String>,
     hash: String,
+    input: String,
     nonce: String,
     to: Option<String>,
     #[serde(rename = "transactionIndex")]
-    tx_idx: String,
+    tx_idx: Option<String>,
     value: String,
+    v: Option<String>,
+    r: Option<String>,
+    s: Option<String>,
+    #[serde(rename = "yParity")]
+    y_parity: Option<String>,
+    #[serde(rename = "accessList")]
+    access_list: Option<Vec<AccessListItem>>,
+    #[serde(rename = "blobVersionedHashes")]
+    blob_versioned_hashes: Option<Vec<String>>,
+    #[serde(rename = "authorizationList")]
+    authorization_list: Option<Vec<AuthorizationHex>>,
+    /// Decoded from raw bytes rather than returned by a node.
+    #[serde(skip)]
+    offline: bool,
 }
 
 impl TransactionHex {
-    pub fn prase(&self) -> Transaction {
-        Transaction {
+    /// Quantities that don't fit the `Transaction` fields are an error rather than being
+    /// truncated, transactions may come from raw bytes rather than from a node.
+    pub fn try_parse(&self) -> Result<Transaction, String> {
+        let opt = |name: &str, val: &Option<String>| val.as_ref().map(|v| quantity(name, v)).transpose();
+
+        Ok(Transaction {
+            tx_type: TxType::from_byte(self.type_byte()?),
             block_hash: self.block_hash.clone(),
-            block_number: hex_to_decimals(&self.block_number, true) as i32,
+            block_number: narrow_opt("blockNumber", &self.block_number)?,
+            chain_id: opt("chainId", &self.chain_id)?,
             from: self.from.clone(),
-            gas: hex_to_decimals(&self.gas, true),
-            gas_price: hex_to_decimals(&self.gas_price, true),
-            max_priority_fee_per_gas: match self.max_priority_fee_per_gas.clone() {
-                Some(val) => Some(hex_to_decimals(&val, true)),
-                None => None,
-            },
-            max_fee_per_gas: match self.max_fee_per_gas.clone() {
-                Some(val) => Some(hex_to_decimals(&val, true)),
-                None => None,
-            },
+            gas: quantity("gas", &self.gas)?,
+            gas_price: opt("gasPrice", &self.gas_price)?,
+            max_priority_fee_per_gas: opt("maxPriorityFeePerGas", &self.max_priority_fee_per_gas)?,
+            max_fee_per_gas: opt("maxFeePerGas", &self.max_fee_per_gas)?,
+            max_fee_per_blob_gas: opt("maxFeePerBlobGas", &self.max_fee_per_blob_gas)?,
             hash: self.hash.clone(),
-            nonce: hex_to_decimals(&self.nonce, true) as i32,
+            input: self.input.clone(),
+            nonce: narrow("nonce", &self.nonce)?,
             to: self.to.clone(),
-            tx_idx: hex_to_decimals(&self.tx_idx, true) as i32,
-            value: hex_to_decimals(&self.value, true),
+            tx_idx: narrow_opt("transactionIndex", &self.tx_idx)?,
+            value: quantity("value", &self.value)?,
+            v: opt("v", &self.v)?,
+            r: self.r.clone(),
+            s: self.s.clone(),
+            y_parity: narrow_opt("yParity", &self.y_parity)?,
+            access_list: self.access_list.clone(),
+            blob_versioned_hashes: self.blob_versioned_hashes.clone(),
+            authorization_list: self
+                .authorization_list
+                .as_ref()
+                .map(|list| list.iter().map(|auth| auth.parse()).collect::<Result<_, _>>())
+                .transpose()?,
+            offline: self.offline,
+            encoded: self.encode(),
+        })
+    }
+
+    pub fn type_byte(&self) -> Result<u8, String> {
+        match &self.tx_type {
+            Some(val) => u8::from_str_radix(val.trim_start_matches("0x"), 16)
+                .map_err(|_| format!("Invalid transaction type {}", val)),
+            None => Ok(0),
         }
     }
-}
 
-#[derive(Debug)]
-pub struct Transaction {
-    block_hash: String,
-    block_number: i32,
-    from: String,
-    gas: i128,
-    gas_price: i128,
-    max_priority_fee_per_gas: Option<i128>,
-    max_fee_per_gas: Option<i128>,
-    hash: String,
-    nonce: i32,
-    to: Option<String>,
-    tx_idx: i32,
-    value: i128,
-}
+    /// Consensus encoding of the signed transaction: what its hash is computed over
+    /// and what the transactions trie stores. Typed transactions are prefixed by their type.
+    pub fn encode(&self) -> Result<Vec<u8>, String> {
+        let tx_type = self.type_byte()?;
+        let to = match &self.to {
+            Some(to) => rlp_bytes(to)?,
+            None => Rlp::Bytes(vec![]),
+        };
+        let access_list = || -> Result<Rlp, String> {
+            let items = self.access_list.iter().flatten().map(|item| item.rlp()).collect::<Result<_, _>>()?;
+            Ok(Rlp::List(items))
+        };
+        // Nodes report the parity of typed transactions as `yParity`, older ones only as `v`.
+        let y_parity = || match (&self.y_parity, &self.v) {
+            (Some(parity), _) | (None, Some(parity)) => rlp_uint(parity),
+            (None, None) => Err("missing yParity".to_string()),
+        };
+        let signature = || -> Result<Vec<Rlp>, String> {
+            Ok(vec![y_parity()?, rlp_uint(required(&self.r, "r")?)?, rlp_uint(required(&self.s, "s")?)?])
+        };
 
+        let mut fields = match tx_type {
+            0 => vec![
+                rlp_uint(&self.nonce)?,
+                rlp_uint(required(&self.gas_price, "gasPrice")?)?,
+                rlp_uint(&self.gas)?,
+                to,
+                rlp_uint(&self.value)?,
+                rlp_bytes(&self.input)?,
+                rlp_uint(required(&self.v, "v")?)?,
+                rlp_uint(required(&self.r, "r")?)?,
+                rlp_uint(required(&self.s, "s")?)?,
+            ],
+            1 => vec![
+                rlp_uint(required(&self.chain_id, "chainId")?)?,
+                rlp_uint(&self.nonce)?,
+                rlp_uint(required(&self.gas_price, "gasPrice")?)?,
+                rlp_uint(&self.gas)?,
+                to,
+                rlp_uint(&self.value)?,
+                rlp_bytes(&self.input)?,
+                access_list()?,
+            ],
+            2..=4 => vec![
+                rlp_uint(required(&self.chain_id, "chainId")?)?,
+                rlp_uint(&self.nonce)?,
+                rlp_uint(required(&self.max_priority_fee_per_gas, "maxPriorityFeePerGas")?)?,
+                rlp_uint(required(&self.max_fee_per_gas, "maxFeePerGas")?)?,
+                rlp_uint(&self.gas)?,
+                to,
+                rlp_uint(&self.value)?,
+                rlp_bytes(&self.input)?,
+                access_list()?,
+            ],
+            other => return Err(format!("unknown transaction type {}", other)),
+        };
 
-impl fmt::Display for Transaction {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let to= match self.to.clone() {
-            Some(val) => val,
-            None => "??".to_string()
+        match tx_type {
+            3 => {
+                fields.push(rlp_uint(required(&self.max_fee_per_blob_gas, "maxFeePerBlobGas")?)?);
+                let hashes = self.blob_versioned_hashes.iter().flatten().map(|hash| rlp_bytes(hash)).collect::<Result<_, _>>()?;
+                fields.push(Rlp::List(hashes));
+            }
+            4 => {
+                let list = self.authorization_list.iter().flatten().map(|auth| auth.rlp()).collect::<Result<_, _>>()?;
+                fields.push(Rlp::List(list));
+            }
+            _ => {}
+        }
+        if tx_type > 0 {
+            fields.extend(signature()?);
+        }
+
+        let mut encoded = match tx_type {
+            0 => vec![],
+            _ => vec![tx_type],
         };
+        encoded.extend(Rlp::List(fields).encode());
+        Ok(encoded)
+    }
 
-        let max_priority_fee_per_gas = match self.max_priority_fee_per_gas {
-            Some(val) => val.to_string(),
-            None => "??".to_string()
+    /// Parses a raw signed transaction, legacy or typed, recomputing its hash and recovering
+    /// its sender. Blob transactions are also accepted in their network form, with the sidecar.
+    pub fn decode(raw: &[u8]) -> Result<TransactionHex, String> {
+        let (tx_type, payload) = match raw.first() {
+            None => return Err("empty transaction".to_string()),
+            Some(byte) if *byte >= 0xc0 => (0, raw),
+            Some(byte) if *byte <= 0x7f => (*byte, &raw[1..]),
+            Some(byte) => return Err(format!("unknown transaction envelope 0x{:02x}", byte)),
         };
+        let rlp = Rlp::decode(payload).map_err(|e| e.to_string())?;
+        let mut items = rlp_list(&rlp)?;
+        // [tx, blobs, commitments, proofs]
+        let wrapped = tx_type == 3 && matches!(items.first(), Some(Rlp::List(_)));
+        if wrapped {
+            items = rlp_list(&items[0])?;
+        }
 
-        let max_fee_per_gas = match self.max_fee_per_gas {
-            Some(val) => val.to_string(),
-            None => "??".to_string()
+        let expected = match tx_type {
+            0 => 9,
+            1 => 11,
+            2 => 12,
+            3 => 14,
+            4 => 13,
+            other => return Err(format!("unknown transaction type {}", other)),
         };
+        if items.len() != expected {
+            return Err(format!("type {} transaction with {} fields instead of {}", tx_type, items.len(), expected));
+        }
+        let mut fields = items.iter();
+        let mut field = || fields.next().expect("field count checked above");
 
+        let mut chain_id = match tx_type {
+            0 => None,
+            _ => Some(quantity_hex(field())?),
+        };
+        let nonce = quantity_hex(field())?;
+        let (gas_price, max_priority_fee_per_gas, max_fee_per_gas) = match tx_type {
+            0 | 1 => (Some(quantity_hex(field())?), None, None),
+            _ => (None, Some(quantity_hex(field())?), Some(quantity_hex(field())?)),
+        };
+        let gas = quantity_hex(field())?;
+        let to = match bytes_hex(field())?.as_str() {
+            "0x" => None,
+            to => Some(to.to_string()),
+        };
+        let value = quantity_hex(field())?;
+        let input = bytes_hex(field())?;
+        let access_list = match tx_type {
+            0 => None,
+            _ => Some(rlp_list(field())?.iter().map(AccessListItem::from_rlp).collect::<Result<_, _>>()?),
+        };
+        let (max_fee_per_blob_gas, blob_versioned_hashes) = match tx_type {
+            3 => (
+                Some(quantity_hex(field())?),
+                Some(rlp_list(field())?.iter().map(bytes_hex).collect::<Result<_, _>>()?),
+            ),
+            _ => (None, None),
+        };
+        let authorization_list = match tx_type {
+            4 => Some(rlp_list(field())?.iter().map(AuthorizationHex::from_rlp).collect::<Result<_, _>>()?),
+            _ => None,
+        };
+        let v = quantity_hex(field())?;
+        let r = quantity_hex(field())?;
+        let s = quantity_hex(field())?;
 
-        let lines = [
+        // Legacy transactions carry the chain id in v since EIP-155.
+        let y_parity = match tx_type {
+            0 => {
+                let v = quantity("v", &v)?;
+                if v >= 35 {
+                    chain_id = Some(format!("0x{:x}", (v - 35) / 2));
+                }
+                None
+            }
+            _ => Some(v.clone()),
+        };
+
+        let mut tx = TransactionHex {
+            tx_type: Some(format!("0x{:x}", tx_type)),
+            block_hash: None,
+            block_number: None,
+            chain_id,
+            from: String::new(),
+            gas,
+            gas_price,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            max_fee_per_blob_gas,
+            hash: String::new(),
+            input,
+            nonce,
+            to,
+            tx_idx: None,
+            value,
+            v: Some(v),
+            r: Some(r),
+            s: Some(s),
+            y_parity,
+            access_list,
+            blob_versioned_hashes,
+            authorization_list,
+            offline: true,
+        };
+
+        let encoded = tx.encode()?;
+        if !wrapped && encoded != raw {
+            return Err("transaction is not canonically encoded".to_string());
+        }
+        tx.hash = encode_hex(&keccak256(&encoded));
+        tx.from = sender::sender(&encoded).map_err(|e| format!("can't recover the sender: {}", e))?;
+        Ok(tx)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    pub tx_type: TxType,
+    pub block_hash: Option<String>,
+    pub block_number: Option<i32>,
+    pub chain_id: Option<i128>,
+    pub from: String,
+    pub gas: i128,
+    pub gas_price: Option<i128>,
+    pub max_priority_fee_per_gas: Option<i128>,
+    pub max_fee_per_gas: Option<i128>,
+    pub max_fee_per_blob_gas: Option<i128>,
+    pub hash: String,
+    pub input: String,
+    pub nonce: i32,
+    pub to: Option<String>,
+    pub tx_idx: Option<i32>,
+    pub value: i128,
+    pub v: Option<i128>,
+    pub r: Option<String>,
+    pub s: Option<String>,
+    pub y_parity: Option<u8>,
+    pub access_list: Option<Vec<AccessListItem>>,
+    pub blob_versioned_hashes: Option<Vec<String>>,
+    pub authorization_list: Option<Vec<Authorization>>,
+    /// Decoded from raw bytes: never seen by a node, so it has no status.
+    pub offline: bool,
+    /// Consensus encoding rebuilt from the fields, an error when some are missing.
+    pub encoded: Result<Vec<u8>, String>,
+}
+
+impl Transaction {
+    pub fn is_pending(&self) -> bool {
+        self.block_hash.is_none()
+    }
+
+    /// Recomputes the hash and recovers the sender from the signature.
+    pub fn check(&self) -> Result<TxCheck, String> {
+        let encoded = self.encoded.as_ref().map_err(|e| e.clone())?;
+        Ok(TxCheck::new(&self.hash, &self.from, encoded))
+    }
+
+    /// 4 byte function selector of the calldata, `None` for plain transfers.
+    pub fn selector(&self) -> Option<String> {
+        match self.input.len() >= 10 {
+            true => Some(self.input[..10].to_lowercase()),
+            false => None,
+        }
+    }
+}
+
+impl fmt::Display for Transaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let or_unknown = |val: Option<String>| val.unwrap_or_else(|| "??".to_string());
+
+        let mut lines = vec![
             ("Hash", self.hash.clone()),
+            (
+                "Type",
+                format!("{} (0x{:x})", self.tx_type.value(), self.tx_type.byte()),
+            ),
             ("From", self.from.clone()),
-            ("To", to),
+            ("To", or_unknown(self.to.clone())),
             ("Value", self.value.to_string()),
-            ("Block Number", self.block_number.to_string()),
-            ("Block Hash", self.block_hash.clone()),
-            ("Gas", self.gas.to_string()),
-            ("Gas Price", self.gas_price.to_string()),
-            ("Max priority fee per gas", max_priority_fee_per_gas),
-            ("Max fee per gas", max_fee_per_gas),
-            ("Nonce", self.nonce.to_string()),
-            ("Tx Idx", self.tx_idx.to_string()),
         ];
 
+        // A transaction decoded offline has no status to show.
+        if !self.is_pending() {
+            lines.push((
+                "Block Number",
+                or_unknown(self.block_number.map(|val| val.to_string())),
+            ));
+            lines.push(("Block Hash", or_unknown(self.block_hash.clone())));
+            lines.push(("Tx Idx", or_unknown(self.tx_idx.map(|val| val.to_string()))));
+        } else if !self.offline {
+            lines.push(("Status", "Pending".to_string()));
+        }
+
+        if let Some(chain_id) = self.chain_id {
+            lines.push(("Chain Id", chain_id.to_string()));
+        }
+        lines.push(("Nonce", self.nonce.to_string()));
+        lines.push(("Gas", self.gas.to_string()));
+
+        match self.tx_type {
+            TxType::Legacy | TxType::AccessList => {
+                lines.push((
+                    "Gas Price",
+                    or_unknown(self.gas_price.map(|val| val.to_string())),
+                ));
+            }
+            _ => {
+                if let Some(gas_price) = self.gas_price {
+                    lines.push(("Effective Gas Price", gas_price.to_string()));
+                }
+                lines.push((
+                    "Max priority fee per gas",
+                    or_unknown(self.max_priority_fee_per_gas.map(|val| val.to_string())),
+                ));
+                lines.push((
+                    "Max fee per gas",
+                    or_unknown(self.max_fee_per_gas.map(|val| val.to_string())),
+                ));
+            }
+        }
+
+        if let Some(max_fee_per_blob_gas) = self.max_fee_per_blob_gas {
+            lines.push(("Max fee per blob gas", max_fee_per_blob_gas.to_string()));
+        }
+
+        if let Some(selector) = self.selector() {
+            lines.push(("Selector", selector));
+        }
+        lines.push(("Input", self.input.clone()));
+
+        if let Some(v) = self.v {
+            lines.push(("V", v.to_string()));
+        }
+        if let Some(y_parity) = self.y_parity {
+            lines.push(("Y Parity", y_parity.to_string()));
+        }
+        lines.push(("R", or_unknown(self.r.clone())));
+        lines.push(("S", or_unknown(self.s.clone())));
+
         for (label, value) in lines {
             write!(f, "{}", format_label_and_value(label, &value))?;
         }
 
+        if let Some(access_list) = &self.access_list {
+            writeln!(f, "Access List ({} entries): ", access_list.len())?;
+            for item in access_list {
+                write!(f, "{}", format_label_and_value("  Address", &item.address))?;
+                for key in &item.storage_keys {
+                    write!(f, "{}", format_label_and_value("    Storage Key", key))?;
+                }
+            }
+        }
+
+        if let Some(hashes) = &self.blob_versioned_hashes {
+            writeln!(f, "Blob Versioned Hashes ({}): ", hashes.len())?;
+            for hash in hashes {
+                write!(f, "{}", format_label_and_value("  Blob", hash))?;
+            }
+        }
+
+        if let Some(authorizations) = &self.authorization_list {
+            writeln!(f, "Authorization List ({} entries): ", authorizations.len())?;
+            for auth in authorizations {
+                let lines = [
+                    ("  Address", auth.address.clone()),
+                    ("    Chain Id", auth.chain_id.to_string()),
+                    ("    Nonce", auth.nonce.to_string()),
+                    ("    Y Parity", auth.y_parity.to_string()),
+                    ("    R", auth.r.clone()),
+                    ("    S", auth.s.clone()),
+                ];
+                for (label, value) in lines {
+                    write!(f, "{}", format_label_and_value(label, &value))?;
+                }
+            }
+        }
+
         write!(f, "")
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Log {
+    pub address: String,
+    pub topics: Vec<String>,
+    pub data: String,
+    #[serde(rename = "logIndex")]
+    pub log_index: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReceiptHex {
+    #[serde(rename = "transactionHash")]
+    tx_hash: String,
+    #[serde(rename = "contractAddress")]
+    contract_address: Option<String>,
+    #[serde(rename = "cumulativeGasUsed")]
+    cumulative_gas_used: String,
+    #[serde(rename = "gasUsed")]
+    gas_used: String,
+    #[serde(rename = "effectiveGasPrice")]
+    effective_gas_price: Option<String>,
+    status: Option<String>,
+    /// Post-transaction state root of pre-Byzantium receipts.
+    root: Option<String>,
+    #[serde(rename = "logsBloom")]
+    logs_bloom: Option<String>,
+    #[serde(rename = "type")]
+    tx_type: Option<String>,
+    logs: Vec<Log>,
+    /// Blob transactions (EIP-4844).
+    #[serde(rename = "blobGasUsed")]
+    blob_gas_used: Option<String>,
+    #[serde(rename = "blobGasPrice")]
+    blob_gas_price: Option<String>,
+    /// Fee for posting the transaction to L1 on OP stack chains.
+    #[serde(rename = "l1Fee")]
+    l1_fee: Option<String>,
+}
+
+impl ReceiptHex {
+    pub fn parse(&self) -> Receipt {
+        let hex_opt = |val: &Option<String>| val.as_ref().map(|v| hex_to_decimals(v, true));
+
+        Receipt {
+            tx_hash: self.tx_hash.clone(),
+            contract_address: self.contract_address.clone(),
+            cumulative_gas_used: hex_to_decimals(&self.cumulative_gas_used, true),
+            gas_used: hex_to_decimals(&self.gas_used, true),
+            effective_gas_price: hex_opt(&self.effective_gas_price),
+            status: hex_opt(&self.status).map(|val| val as u8),
+            logs: self.logs.clone(),
+            blob_gas_used: hex_opt(&self.blob_gas_used),
+            blob_gas_price: hex_opt(&self.blob_gas_price),
+            l1_fee: hex_opt(&self.l1_fee),
+        }
+    }
+
+    /// Consensus encoding stored in the receipts trie, prefixed by the type for typed transactions.
+    pub fn encode(&self) -> Result<Vec<u8>, String> {
+        let outcome = match (&self.status, &self.root) {
+            (Some(status), _) => rlp_uint(status)?,
+            (None, Some(root)) => rlp_bytes(root)?,
+            (None, None) => return Err("missing status".to_string()),
+        };
+        let logs = self
+            .logs
+            .iter()
+            .map(|log| {
+                let topics = log.topics.iter().map(|topic| rlp_bytes(topic)).collect::<Result<_, _>>()?;
+                Ok(Rlp::List(vec![rlp_bytes(&log.address)?, Rlp::List(topics), rlp_bytes(&log.data)?]))
+            })
+            .collect::<Result<_, String>>()?;
+
+        let fields = vec![
+            outcome,
+            rlp_uint(&self.cumulative_gas_used)?,
+            rlp_bytes(required(&self.logs_bloom, "logsBloom")?)?,
+            Rlp::List(logs),
+        ];
+        let mut encoded = match self.tx_type.as_ref().map(|val| hex_to_decimals(val, true) as u8) {
+            Some(tx_type) if tx_type > 0 => vec![tx_type],
+            _ => vec![],
+        };
+        encoded.extend(Rlp::List(fields).encode());
+        Ok(encoded)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Receipt {
+    pub tx_hash: String,
+    pub contract_address: Option<String>,
+    pub cumulative_gas_used: i128,
+    pub gas_used: i128,
+    pub effective_gas_price: Option<i128>,
+    /// `None` for pre-Byzantium receipts which carry a state root instead.
+    pub status: Option<u8>,
+    pub logs: Vec<Log>,
+    pub blob_gas_used: Option<i128>,
+    pub blob_gas_price: Option<i128>,
+    pub l1_fee: Option<i128>,
+}
@@ -1,17 +1,94 @@
 use colored::Colorize;
+use sha3::{Digest, Keccak256};
+use std::{env, path::PathBuf};
 
-pub fn hex_to_decimals(hex: &String, with_prefix: bool) -> i128 {
-    let mut value = hex.as_str();
-    if with_prefix == true {
+pub fn hex_to_decimals(hex: &str, with_prefix: bool) -> i128 {
+    let mut value = hex;
+    if with_prefix {
         value = hex.trim_start_matches("0x");
     }
-    return i128::from_str_radix(value, 16).unwrap();
+    i128::from_str_radix(value, 16).unwrap()
 }
 
-pub fn format_label_and_value(label: &str, value: &String) -> std::string::String {
+/// Like `hex_to_decimals`, for untrusted input: an error instead of a panic when the
+/// quantity isn't hex or doesn't fit in an i128. `0x` is zero, as in RLP.
+pub fn checked_hex_to_decimals(hex: &str) -> Result<i128, String> {
+    match hex.trim_start_matches("0x") {
+        "" => Ok(0),
+        value => i128::from_str_radix(value, 16).map_err(|e| match e.kind() {
+            std::num::IntErrorKind::PosOverflow => format!("{} is too large", hex),
+            _ => format!("{} is not a hex quantity", hex),
+        }),
+    }
+}
+
+pub fn format_label_and_value(label: &str, value: &str) -> std::string::String {
     format!(
         "{}: {}\n",
-        format!("{}", label).bold().underline().white().on_green(),
-        format!("{}", value).bold().underline()
+        label.bold().underline().white().on_green(),
+        value.bold().underline()
     )
-}
\ No newline at end of file
+}
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+pub fn decode_hex(value: &str) -> Result<Vec<u8>, String> {
+    let value = value.trim().trim_start_matches("0x");
+    if value.len() % 2 == 1 {
+        return hex::decode(format!("0{}", value)).map_err(|e| e.to_string());
+    }
+    hex::decode(value).map_err(|e| e.to_string())
+}
+
+pub fn encode_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Converts a big endian unsigned integer of any width into its decimal representation.
+pub fn be_bytes_to_decimal(bytes: &[u8]) -> String {
+    let mut digits: Vec<u8> = vec![0];
+    for byte in bytes {
+        let mut carry = *byte as u32;
+        for digit in digits.iter_mut() {
+            let value = *digit as u32 * 256 + carry;
+            *digit = (value % 10) as u8;
+            carry = value / 10;
+        }
+        while carry > 0 {
+            digits.push((carry % 10) as u8);
+            carry /= 10;
+        }
+    }
+
+    digits.iter().rev().map(|d| (b'0' + d) as char).collect()
+}
+
+/// Directory used to persist local state (signature database, caches, ...).
+/// Can be overridden with the `BLOCKCHAIN_CLI_HOME` env var.
+pub fn data_dir() -> PathBuf {
+    match env::var("BLOCKCHAIN_CLI_HOME") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".blockchain-cli"),
+    }
+}
+
+/// Formats an integer amount of base units (e.g. wei) with `decimals` fractional digits.
+pub fn format_units(value: i128, decimals: u32) -> String {
+    let base = 10i128.pow(decimals);
+    let sign = if value < 0 { "-" } else { "" };
+    let value = value.unsigned_abs();
+    let base = base as u128;
+    let fraction = format!("{:0width$}", value % base, width = decimals as usize);
+    let fraction = fraction.trim_end_matches('0');
+
+    match fraction.is_empty() {
+        true => format!("{}{}", sign, value / base),
+        false => format!("{}{}.{}", sign, value / base, fraction),
+    }
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use common::ADDR_A;
+use ethereum::{
+    abi::{self, Abi, AbiItem, ParamType, Token},
+    signatures::SignatureDb,
+    utils::{decode_hex, encode_hex, keccak256},
+};
+use serde_json::json;
+
+#[test]
+fn abi_selectors_use_canonical_types() {
+    let item = AbiItem::from_signature("function", "transfer(address, uint)").unwrap();
+    assert_eq!(item.signature().unwrap(), "transfer(address,uint256)");
+    assert_eq!(item.selector().unwrap(), "0xa9059cbb");
+
+    // Tuples and arrays canonicalize recursively, `tuple` components included.
+    let items: Vec<AbiItem> = serde_json::from_value(json!([
+        {
+            "type": "function",
+            "name": "aggregate",
+            "inputs": [{
+                "name": "calls",
+                "type": "tuple[]",
+                "components": [
+                    { "name": "target", "type": "address" },
+                    { "name": "callData", "type": "bytes" }
+                ]
+            }]
+        },
+        {
+            "type": "function",
+            "name": "exactInputSingle",
+            "inputs": [{
+                "name": "params",
+                "type": "tuple",
+                "components": [
+                    { "type": "address" }, { "type": "address" }, { "type": "uint24" },
+                    { "type": "address" }, { "type": "uint256" }, { "type": "uint256" },
+                    { "type": "uint256" }, { "type": "uint160" }
+                ]
+            }]
+        },
+        {
+            "type": "function",
+            "name": "schedule",
+            "inputs": [{ "type": "function" }, { "type": "uint8[2][]" }]
+        }
+    ]))
+    .unwrap();
+    let abi = Abi { items };
+    let aggregate = abi.function_by_selector("0x252dba42").unwrap();
+    assert_eq!(
+        aggregate.signature().unwrap(),
+        "aggregate((address,bytes)[])"
+    );
+    let swap = abi.function_by_selector("0x414bf389").unwrap();
+    assert_eq!(swap.name, "exactInputSingle");
+
+    // External function parameters hash as `function`, not as the bytes24 they encode to.
+    let schedule = &abi.items[2];
+    assert_eq!(
+        schedule.signature().unwrap(),
+        "schedule(function,uint8[2][])"
+    );
+    let selector = encode_hex(&keccak256(b"schedule(function,uint8[2][])")[..4]);
+    assert_eq!(schedule.selector().unwrap(), selector);
+    assert_eq!(
+        abi.function_by_selector(&selector).unwrap().name,
+        "schedule"
+    );
+
+    assert!(ParamType::parse("uint7").is_err());
+    assert!(ParamType::parse("bytes33").is_err());
+}
+
+#[test]
+fn abi_decodes_static_and_dynamic_params() {
+    let word = |hex: &str| format!("{:0>64}", hex);
+    let data = [
+        word("2a"),
+        "f".repeat(64),
+        word("a0"),
+        word("e0"),
+        format!(
+            "{:0<64}",
+            format!("{}{}", ADDR_A.trim_start_matches("0x"), "a9059cbb")
+        ),
+        word("2"),
+        format!("{:0<64}", "6869"),
+        word("2"),
+        word("1"),
+        word("2"),
+    ]
+    .concat();
+    let data = decode_hex(&data).unwrap();
+    let types: Vec<ParamType> = ["uint256", "int256", "string", "uint16[]", "function"]
+        .iter()
+        .map(|kind| ParamType::parse(kind).unwrap())
+        .collect();
+
+    let tokens = abi::decode(&types, &data).unwrap();
+    assert_eq!(tokens[0], Token::Uint("42".to_string()));
+    assert_eq!(tokens[1], Token::Int("-1".to_string()));
+    assert_eq!(tokens[2], Token::String("hi".to_string()));
+    assert_eq!(tokens[3].to_string(), "[1, 2]");
+    assert_eq!(tokens[4], Token::FixedBytes(format!("{}a9059cbb", ADDR_A)));
+
+    // Truncated data and values wider than their type are rejected.
+    assert!(abi::decode(&types, &data[..100]).is_err());
+    let uint8 = [ParamType::parse("uint8").unwrap()];
+    assert!(abi::decode(&uint8, &decode_hex(&word("100")).unwrap()).is_err());
+    // Array sizes are checked against the data before anything is allocated.
+    let huge = [ParamType::parse("uint256[100000000000]").unwrap()];
+    assert!(abi::decode(&huge, &decode_hex(&word("1")).unwrap()).is_err());
+    let nested = [ParamType::parse("uint256[4294967296][4294967296]").unwrap()];
+    assert!(abi::decode(&nested, &[]).is_err());
+
+    let selectors = SignatureDb::load();
+    assert!(selectors
+        .lookup("0xA9059CBB")
+        .contains(&"transfer(address,uint256)".to_string()));
+    let candidates = selectors.candidates("0x252dba42");
+    assert_eq!(
+        candidates[0].signature().unwrap(),
+        "aggregate((address,bytes)[])"
+    );
+}
@@ -0,0 +1,775 @@
+mod common;
+
+use common::{
+    block_hash, chain_fixture, fixture_path, tx_hash, MockServer, ADDR_A, ADDR_B, CLONE, EIP155_TX,
+    PROXY, TOKEN,
+};
+use serde_json::json;
+use std::{
+    fs,
+    io::{Read, Write},
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+use tempfile::TempDir;
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Runs the CLI against `rpc` with `inputs` fed to stdin, one per line, and returns stdout.
+/// Every script must end by answering `N` to "Continue?".
+fn run(rpc: &str, home: &Path, inputs: &[&str], envs: &[(&str, &str)]) -> String {
+    run_args(rpc, home, &[], inputs, envs).0
+}
+
+/// Like `run` with command line `args`, returns stdout and stderr.
+fn run_args(
+    rpc: &str,
+    home: &Path,
+    args: &[&str],
+    inputs: &[&str],
+    envs: &[(&str, &str)],
+) -> (String, String) {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_ethereum"));
+    cmd.args(args)
+        .env("BLOCKCHAIN_CLI_HOME", home)
+        .env("BLOCKCHAIN_CLI_CACHE", "off")
+        .env("NO_COLOR", "1")
+        .env("CLICOLOR", "0")
+        .env_remove("BLOCKCHAIN_CLI_PROFILE")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    match rpc.is_empty() {
+        true => cmd.env_remove("BLOCKCHAIN_CLI_RPC"),
+        false => cmd.env("BLOCKCHAIN_CLI_RPC", rpc),
+    };
+    for (key, value) in envs {
+        cmd.env(key, value);
+    }
+
+    let mut child = cmd.spawn().unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(inputs.join("\n").as_bytes()).unwrap();
+    stdin.write_all(b"\n").unwrap();
+    drop(stdin);
+
+    let read_all = |mut pipe: Box<dyn Read + Send>| {
+        thread::spawn(move || {
+            let mut out = String::new();
+            pipe.read_to_string(&mut out).unwrap();
+            out
+        })
+    };
+    let stdout = read_all(Box::new(child.stdout.take().unwrap()));
+    let stderr = read_all(Box::new(child.stderr.take().unwrap()));
+
+    let start = Instant::now();
+    loop {
+        if child.try_wait().unwrap().is_some() {
+            break;
+        }
+        if start.elapsed() > TIMEOUT {
+            child.kill().unwrap();
+            panic!("CLI did not exit, inputs: {:?}", inputs);
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    (stdout.join().unwrap(), stderr.join().unwrap())
+}
+
+fn run_default(inputs: &[&str]) -> (MockServer, String) {
+    let server = MockServer::start(chain_fixture());
+    let home = TempDir::new().unwrap();
+    let out = run(&server.url, home.path(), inputs, &[]);
+    (server, out)
+}
+
+#[test]
+fn connects_and_detects_the_chain() {
+    let (server, out) = run_default(&["3", "N"]);
+    assert!(out.contains(&format!("Profile default ({}, failover)", server.url)));
+    assert!(out.contains("Connected to Ethereum Mainnet (chain id 1)"));
+    assert!(out.contains("16) Rate limiter stats"));
+}
+
+#[test]
+fn option_1_accounts() {
+    let (_, out) = run_default(&["1", "N"]);
+    assert!(out.contains(ADDR_A));
+}
+
+#[test]
+fn option_2_gas_price() {
+    let (_, out) = run_default(&["2", "N"]);
+    assert!(out.contains("> 20000000000 Wei"));
+}
+
+#[test]
+fn option_3_block_number() {
+    let (server, out) = run_default(&["3", "N"]);
+    assert!(out.contains("> 3 block"));
+    assert_eq!(server.count("eth_blockNumber"), 1);
+}
+
+#[test]
+fn option_4_balance() {
+    let (server, out) = run_default(&["4", ADDR_A, "N"]);
+    assert!(out.contains("> 1.5 ETH"));
+    assert!(out.contains("> 1500000000000000000 Wei"));
+    assert!(out.contains(&format!("https://etherscan.io/address/{}", ADDR_A)));
+
+    let request = server
+        .requests()
+        .into_iter()
+        .find(|req| req["method"] == "eth_getBalance")
+        .unwrap();
+    assert_eq!(request["params"], json!([ADDR_A, "latest"]));
+}
+
+#[test]
+fn option_5_tx_count() {
+    let (_, out) = run_default(&["5", ADDR_A, "N"]);
+    assert!(out.contains("> 5 Transaction"));
+}
+
+#[test]
+fn option_6_block_tx_count() {
+    let (server, out) = run_default(&["6", "3", "Y", "6", "nope", "N"]);
+    assert!(out.contains("> 2 Transaction"));
+    assert!(out.contains("Invalid block number"));
+    assert_eq!(server.count("eth_getBlockTransactionCountByNumber"), 1);
+}
+
+#[test]
+fn option_7_block() {
+    let (server, out) = run_default(&["7", "2", "N"]);
+    assert!(out.contains(&block_hash(2)));
+    assert!(out.contains(&block_hash(1)));
+
+    let request = server
+        .requests()
+        .into_iter()
+        .find(|req| req["method"] == "eth_getBlockByNumber" && req["params"][1] == true)
+        .unwrap();
+    assert_eq!(request["params"][0], "0x2");
+}
+
+#[test]
+fn option_8_tx_with_decoded_calldata() {
+    let (_, out) = run_default(&["8", &tx_hash(2), "", "N"]);
+    assert!(out.contains(&format!("https://etherscan.io/tx/{}", tx_hash(2))));
+    assert!(out.contains("transfer(address,uint256)"));
+    assert!(out.contains("1000000"));
+}
+
+#[test]
+fn option_8_legacy_tx_without_calldata() {
+    let (_, out) = run_default(&["8", &tx_hash(1), "N"]);
+    assert!(out.contains(ADDR_B));
+    assert!(!out.contains("ABI file"));
+    assert!(out.contains("Computed Hash: ok"));
+    assert!(out.contains("Recovered Sender: ok"));
+    assert!(out.contains("Gas Used: 21000 of 21000 (100.0%)"));
+    assert!(out.contains("Effective Gas Price: 20 gwei"));
+    assert!(out.contains("Base Fee: 1 gwei, 0.000021 ETH burnt"));
+    assert!(out.contains("Priority Tip: 19 gwei, 0.000399 ETH to the fee recipient"));
+    assert!(out.contains("Total Fee: 0.00042 ETH"));
+    assert!(out.contains("Total Cost: 1.00042 ETH (fee and value)"));
+}
+
+#[test]
+fn option_8_blob_fees() {
+    let (_, out) = run_default(&["8", &tx_hash(4), "N"]);
+    assert!(out.contains("Blob Fee: 131072 blob gas at 1 wei, 0.000000000000131072 ETH burnt"));
+    assert!(out.contains("Total Fee: 0.000021000000131072 ETH"));
+}
+
+#[test]
+fn option_8_flags_a_forged_sender() {
+    let mut fixture = chain_fixture();
+    fixture["eth_getTransactionByHash"][0]["result"]["from"] = json!(ADDR_B);
+    let server = MockServer::start(fixture);
+    let home = TempDir::new().unwrap();
+    let out = run(&server.url, home.path(), &["8", &tx_hash(1), "N"], &[]);
+    assert!(out.contains("Computed Hash: ok"));
+    assert!(out.contains(&format!(
+        "Recovered Sender: MISMATCH, signature recovers to {}",
+        ADDR_A
+    )));
+}
+
+#[test]
+fn decode_tx_without_a_node() {
+    let home = TempDir::new().unwrap();
+    let (out, _) = run_args("", home.path(), &["decode-tx", EIP155_TX], &[], &[]);
+    assert!(
+        out.contains("Hash: 0x33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788")
+    );
+    assert!(out.contains("From: 0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"));
+    assert!(out.contains("To: 0x3535353535353535353535353535353535353535"));
+    assert!(out.contains("Value: 1000000000000000000"));
+    assert!(out.contains("Chain Id: 1"));
+    assert!(out.contains("Nonce: 9"));
+    assert!(out.contains("Gas Price: 20000000000"));
+    assert!(!out.contains("Status"));
+    assert!(!out.contains("Connected"));
+
+    // Same transfer with a value of 2^200, validly signed but too large to show.
+    let huge = "0xf87e098504a817c8008252089435353535353535353535353535353535353535359a01000000000000000000000000000000000000000000000000008025a0856a8c43f9bc022677a45255c04d4102f504d4b181389fbf1ed484ca357357bca06d9667fb0a8daf52932da633137fced92f119fd43c51e662109ae45db4c77a0d";
+    let (out, _) = run_args("", home.path(), &["decode-tx", huge], &[], &[]);
+    assert!(out.contains("Can't decode the transaction: value: 0x1000"));
+    assert!(out.contains("is too large"));
+
+    let (out, _) = run_args("", home.path(), &["decode-tx", "0x01c0"], &[], &[]);
+    assert!(out
+        .contains("Can't decode the transaction: type 1 transaction with 0 fields instead of 11"));
+}
+
+#[test]
+fn option_9_import_signatures() {
+    let server = MockServer::start(chain_fixture());
+    let home = TempDir::new().unwrap();
+    let dump = fixture_path("4byte.json");
+    let out = run(
+        &server.url,
+        home.path(),
+        &["9", dump.to_str().unwrap(), "N"],
+        &[],
+    );
+    assert!(out.contains("> 2 new signatures"));
+
+    let saved = fs::read_to_string(home.path().join("signatures.tsv")).unwrap();
+    assert!(saved.contains("0x449a52f8\tmintTo(address,uint256)"));
+
+    let out = run(
+        &server.url,
+        home.path(),
+        &["9", "/does/not/exist", "N"],
+        &[],
+    );
+    assert!(out.contains("Failed to import signatures"));
+}
+
+#[test]
+fn option_10_scan_addresses() {
+    let server = MockServer::start(chain_fixture());
+    let home = TempDir::new().unwrap();
+    let out = run(
+        &server.url,
+        home.path(),
+        &["10", ADDR_A, "2", "N", "1", "", "N"],
+        &[],
+    );
+    assert!(out.contains("> 2 matches"));
+    assert!(out.contains("> 1..=3 blocks scanned"));
+
+    // A second scan offers to resume after the checkpoint.
+    let out = run(
+        &server.url,
+        home.path(),
+        &["10", ADDR_A, "2", "N", "1", "Y", "", "N"],
+        &[],
+    );
+    assert!(out.contains("Resume from block 4?"));
+    assert!(out.contains("> 0 matches"));
+}
+
+#[test]
+fn option_10_scan_stops_only_when_traces_are_unsupported() {
+    let mut fixture = chain_fixture();
+    fixture
+        .as_object_mut()
+        .unwrap()
+        .remove("debug_traceBlockByNumber");
+    fixture.as_object_mut().unwrap().remove("trace_block");
+    let server = MockServer::start(fixture);
+    let home = TempDir::new().unwrap();
+    let (out, err) = run_args(
+        &server.url,
+        home.path(),
+        &[],
+        &["10", ADDR_A, "2", "Y", "1", "", "N"],
+        &[],
+    );
+    assert!(out.contains("Scan stopped, the node doesn't serve traces"));
+    assert!(err.is_empty());
+
+    // Other failures are reported like any failed request.
+    let server = MockServer::start(chain_fixture());
+    let (out, err) = run_args(
+        &server.url,
+        home.path(),
+        &[],
+        &["10", ADDR_A, "2", "Y", "99", "99", "N"],
+        &[],
+    );
+    assert!(!out.contains("Scan stopped"));
+    assert!(err.contains("Request failed"));
+}
+
+#[test]
+fn options_11_and_12_index_and_query() {
+    let server = MockServer::start(chain_fixture());
+    let home = TempDir::new().unwrap();
+    let out = run(&server.url, home.path(), &["11", "1", "", "N"], &[]);
+    assert!(out.contains("> 3 blocks indexed"));
+    assert!(out.contains("> 3 indexed head"));
+    assert!(home.path().join("index.sqlite").exists());
+
+    let out = run(
+        &server.url,
+        home.path(),
+        &[
+            "12",
+            "address",
+            ADDR_A,
+            "Y",
+            "12",
+            "block",
+            "3",
+            "Y",
+            "12",
+            "time",
+            "1700000000",
+            "1700000000",
+            "N",
+        ],
+        &[],
+    );
+    assert!(out.contains("> 2 transactions"));
+    assert!(out.contains(&tx_hash(4)));
+    assert!(out.contains("> 1 transactions"));
+}
+
+#[test]
+fn option_13_cache() {
+    let (_, out) = run_default(&["13", "N"]);
+    assert!(out.contains("Cache is disabled"));
+
+    let server = MockServer::start(chain_fixture());
+    let home = TempDir::new().unwrap();
+    let out = run(
+        &server.url,
+        home.path(),
+        &[
+            "4", ADDR_A, "Y", "4", ADDR_A, "Y", "13", "stats", "Y", "13", "clear", "N",
+        ],
+        &[("BLOCKCHAIN_CLI_CACHE", "on")],
+    );
+    assert_eq!(server.count("eth_getBalance"), 1);
+    // The chain detected at startup names the cache namespace, it isn't asked again.
+    assert_eq!(server.count("eth_chainId"), 1);
+    assert!(out.contains("> 1 hits this session"));
+    assert!(out.contains("> 1 entries removed"));
+}
+
+#[test]
+fn option_14_node_info() {
+    let (_, out) = run_default(&["14", "N"]);
+    assert!(out.contains("Geth/v1.14.0-mock"));
+    assert!(out.contains("Ethereum Mainnet (1)"));
+    assert!(out.contains("No (synced)"));
+}
+
+#[test]
+fn option_17_inspect_account() {
+    let (server, out) = run_default(&["17", TOKEN, "0", "", "", "2", ADDR_A, "", "", "N"]);
+    assert!(out.contains("Type: Contract"));
+    assert!(out.contains("Code Size: 17 bytes"));
+    assert!(out.contains("> 1000000 as uint"));
+    assert!(
+        out.contains("Slot: 0x8790c3214e827aff5791142cef58005e820af178c6a72561557a8a28621a0977")
+    );
+    assert!(out.contains("> 1000 as uint"));
+    // Two slot reads, plus the three proxy slots checked on a contract.
+    assert_eq!(server.count("eth_getStorageAt"), 5);
+    assert!(!out.contains("Proxy:"));
+
+    let (_, out) = run_default(&["17", ADDR_B, "", "N"]);
+    assert!(out.contains(&format!("Type: EOA delegated to {} (EIP-7702)", ADDR_A)));
+
+    let (_, out) = run_default(&["17", CLONE, "", "N"]);
+    assert!(out.contains(&format!(
+        "Proxy: {} (EIP-1167 minimal proxy) -> {}",
+        CLONE, PROXY
+    )));
+    assert!(out.contains(&format!(
+        "Proxy: {} (EIP-1967) -> {}, admin {}",
+        PROXY, TOKEN, ADDR_B
+    )));
+    assert!(out.contains(&format!("Implementation:\nAddress: {}", TOKEN)));
+
+    // The account is still shown, and its slots can be read, when proxy detection fails.
+    let mut fixture = chain_fixture();
+    fixture.as_object_mut().unwrap().remove("eth_getStorageAt");
+    let server = MockServer::start(fixture);
+    let home = TempDir::new().unwrap();
+    let out = run(&server.url, home.path(), &["17", TOKEN, "", "N"], &[]);
+    assert!(out.contains("Code Size: 17 bytes"));
+    assert!(out.contains("Could not check for a proxy"));
+    assert!(out.contains("Storage slot (empty to finish)"));
+}
+
+#[test]
+fn option_18_verified_balance() {
+    let (server, out) = run_default(&["18", ADDR_A, "", "", "N"]);
+    assert!(out.contains("Proof verified against the state root of block 3"));
+    assert!(out.contains("Balance: 1.5 ETH (verified)"));
+    assert!(out.contains("Nonce: 5 (verified)"));
+    assert_eq!(server.count("eth_getProof"), 1);
+    // Only the header is needed for the state root.
+    assert_eq!(server.count("eth_getBlockByNumber"), 1);
+    assert!(server
+        .requests()
+        .iter()
+        .filter(|req| req["method"] == "eth_getBlockByNumber")
+        .all(|req| req["params"][1] == json!(false)));
+
+    let (_, out) = run_default(&[
+        "18",
+        TOKEN,
+        "3",
+        "0, 0x8790c3214e827aff5791142cef58005e820af178c6a72561557a8a28621a0977, 5",
+        "N",
+    ]);
+    assert!(out.contains("Proof verified against the state root of block 3"));
+    assert!(out.contains("0x3e8 (verified)"));
+
+    // A node lying about the balance is caught.
+    let mut fixture = chain_fixture();
+    fixture["eth_getProof"][0]["result"]["balance"] = json!("0x1bc16d674ec80000");
+    let server = MockServer::start(fixture);
+    let home = TempDir::new().unwrap();
+    let out = run(&server.url, home.path(), &["18", ADDR_A, "", "", "N"], &[]);
+    assert!(out.contains(
+        "Proof verification FAILED: balance is 0x1bc16d674ec80000 but the proof says 0x14d1120d7b160000"
+    ));
+    assert!(out.contains("Balance: 2 ETH (unverified)"));
+}
+
+#[test]
+fn option_19_verify_headers() {
+    let (_, out) = run_default(&["19", "1", "3", "N"]);
+    assert!(out.contains(&format!("Block 1 (Shanghai) {}: ok", block_hash(1))));
+    assert!(out.contains(&format!("Block 3 (Prague) {}: ok", block_hash(3))));
+    assert!(out.contains("> 3 headers checked"));
+    assert!(out.contains("> 0 issues"));
+
+    // A provider serving a modified header gets caught.
+    let mut fixture = chain_fixture();
+    for entry in fixture["eth_getBlockByNumber"].as_array_mut().unwrap() {
+        if entry["params"] == json!(["0x2", false]) {
+            entry["result"]["gasUsed"] = json!("0x1");
+        }
+    }
+    let server = MockServer::start(fixture);
+    let home = TempDir::new().unwrap();
+    let out = run(&server.url, home.path(), &["19", "2", "", "N"], &[]);
+    assert!(out.contains(&format!("Block 2 (Cancun) {}: FAILED", block_hash(2))));
+    assert!(out.contains(&format!(
+        "Block 2: reported hash {} but the header hashes to",
+        block_hash(2)
+    )));
+    assert!(out.contains("> 1 issues"));
+}
+
+#[test]
+fn option_20_verify_block_body() {
+    let (_, out) = run_default(&["20", "3", "N"]);
+    assert!(out.contains(&format!("Block: 3 {}", block_hash(3))));
+    assert!(out.contains("Header Hash: ok"));
+    assert!(out.contains("Transactions Root: ok (2 transactions)"));
+    assert!(out.contains("Receipts Root: ok (2 receipts)"));
+    assert!(out.contains("Block data matches its header"));
+
+    let mut fixture = chain_fixture();
+    for entry in fixture["eth_getBlockReceipts"].as_array_mut().unwrap() {
+        if entry["params"] == json!(["0x3"]) {
+            entry["result"][1]["cumulativeGasUsed"] = json!("0x1");
+        }
+    }
+    let server = MockServer::start(fixture);
+    let home = TempDir::new().unwrap();
+    let out = run(&server.url, home.path(), &["20", "3", "N"], &[]);
+    assert!(out.contains("Transactions Root: ok (2 transactions)"));
+    assert!(out.contains("Receipts Root: MISMATCH, header has 0x"));
+    assert!(out.contains("Block data does NOT match its header"));
+}
+
+#[test]
+fn option_21_trace_transaction() {
+    let server = MockServer::start(chain_fixture());
+    let home = TempDir::new().unwrap();
+    let saved = home.path().join("trace.json");
+    let out = run(
+        &server.url,
+        home.path(),
+        &["21", &tx_hash(2), saved.to_str().unwrap(), "N"],
+        &[],
+    );
+    assert!(out.contains(&format!(
+        "CALL {} -> {}, gas used 46109, transfer(address,uint256)",
+        ADDR_B, TOKEN
+    )));
+    assert!(out.contains("  CALL 0xdac17f958d2ee523a2206206994597c13d831ec7 -> 0x0000000000000000000000000000000000001111, 0.5 ETH"));
+    assert!(out.contains("> 3 calls"));
+    assert!(out.contains("> 0 failed calls"));
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&saved).unwrap()).unwrap();
+    assert_eq!(json["calls"][0]["input"], "0xd0e30db0");
+
+    let out = run(&server.url, home.path(), &["21", &tx_hash(3), "", "N"], &[]);
+    assert!(out.contains("[FAILED: Ownable: caller is not the owner]"));
+    assert!(out.contains("> 1 failed calls"));
+
+    // No debug namespace on the node.
+    let out = run(&server.url, home.path(), &["21", &tx_hash(1), "N"], &[]);
+    assert!(out.contains("Tracing failed:"));
+    let out = run(&server.url, home.path(), &["21", &tx_hash(5), "N"], &[]);
+    assert!(out.contains("Pending transactions can't be traced"));
+}
+
+#[test]
+fn option_22_internal_transfers() {
+    let server = MockServer::start(chain_fixture());
+    let home = TempDir::new().unwrap();
+    let out = run(&server.url, home.path(), &["22", &tx_hash(2), "N"], &[]);
+    assert!(out.contains(&format!(
+        "{} -> 0x0000000000000000000000000000000000001111, 0.5 ETH (CALL in {})",
+        TOKEN,
+        tx_hash(2)
+    )));
+    assert!(out.contains("> 1 internal transfers"));
+    assert!(out.contains("> 0.5 ETH moved internally"));
+
+    let out = run(&server.url, home.path(), &["22", "3", "N"], &[]);
+    assert!(out.contains("> 1 internal transfers"));
+    assert!(out.contains("> 0.2 ETH moved internally"));
+
+    // Scans pick up value moved by contracts.
+    let out = run(
+        &server.url,
+        home.path(),
+        &["10", ADDR_B, "2", "Y", "1", "", "N"],
+        &[],
+    );
+    assert!(out.contains("Transfer: internal (CALL)"));
+    assert!(out.contains("> 3 matches"));
+}
+
+#[test]
+fn option_23_state_diff() {
+    let server = MockServer::start(chain_fixture());
+    let home = TempDir::new().unwrap();
+    let layout = fixture_path("layout.json");
+    let out = run(
+        &server.url,
+        home.path(),
+        &["23", &tx_hash(2), layout.to_str().unwrap(), "", "N"],
+        &[],
+    );
+    assert!(out.contains(&format!("Account: {} (modified)", ADDR_B)));
+    assert!(out.contains("Balance: 1 -> 0.999953891 ETH (-0.000046109)"));
+    assert!(out.contains("Nonce: 0 -> 1"));
+    assert!(out.contains("Account: 0x0000000000000000000000000000000000001111 (created)"));
+    assert!(out.contains(&format!("Slot: balances[{}] (0x8790c321", ADDR_A)));
+    assert!(out.contains(
+        "Slot: holders.length (0x0000000000000000000000000000000000000000000000000000000000000003)"
+    ));
+    assert!(out.contains("> 3 accounts changed"));
+    assert!(out.contains("> 4 storage slots changed"));
+
+    // Without a layout slots are shown raw.
+    let out = run(&server.url, home.path(), &["23", &tx_hash(2), "", "N"], &[]);
+    assert!(out.contains("Slot: 0x8790c321"));
+
+    let out = run(&server.url, home.path(), &["23", &tx_hash(1), "N"], &[]);
+    assert!(out.contains("Tracing failed:"));
+}
+
+#[test]
+fn option_24_gas_profile() {
+    let server = MockServer::start(chain_fixture());
+    let home = TempDir::new().unwrap();
+    let folded = home.path().join("tx.folded");
+    let out = run(
+        &server.url,
+        home.path(),
+        &["24", &tx_hash(2), folded.to_str().unwrap(), "N"],
+        &[],
+    );
+    assert!(out.contains("Execution Gas: 38823"));
+    assert!(out.contains("Intrinsic and Refunds: 7286"));
+    assert!(out.contains("SSTORE: 25000 gas, 2 steps, 64.4%"));
+    assert!(out.contains("2: 20003 gas, 51.5%"));
+    assert!(out.contains(&format!("{} pc 7: CALL 11700 gas, 1 steps, 30.1%", TOKEN)));
+    let stacks = fs::read_to_string(&folded).unwrap();
+    assert_eq!(stacks.lines().count(), 8);
+
+    let out = run(&server.url, home.path(), &["24", &tx_hash(1), "N"], &[]);
+    assert!(out.contains("Tracing failed:"));
+}
+
+#[test]
+fn option_8_explains_a_failed_transaction() {
+    let (_, out) = run_default(&["8", &tx_hash(3), "N"]);
+    assert!(out.contains("Transaction failed, replaying it on its parent block..."));
+    assert!(out.contains("Revert Reason: Ownable: caller is not the owner"));
+
+    // Custom errors are decoded with the ABI the user points to.
+    let mut fixture = chain_fixture();
+    let selector = "0xcf479181";
+    let data = format!("{}{:064x}{:064x}", selector, 5, 10);
+    let len = fixture["eth_call"].as_array().unwrap().len();
+    fixture["eth_call"][len - 1]["error"]["data"] = json!(data);
+    let server = MockServer::start(fixture);
+    let home = TempDir::new().unwrap();
+    let abi = home.path().join("abi.json");
+    let errors = json!([{
+        "type": "error",
+        "name": "InsufficientBalance",
+        "inputs": [
+            { "name": "available", "type": "uint256" },
+            { "name": "required", "type": "uint256" }
+        ]
+    }]);
+    fs::write(&abi, errors.to_string()).unwrap();
+    let out = run(
+        &server.url,
+        home.path(),
+        &["8", &tx_hash(3), abi.to_str().unwrap(), "N"],
+        &[],
+    );
+    assert!(out.contains(&format!(
+        "Revert Reason: InsufficientBalance(uint256,uint256) [{}]",
+        selector
+    )));
+    assert!(out.contains("  required (uint256): 10"));
+}
+
+#[test]
+fn option_15_disagreements_with_quorum() {
+    let honest = MockServer::start(chain_fixture());
+    let mut fixture = chain_fixture();
+    fixture["eth_getBalance"] = json!([{ "result": "0x1" }]);
+    let lying = MockServer::start(fixture);
+
+    let home = TempDir::new().unwrap();
+    let config = json!({
+        "default": "quorum",
+        "profiles": {
+            "quorum": {
+                "rpc": honest.url,
+                "endpoints": [lying.url],
+                "strategy": "quorum",
+                "quorum": 1,
+                "chainId": 10
+            }
+        }
+    });
+    fs::write(home.path().join("config.json"), config.to_string()).unwrap();
+
+    let out = run("", home.path(), &["4", ADDR_A, "Y", "15", "N"], &[]);
+    assert!(out.contains("Profile quorum"));
+    assert!(out.contains("expects OP Mainnet (chain id 10) but the node serves chain id 1"));
+    assert!(out.contains("Warning: endpoints disagree on eth_getBalance"));
+    assert!(out.contains("> 1 disagreements"));
+    assert_eq!(honest.count("eth_getBalance"), 1);
+    assert_eq!(lying.count("eth_getBalance"), 1);
+}
+
+#[test]
+fn option_16_rate_limits() {
+    let server = MockServer::start(chain_fixture());
+    let home = TempDir::new().unwrap();
+    let config = json!({
+        "profiles": {
+            "default": { "rpc": server.url, "rateLimit": { "rps": 1000.0 } }
+        }
+    });
+    fs::write(home.path().join("config.json"), config.to_string()).unwrap();
+
+    let out = run("", home.path(), &["3", "Y", "16", "N"], &[]);
+    assert!(out.contains(&server.url));
+    assert!(out.contains("2 requests"));
+}
+
+#[test]
+fn failover_skips_dead_endpoints() {
+    let server = MockServer::start(chain_fixture());
+    let home = TempDir::new().unwrap();
+    let rpc = format!("http://127.0.0.1:1,{}", server.url);
+    let out = run(&rpc, home.path(), &["3", "N"], &[]);
+    assert!(out.contains("> 3 block"));
+}
+
+#[test]
+fn record_then_replay_offline() {
+    let server = MockServer::start(chain_fixture());
+    let home = TempDir::new().unwrap();
+    let cassette = home.path().join("session.ndjson");
+    let cassette = cassette.to_str().unwrap();
+    let script = [
+        "4",
+        ADDR_A,
+        "Y",
+        "7",
+        "2",
+        "Y",
+        "8",
+        &tx_hash(2),
+        "",
+        "Y",
+        "3",
+        "Y",
+        "3",
+        "N",
+    ];
+
+    let (recorded, _) = run_args(
+        &server.url,
+        home.path(),
+        &["--record", cassette],
+        &script,
+        &[],
+    );
+    assert!(recorded.contains(&format!("Recording to {}", cassette)));
+    let lines = fs::read_to_string(cassette).unwrap();
+    assert_eq!(lines.lines().count(), server.requests().len());
+    assert!(lines.contains("\"method\":\"eth_getBalance\""));
+
+    // Nothing listens on the endpoint anymore, every answer comes from the cassette.
+    let (replayed, _) = run_args(
+        "http://127.0.0.1:1",
+        home.path(),
+        &["--replay", cassette],
+        &script,
+        &[],
+    );
+    assert!(replayed.contains(&format!("Replaying {}", cassette)));
+    assert!(replayed.contains(&format!(
+        "Profile default (replay://{}, failover)",
+        cassette
+    )));
+    let strip = |out: &str| out.lines().skip(2).collect::<Vec<_>>().join("\n");
+    assert_eq!(strip(&recorded), strip(&replayed));
+
+    // Requests missing from the cassette fail loudly.
+    let (_, stderr) = run_args(
+        "",
+        home.path(),
+        &["--replay", cassette],
+        &["5", ADDR_A, "N"],
+        &[],
+    );
+    assert!(stderr.contains("has no recorded response for eth_getTransactionCount"));
+    assert!(!stderr.contains("panicked"));
+
+    // Closing stdin at a prompt ends the session instead of looping on empty answers.
+    let (out, stderr) = run_args("", home.path(), &["--replay", cassette], &["3"], &[]);
+    assert!(out.contains("Continue?(Y/N)"));
+    assert!(stderr.is_empty());
+}
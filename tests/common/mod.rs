@@ -0,0 +1,255 @@
+//! Mock JSON-RPC node used by the integration tests.
+//!
+//! Responses come from a fixture map of `method -> [{ params?, result | error }]`.
+//! The first entry whose `params` equal the request params (or that has no `params`)
+//! answers the request; unknown methods get a `-32601` error like a real node.
+#![allow(dead_code)]
+
+use serde_json::{json, Value};
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+use tokio_tungstenite::tungstenite::{self, Message};
+
+pub const ADDR_A: &str = "0x7e5f4552091a69125d5dfcb7b8c2659029395bdf";
+pub const ADDR_B: &str = "0x2b5ad5c4795c026514f8317c7a215e218dccd6cf";
+pub const TOKEN: &str = "0xdac17f958d2ee523a2206206994597c13d831ec7";
+/// EIP-1967 proxy in front of `TOKEN`, administered by `ADDR_B`.
+pub const PROXY: &str = "0x1967000000000000000000000000000000001967";
+/// EIP-1167 clone of `PROXY`.
+pub const CLONE: &str = "0x1167000000000000000000000000000000001167";
+/// The signed transaction from the EIP-155 example, sent by `0x9d8a62f6...`.
+pub const EIP155_TX: &str = "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+pub fn fixture_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+/// The default chain fixture: blocks 1 to 3 with a legacy, an EIP-1559, an access list
+/// and a blob transaction, their receipts, and a pending EIP-7702 transaction.
+pub fn chain_fixture() -> Value {
+    let content = std::fs::read_to_string(fixture_path("chain.json")).unwrap();
+    serde_json::from_str(&content).unwrap()
+}
+
+/// Hash of the fixture block `number`.
+pub fn block_hash(number: u8) -> String {
+    let params = json!([format!("0x{:x}", number), true]);
+    let fixture = chain_fixture();
+    let block = fixture["eth_getBlockByNumber"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|entry| entry["params"] == params)
+        .unwrap();
+    block["result"]["hash"].as_str().unwrap().to_string()
+}
+
+/// Hash of the fixture transaction `idx` (1 to 4 are mined, 5 is pending).
+pub fn tx_hash(idx: u8) -> String {
+    let fixture = chain_fixture();
+    let entry = &fixture["eth_getTransactionByHash"][idx as usize - 1];
+    entry["params"][0].as_str().unwrap().to_string()
+}
+
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<Value>>>,
+}
+
+impl MockServer {
+    pub fn start(fixtures: Value) -> MockServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let fixtures = Arc::new(fixtures);
+
+        let log = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let fixtures = fixtures.clone();
+                let log = log.clone();
+                thread::spawn(move || handle(stream, &fixtures, &log));
+            }
+        });
+
+        MockServer { url, requests }
+    }
+
+    /// Every JSON-RPC request received so far, batches flattened.
+    pub fn requests(&self) -> Vec<Value> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub fn methods(&self) -> Vec<String> {
+        self.requests()
+            .iter()
+            .filter_map(|req| req["method"].as_str().map(|m| m.to_string()))
+            .collect()
+    }
+
+    pub fn count(&self, method: &str) -> usize {
+        self.methods().iter().filter(|m| *m == method).count()
+    }
+
+    /// Same fixtures over a WebSocket. A subscription notification is pushed before
+    /// every answer, like a node with an active `eth_subscribe`.
+    pub fn start_ws(fixtures: Value) -> MockServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let fixtures = Arc::new(fixtures);
+
+        let log = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let fixtures = fixtures.clone();
+                let log = log.clone();
+                thread::spawn(move || {
+                    let mut socket = match tungstenite::accept(stream) {
+                        Ok(socket) => socket,
+                        Err(_) => return,
+                    };
+                    while let Ok(message) = socket.read_message() {
+                        let request: Value = match message {
+                            Message::Text(text) => serde_json::from_str(&text).unwrap(),
+                            Message::Close(_) => return,
+                            _ => continue,
+                        };
+                        let notification = json!({
+                            "jsonrpc": "2.0",
+                            "method": "eth_subscription",
+                            "params": { "subscription": "0x1", "result": {} }
+                        });
+                        let response = respond(&request, &fixtures, &log);
+                        let _ = socket.write_message(Message::Text(notification.to_string()));
+                        let _ = socket.write_message(Message::Text(response.to_string()));
+                    }
+                });
+            }
+        });
+
+        MockServer { url, requests }
+    }
+
+    /// Same fixtures over a unix socket at `path`. Responses are written without any
+    /// delimiter and split in two writes, like a busy node.
+    #[cfg(unix)]
+    pub fn start_ipc(fixtures: Value, path: &Path) -> MockServer {
+        let listener = UnixListener::bind(path).unwrap();
+        let url = format!("ipc://{}", path.display());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let fixtures = Arc::new(fixtures);
+
+        let log = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let fixtures = fixtures.clone();
+                let log = log.clone();
+                thread::spawn(move || {
+                    let mut writer = stream.try_clone().unwrap();
+                    let reader = BufReader::new(stream);
+                    let requests =
+                        serde_json::Deserializer::from_reader(reader).into_iter::<Value>();
+                    for request in requests.flatten() {
+                        let response = respond(&request, &fixtures, &log).to_string();
+                        let (head, tail) = response.split_at(response.len() / 2);
+                        let _ = writer.write_all(head.as_bytes());
+                        let _ = writer.flush();
+                        thread::sleep(Duration::from_millis(5));
+                        let _ = writer.write_all(tail.as_bytes());
+                    }
+                });
+            }
+        });
+
+        MockServer { url, requests }
+    }
+}
+
+fn handle(stream: TcpStream, fixtures: &Value, log: &Mutex<Vec<Value>>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut content_length = 0;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+            return;
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    if reader.read_exact(&mut body).is_err() {
+        return;
+    }
+
+    let response = match serde_json::from_slice::<Value>(&body) {
+        Ok(Value::Array(batch)) => Value::Array(
+            batch
+                .iter()
+                .map(|request| respond(request, fixtures, log))
+                .collect(),
+        ),
+        Ok(request) => respond(&request, fixtures, log),
+        Err(_) => {
+            json!({ "jsonrpc": "2.0", "id": null, "error": { "code": -32700, "message": "Parse error" } })
+        }
+    };
+
+    let body = response.to_string();
+    let mut stream = stream;
+    let _ = write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+}
+
+fn respond(request: &Value, fixtures: &Value, log: &Mutex<Vec<Value>>) -> Value {
+    log.lock().unwrap().push(request.clone());
+
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request["method"].as_str().unwrap_or("");
+    let params = request.get("params").cloned().unwrap_or(json!([]));
+
+    let entry = fixtures
+        .get(method)
+        .and_then(|entries| entries.as_array())
+        .and_then(|entries| {
+            entries
+                .iter()
+                .find(|entry| entry.get("params").is_none_or(|p| *p == params))
+        });
+
+    match entry {
+        Some(entry) if entry.get("error").is_some() => {
+            json!({ "jsonrpc": "2.0", "id": id, "error": entry["error"] })
+        }
+        Some(entry) => json!({ "jsonrpc": "2.0", "id": id, "result": entry["result"] }),
+        None => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": -32601, "message": format!("the method {} does not exist/is not available", method) }
+        }),
+    }
+}
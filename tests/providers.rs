@@ -0,0 +1,1451 @@
+mod common;
+
+use common::{
+    block_hash, chain_fixture, tx_hash, MockServer, ADDR_A, ADDR_B, CLONE, EIP155_TX, PROXY, TOKEN,
+};
+use ethereum::{
+    abi::{AbiItem, DecodedCall},
+    account::{self, AccountInfo, AccountKind},
+    body::{self, RootStatus},
+    cache::{Cache, Policy},
+    chains::ChainInfo,
+    config::Strategy,
+    cost::TxCost,
+    header::{self, Fork, Header, HeaderIssue},
+    indexer::{IndexError, Indexer},
+    node::NodeInfo,
+    profile::{GasProfile, GasStats},
+    providers::{
+        self, block_param, FixtureProvider, HttpProvider, MultiProvider, Provider, ProviderError,
+        RecordingProvider, ReplayProvider, WsProvider,
+    },
+    proxy::{self, ProxyKind},
+    ratelimit,
+    revert::{self, Revert, RevertReason},
+    rlp::{Rlp, RlpError},
+    scanner::{Direction, Scanner},
+    sender::{self, TxCheck},
+    signatures::SignatureDb,
+    statediff::{AccountStatus, StorageLayout},
+    trace::{self, InternalTransfer},
+    trie::{self, ProofError, EMPTY_ROOT},
+    types::{Method, ResultParser, TransactionHex, TxType},
+    utils::{checked_hex_to_decimals, decode_hex, encode_hex, keccak256},
+};
+use serde_json::{json, Value};
+use std::{env, sync::OnceLock};
+use tempfile::TempDir;
+
+struct Setup {
+    server: MockServer,
+    home: TempDir,
+}
+
+static SETUP: OnceLock<Setup> = OnceLock::new();
+
+/// Config and cache are read once per process, so every test in this binary shares
+/// one data dir and one mock node.
+fn setup() -> &'static Setup {
+    SETUP.get_or_init(|| {
+        let server = MockServer::start(chain_fixture());
+        let home = TempDir::new().unwrap();
+        env::set_var("BLOCKCHAIN_CLI_HOME", home.path());
+        env::set_var("BLOCKCHAIN_CLI_CACHE", "off");
+        env::remove_var("BLOCKCHAIN_CLI_RPC");
+        Setup { server, home }
+    })
+}
+
+fn http() -> HttpProvider {
+    HttpProvider::new(&setup().server.url)
+}
+
+#[test]
+fn checked_quantities() {
+    assert_eq!(checked_hex_to_decimals("0x"), Ok(0));
+    assert_eq!(checked_hex_to_decimals("0x2a"), Ok(42));
+    assert!(checked_hex_to_decimals(&format!("0x1{}", "0".repeat(50))).is_err());
+    assert!(checked_hex_to_decimals("0xzz").is_err());
+}
+
+#[tokio::test]
+async fn out_of_range_transactions_are_invalid() {
+    let mut fixture = chain_fixture();
+    for entry in fixture["eth_getTransactionByHash"].as_array_mut().unwrap() {
+        if entry["params"][0] == json!(tx_hash(1)) {
+            entry["result"]["type"] = json!("0x100");
+        }
+    }
+    let provider = FixtureProvider::new(fixture);
+    assert!(matches!(
+        provider.get_tx(&tx_hash(1)).await,
+        Err(ProviderError::Invalid(_))
+    ));
+    assert!(provider.get_tx(&tx_hash(2)).await.is_ok());
+}
+
+#[test]
+fn block_param_is_hex() {
+    assert_eq!(block_param(Some(12710481)), "0xc1f251");
+    assert_eq!(block_param(Some(0)), "0x0");
+    assert_eq!(block_param(None), "latest");
+}
+
+#[tokio::test]
+async fn scalar_calls() {
+    let provider = http();
+
+    let accounts = provider.get_accounts().await.unwrap();
+    assert_eq!(accounts, vec![ADDR_A.to_string()]);
+
+    assert_eq!(
+        provider.gas_price().await.unwrap().to_decimals(),
+        20_000_000_000
+    );
+    assert_eq!(provider.block_number().await.unwrap().to_decimals(), 3);
+
+    let balance = provider.get_balance(ADDR_A, None).await.unwrap();
+    assert_eq!(balance.to_decimals(), 1_500_000_000_000_000_000);
+    assert_eq!(
+        provider
+            .get_balance(ADDR_B, None)
+            .await
+            .unwrap()
+            .to_decimals(),
+        0
+    );
+
+    assert_eq!(
+        provider
+            .get_tx_count(ADDR_A, None)
+            .await
+            .unwrap()
+            .to_decimals(),
+        5
+    );
+}
+
+#[tokio::test]
+async fn block_tx_count_by_number_and_hash() {
+    let provider = http();
+
+    let count = provider.block_tx_count(None, Some(1)).await.unwrap();
+    assert_eq!(count.to_decimals(), 1);
+    let count = provider.block_tx_count(None, None).await.unwrap();
+    assert_eq!(count.to_decimals(), 2);
+    let count = provider
+        .block_tx_count(Some(&block_hash(3)), None)
+        .await
+        .unwrap();
+    assert_eq!(count.to_decimals(), 2);
+}
+
+#[tokio::test]
+async fn blocks_link_to_their_parent() {
+    let provider = http();
+
+    let mut parent = provider.get_block(None, Some(1)).await.unwrap();
+    assert_eq!(parent.number, 1);
+    assert_eq!(parent.timestamp, 1700000000);
+    assert_eq!(parent.transactions.len(), 1);
+
+    for number in 2..=3 {
+        let block = provider.get_block(None, Some(number)).await.unwrap();
+        assert_eq!(block.number, number);
+        assert_eq!(block.hash, block_hash(number as u8));
+        assert_eq!(block.parent_hash, parent.hash);
+        parent = block;
+    }
+
+    let latest = provider.get_block(None, None).await.unwrap();
+    assert_eq!(latest.hash, block_hash(3));
+    let by_hash = provider
+        .get_block(Some(&block_hash(2)), None)
+        .await
+        .unwrap();
+    assert_eq!(by_hash.number, 2);
+}
+
+#[tokio::test]
+async fn transactions_of_every_type() {
+    let provider = http();
+
+    let legacy = provider.get_tx(&tx_hash(1)).await.unwrap();
+    assert!(matches!(legacy.tx_type, TxType::Legacy));
+    assert_eq!(legacy.from, ADDR_A);
+    assert_eq!(legacy.to.as_deref(), Some(ADDR_B));
+    assert_eq!(legacy.value, 1_000_000_000_000_000_000);
+    assert_eq!(legacy.gas_price, Some(20_000_000_000));
+    assert_eq!(legacy.selector(), None);
+
+    let dynamic = provider.get_tx(&tx_hash(2)).await.unwrap();
+    assert!(matches!(dynamic.tx_type, TxType::DynamicFee));
+    assert_eq!(dynamic.max_fee_per_gas, Some(2_000_000_000));
+    assert_eq!(dynamic.max_priority_fee_per_gas, Some(1_000_000_000));
+    assert_eq!(dynamic.to.as_deref(), Some(TOKEN));
+    assert_eq!(dynamic.selector().as_deref(), Some("0xa9059cbb"));
+
+    let access_list = provider.get_tx(&tx_hash(3)).await.unwrap();
+    assert!(matches!(access_list.tx_type, TxType::AccessList));
+    let items = access_list.access_list.unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].storage_keys.len(), 1);
+
+    let blob = provider.get_tx(&tx_hash(4)).await.unwrap();
+    assert!(matches!(blob.tx_type, TxType::Blob));
+    assert_eq!(
+        blob.blob_versioned_hashes.map(|hashes| hashes.len()),
+        Some(1)
+    );
+    assert_eq!(blob.max_fee_per_blob_gas, Some(1_000_000_000));
+
+    let pending = provider.get_tx(&tx_hash(5)).await.unwrap();
+    assert!(pending.is_pending());
+    assert!(matches!(pending.tx_type, TxType::SetCode));
+    assert!(pending.to_string().contains("Pending"));
+    let authorizations = pending.authorization_list.unwrap();
+    assert_eq!(authorizations.len(), 1);
+    assert_eq!(authorizations[0].nonce, 6);
+}
+
+#[tokio::test]
+async fn transfer_calldata_decodes_with_bundled_signatures() {
+    let provider = http();
+
+    let tx = provider.get_tx(&tx_hash(2)).await.unwrap();
+    let candidates = SignatureDb::load().candidates(&tx.selector().unwrap());
+    let call = DecodedCall::decode(&tx.input, &candidates).unwrap();
+    assert_eq!(call.signature, "transfer(address,uint256)");
+    assert_eq!(call.params[0].2.to_string(), ADDR_A);
+    assert_eq!(call.params[1].2.to_string(), "1000000");
+}
+
+#[tokio::test]
+async fn receipts() {
+    let provider = http();
+
+    let receipt = provider.get_tx_receipt(&tx_hash(2)).await.unwrap().unwrap();
+    assert_eq!(receipt.status, Some(1));
+    assert_eq!(receipt.gas_used, 46109);
+    assert_eq!(receipt.logs.len(), 1);
+    assert_eq!(receipt.logs[0].address, TOKEN);
+
+    let failed = provider.get_tx_receipt(&tx_hash(3)).await.unwrap().unwrap();
+    assert_eq!(failed.status, Some(0));
+
+    let pending = provider.get_tx_receipt(&tx_hash(5)).await.unwrap();
+    assert!(pending.is_none());
+
+    let block = provider.get_block_receipts(Some(3)).await.unwrap().unwrap();
+    assert_eq!(block.len(), 2);
+    assert_eq!(block[1].cumulative_gas_used, 46200);
+
+    // Block 1's node answers eth_getBlockReceipts with "method not found".
+    assert!(provider
+        .get_block_receipts(Some(1))
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn chain_detection() {
+    let provider = http();
+
+    assert_eq!(provider.chain_id().await.unwrap(), Some(1));
+    assert_eq!(provider.net_version().await.unwrap(), Some(1));
+
+    let chain = ChainInfo::detect(&provider).await.unwrap().unwrap();
+    assert_eq!(chain.name, "Ethereum Mainnet");
+    assert_eq!(ChainInfo::current().symbol, "ETH");
+}
+
+#[tokio::test]
+async fn raw_requests() {
+    let provider = http();
+
+    let version = provider
+        .request(Method::ClientVersion, vec![])
+        .await
+        .unwrap();
+    assert_eq!(
+        version.unwrap().as_str(),
+        Some("Geth/v1.14.0-mock/linux-amd64/go1.22")
+    );
+    let listening = provider.request(Method::Listening, vec![]).await.unwrap();
+    assert_eq!(listening.unwrap().as_bool(), Some(true));
+
+    // Unknown methods come back as JSON-RPC errors, which `request` reports as `None`.
+    let sent = provider
+        .request(Method::SendTx, vec![json!("0x00")])
+        .await
+        .unwrap();
+    assert!(sent.is_none());
+}
+
+#[tokio::test]
+async fn node_info() {
+    let provider = http();
+
+    let info = NodeInfo::fetch(&provider).await.unwrap();
+    assert_eq!(
+        info.client_version.as_deref(),
+        Some("Geth/v1.14.0-mock/linux-amd64/go1.22")
+    );
+    assert_eq!(info.peer_count, Some(25));
+    assert_eq!(info.listening, Some(true));
+    assert_eq!(info.latest_block, Some(3));
+    assert_eq!(info.chain.map(|chain| chain.id), Some(1));
+    assert!(info.syncing.is_none());
+}
+
+#[tokio::test]
+async fn accounts_code_and_storage() {
+    let provider = http();
+
+    let eoa = AccountInfo::fetch(&provider, ADDR_A, None).await.unwrap();
+    assert_eq!(eoa.kind, AccountKind::Eoa);
+    assert_eq!(eoa.nonce, 5);
+    assert_eq!(eoa.code_size, 0);
+    assert_eq!(
+        encode_hex(&eoa.code_hash),
+        "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+    );
+
+    let token = AccountInfo::fetch(&provider, TOKEN, None).await.unwrap();
+    assert_eq!(token.kind, AccountKind::Contract);
+    assert_eq!(token.code_size, 17);
+
+    let delegated = AccountInfo::fetch(&provider, ADDR_B, None).await.unwrap();
+    assert_eq!(delegated.kind, AccountKind::Delegated(ADDR_A.to_string()));
+
+    // balances[ADDR_A] of a mapping declared at slot 2.
+    let slot = account::mapping_slot(&account::parse_word("2").unwrap(), ADDR_A).unwrap();
+    let slot = encode_hex(&slot);
+    let value = provider.get_storage_at(TOKEN, &slot, None).await.unwrap();
+    assert_eq!(value.to_decimals(), 1000);
+}
+
+#[test]
+fn storage_slot_helpers() {
+    let zero = account::parse_word("0").unwrap();
+    assert_eq!(account::parse_word("0x0").unwrap(), zero);
+    assert_eq!(
+        account::parse_word("256").unwrap(),
+        account::parse_word("0x100").unwrap()
+    );
+    assert!(account::parse_word("1".repeat(80).as_str()).is_err());
+
+    // Elements of a dynamic array at slot 0 start at keccak256(0).
+    let first = account::array_slot(&zero, 0, 1);
+    assert_eq!(
+        encode_hex(&first),
+        "0x290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563"
+    );
+    assert_eq!(
+        encode_hex(&account::array_slot(&zero, 3, 2)),
+        "0x290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e569"
+    );
+
+    // String keys are hashed as raw bytes, value keys as 32 byte words.
+    assert_eq!(
+        account::mapping_slot(&zero, "\"a\"").unwrap(),
+        keccak256(&[b"a".as_slice(), &zero].concat())
+    );
+    assert_eq!(
+        account::mapping_slot(&zero, "1").unwrap(),
+        keccak256(&[account::parse_word("1").unwrap(), zero].concat())
+    );
+}
+
+#[tokio::test]
+async fn proxies_resolve_to_the_implementation() {
+    let provider = http();
+
+    let hop = proxy::detect(&provider, PROXY, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(hop.kind, ProxyKind::Eip1967);
+    assert_eq!(hop.implementation, TOKEN);
+    assert_eq!(hop.admin.as_deref(), Some(ADDR_B));
+
+    let beacon = "0xbeac000000000000000000000000000000000002";
+    let hop = proxy::detect(
+        &provider,
+        "0xbeac000000000000000000000000000000000001",
+        None,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        hop.kind,
+        ProxyKind::Eip1967Beacon {
+            beacon: beacon.to_string()
+        }
+    );
+    assert_eq!(hop.implementation, TOKEN);
+
+    let hop = proxy::detect(
+        &provider,
+        "0x1822000000000000000000000000000000001822",
+        None,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(hop.kind, ProxyKind::Eip1822);
+
+    assert!(proxy::detect(&provider, TOKEN, None)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(proxy::detect(&provider, ADDR_A, None)
+        .await
+        .unwrap()
+        .is_none());
+
+    // The clone points at the EIP-1967 proxy, which points at the token.
+    let hops = proxy::resolve(&provider, CLONE, None).await.unwrap();
+    let kinds: Vec<ProxyKind> = hops.iter().map(|hop| hop.kind.clone()).collect();
+    assert_eq!(kinds, vec![ProxyKind::Eip1167, ProxyKind::Eip1967]);
+    assert_eq!(hops[0].implementation, PROXY);
+    assert_eq!(proxy::implementation(CLONE, &hops), TOKEN);
+    assert_eq!(proxy::implementation(TOKEN, &[]), TOKEN);
+}
+
+#[test]
+fn rlp_round_trips() {
+    let dog = Rlp::Bytes(b"dog".to_vec());
+    let vectors = [
+        (dog.clone(), "0x83646f67"),
+        (
+            Rlp::List(vec![Rlp::Bytes(b"cat".to_vec()), dog]),
+            "0xc88363617483646f67",
+        ),
+        (Rlp::Bytes(vec![]), "0x80"),
+        (Rlp::Bytes(vec![0x0f]), "0x0f"),
+        (Rlp::uint(&1024u64.to_be_bytes()), "0x820400"),
+        (Rlp::uint(&[0, 0]), "0x80"),
+        (Rlp::List(vec![]), "0xc0"),
+        (
+            Rlp::List(vec![Rlp::List(vec![]), Rlp::List(vec![Rlp::List(vec![])])]),
+            "0xc3c0c1c0",
+        ),
+    ];
+    for (item, encoded) in vectors {
+        assert_eq!(encode_hex(&item.encode()), encoded);
+        assert_eq!(Rlp::decode(&decode_hex(encoded).unwrap()).unwrap(), item);
+    }
+
+    let long = Rlp::Bytes(vec![0xaa; 56]);
+    let encoded = long.encode();
+    assert_eq!(&encoded[..2], &[0xb8, 56]);
+    assert_eq!(Rlp::decode(&encoded).unwrap(), long);
+
+    assert_eq!(Rlp::decode(&[0x81, 0x05]), Err(RlpError::NonCanonical));
+    assert_eq!(
+        Rlp::decode(&[0xb8, 0x05, 1, 2, 3, 4, 5]),
+        Err(RlpError::NonCanonical)
+    );
+    assert_eq!(
+        Rlp::decode(&[0x83, b'd', b'o']),
+        Err(RlpError::UnexpectedEnd)
+    );
+    assert_eq!(Rlp::decode(&[0x80, 0x80]), Err(RlpError::TrailingBytes));
+}
+
+#[tokio::test]
+async fn proofs_verify_against_the_state_root() {
+    let provider = http();
+    let block = provider.get_block(None, None).await.unwrap();
+
+    let proof = provider.get_proof(ADDR_A, &[], Some(3)).await.unwrap();
+    proof.verify(&block.state_root).unwrap();
+
+    // Two stored slots and an empty one, proven absent.
+    let slots = [
+        format!("0x{}", "0".repeat(64)),
+        "0x8790c3214e827aff5791142cef58005e820af178c6a72561557a8a28621a0977".to_string(),
+        format!("0x{:064x}", 5),
+    ];
+    let proof = provider.get_proof(TOKEN, &slots, Some(3)).await.unwrap();
+    proof.verify(&block.state_root).unwrap();
+    let values: Vec<&str> = proof
+        .storage_proof
+        .iter()
+        .map(|s| s.value.as_str())
+        .collect();
+    assert_eq!(values, ["0xf4240", "0x3e8", "0x0"]);
+
+    // An account missing from the state verifies as empty.
+    let dead = "0x000000000000000000000000000000000000dead";
+    let proof = provider.get_proof(dead, &[], Some(3)).await.unwrap();
+    proof.verify(&block.state_root).unwrap();
+    let mut lie = proof.clone();
+    lie.balance = "0x1".to_string();
+    assert!(matches!(
+        lie.verify(&block.state_root),
+        Err(ProofError::Mismatch { field, .. }) if field == "balance"
+    ));
+
+    let proof = provider.get_proof(ADDR_A, &[], Some(3)).await.unwrap();
+    let mut lie = proof.clone();
+    lie.balance = "0x14d1120d7b160001".to_string();
+    assert!(matches!(
+        lie.verify(&block.state_root),
+        Err(ProofError::Mismatch { field, .. }) if field == "balance"
+    ));
+
+    let mut lie = proof.clone();
+    lie.storage_proof.push(ethereum::proof::StorageProof {
+        key: "0x0".to_string(),
+        value: "0x1".to_string(),
+        proof: vec![],
+    });
+    assert!(lie.verify(&block.state_root).is_err());
+
+    let mut tampered = proof.clone();
+    let last = tampered.account_proof.last_mut().unwrap();
+    last.replace_range(last.len() - 2.., "ff");
+    assert!(matches!(
+        tampered.verify(&block.state_root),
+        Err(ProofError::HashMismatch(_))
+    ));
+
+    let other_root = format!("0x{}", "11".repeat(32));
+    assert!(matches!(
+        proof.verify(&other_root),
+        Err(ProofError::HashMismatch(0))
+    ));
+}
+
+fn genesis() -> Value {
+    json!({
+        "hash": "0xd4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3",
+        "parentHash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+        "sha3Uncles": "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347",
+        "miner": "0x0000000000000000000000000000000000000000",
+        "stateRoot": "0xd7f8974fb5ac78d9ac099b9ad5018bedc2ce0a72dad1827a1709da30580f0544",
+        "transactionsRoot": "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+        "receiptsRoot": "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+        "logsBloom": format!("0x{}", "00".repeat(256)),
+        "difficulty": "0x400000000",
+        "number": "0x0",
+        "gasLimit": "0x1388",
+        "gasUsed": "0x0",
+        "timestamp": "0x0",
+        "extraData": "0x11bbe8db4e347b4e8c937c1c8370e4b5ed33adb3db69cbdb7a38e1e50b1b82fa",
+        "mixHash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+        "nonce": "0x0000000000000042",
+        "size": "0x21c",
+        "totalDifficulty": "0x400000000",
+        "transactions": [],
+        "uncles": []
+    })
+}
+
+#[tokio::test]
+async fn headers_hash_across_forks() {
+    // Mainnet genesis, a pre-London header.
+    let genesis: Header = serde_json::from_value(genesis()).unwrap();
+    assert_eq!(genesis.fork(), Fork::Frontier);
+    genesis.verify().unwrap();
+
+    let provider = http();
+    let mut headers = vec![];
+    for number in 1..=3 {
+        headers.push(provider.get_header(None, Some(number)).await.unwrap());
+    }
+    let forks: Vec<Fork> = headers.iter().map(|header| header.fork()).collect();
+    assert_eq!(forks, [Fork::Shanghai, Fork::Cancun, Fork::Prague]);
+    assert!(header::verify_chain(&headers).is_empty());
+    assert_eq!(headers[2].hash, block_hash(3));
+
+    let mut london = headers[0].clone();
+    london.withdrawals_root = None;
+    assert_eq!(london.fork(), Fork::London);
+    assert!(matches!(
+        london.verify(),
+        Err(HeaderIssue::HashMismatch { .. })
+    ));
+
+    let mut tampered = headers[1].clone();
+    tampered.gas_used = "0x5209".to_string();
+    assert!(matches!(
+        tampered.verify(),
+        Err(HeaderIssue::HashMismatch { number: 2, .. })
+    ));
+
+    let mut gap = headers[1].clone();
+    gap.withdrawals_root = None;
+    assert_eq!(
+        gap.verify(),
+        Err(HeaderIssue::Malformed {
+            number: 2,
+            message: "blobGasUsed without withdrawalsRoot".to_string()
+        })
+    );
+
+    let issues = header::verify_chain(&[headers[0].clone(), headers[2].clone()]);
+    assert_eq!(
+        issues,
+        [HeaderIssue::BrokenLink {
+            number: 3,
+            parent_hash: block_hash(2),
+            previous_hash: block_hash(1),
+        }]
+    );
+}
+
+#[tokio::test]
+async fn pre_london_blocks_parse() {
+    let provider = FixtureProvider::new(json!({
+        "eth_getBlockByNumber": [{ "result": genesis() }]
+    }));
+    let block = provider.get_block(None, Some(0)).await.unwrap();
+    assert_eq!(block.base_gas_fee, None);
+    assert_eq!(block.number, 0);
+}
+
+#[tokio::test]
+async fn block_bodies_match_their_roots() {
+    assert_eq!(trie::ordered_root(&[]), EMPTY_ROOT);
+
+    // Block 1 has no eth_getBlockReceipts and falls back to per transaction receipts.
+    let provider = http();
+    for number in 1..=3 {
+        let report = body::verify_block(&provider, Some(number)).await.unwrap();
+        assert!(report.is_valid(), "{}", report);
+        assert_eq!(report.hash, block_hash(number as u8));
+    }
+    let report = body::verify_block(&provider, Some(3)).await.unwrap();
+    assert_eq!(report.transactions.count, 2);
+    assert_eq!(report.receipts.count, 2);
+
+    // A node hiding a transaction or altering a receipt no longer matches the header.
+    let mut fixture = chain_fixture();
+    for entry in fixture["eth_getBlockByNumber"].as_array_mut().unwrap() {
+        if entry["params"] == json!(["0x3", true]) {
+            entry["result"]["transactions"]
+                .as_array_mut()
+                .unwrap()
+                .pop();
+        }
+    }
+    for entry in fixture["eth_getBlockReceipts"].as_array_mut().unwrap() {
+        if entry["params"] == json!(["0x2"]) {
+            entry["result"][0]["status"] = json!("0x0");
+        }
+    }
+    let provider = FixtureProvider::new(fixture);
+
+    let report = body::verify_block(&provider, Some(3)).await.unwrap();
+    assert!(report.header.is_ok());
+    assert!(matches!(
+        report.transactions.status,
+        RootStatus::Mismatch { .. }
+    ));
+    assert_eq!(report.transactions.count, 1);
+    assert_eq!(report.receipts.status, RootStatus::Match);
+
+    let report = body::verify_block(&provider, Some(2)).await.unwrap();
+    assert_eq!(report.transactions.status, RootStatus::Match);
+    assert!(matches!(
+        report.receipts.status,
+        RootStatus::Mismatch { .. }
+    ));
+    assert!(!report.is_valid());
+
+    // A receipt the node can't find leaves the root unchecked rather than mismatched.
+    let mut fixture = chain_fixture();
+    for entry in fixture["eth_getTransactionReceipt"].as_array_mut().unwrap() {
+        if entry["params"] == json!([tx_hash(1)]) {
+            entry["result"] = Value::Null;
+        }
+    }
+    let report = body::verify_block(&FixtureProvider::new(fixture), Some(1))
+        .await
+        .unwrap();
+    assert_eq!(report.transactions.status, RootStatus::Match);
+    assert_eq!(
+        report.receipts.status,
+        RootStatus::Unavailable(format!("receipt of {} is missing", tx_hash(1)))
+    );
+}
+
+#[tokio::test]
+async fn transactions_hash_and_recover_their_sender() {
+    let encoded = decode_hex(EIP155_TX).unwrap();
+    assert_eq!(
+        encode_hex(&keccak256(&encoded)),
+        "0x33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788"
+    );
+    assert_eq!(
+        sender::sender(&encoded).unwrap(),
+        "0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"
+    );
+
+    // One transaction of each type, legacy to EIP-7702.
+    let provider = http();
+    let mut types = vec![];
+    for idx in 1..=5 {
+        let tx = provider.get_tx(&tx_hash(idx)).await.unwrap();
+        let check = tx.check().unwrap();
+        assert!(check.is_valid(), "{}", check);
+        types.push(tx.tx_type.byte());
+    }
+    assert_eq!(types, [0, 2, 1, 3, 4]);
+
+    let mut json = chain_fixture()["eth_getTransactionByHash"][1]["result"].clone();
+    json["value"] = json!("0x1");
+    let tampered: TransactionHex = serde_json::from_value(json).unwrap();
+    let check = TxCheck::new(&tx_hash(2), ADDR_B, &tampered.encode().unwrap());
+    assert!(!check.hash_matches());
+    assert!(!check.sender_matches());
+
+    assert_eq!(
+        sender::sender(&[0x05, 0xc0]),
+        Err("transaction with 0 fields".to_string())
+    );
+}
+
+#[tokio::test]
+async fn raw_transactions_decode_offline() {
+    let tx = TransactionHex::decode(&decode_hex(EIP155_TX).unwrap())
+        .unwrap()
+        .try_parse()
+        .unwrap();
+    assert_eq!(tx.tx_type, TxType::Legacy);
+    assert_eq!(tx.chain_id, Some(1));
+    assert_eq!(tx.nonce, 9);
+    assert_eq!(tx.gas_price, Some(20_000_000_000));
+    assert_eq!(tx.gas, 21000);
+    assert_eq!(tx.value, 1_000_000_000_000_000_000);
+    assert_eq!(tx.from, "0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f");
+    assert!(tx.is_pending());
+
+    // Every fixture transaction decodes back to what the node reported.
+    let provider = http();
+    for idx in 1..=5 {
+        let fetched = provider.get_tx(&tx_hash(idx)).await.unwrap();
+        let encoded = fetched.encoded.clone().unwrap();
+        let decoded = TransactionHex::decode(&encoded)
+            .unwrap()
+            .try_parse()
+            .unwrap();
+        assert_eq!(decoded.hash, fetched.hash);
+        assert_eq!(decoded.from, fetched.from);
+        assert_eq!(decoded.tx_type, fetched.tx_type);
+        assert_eq!(decoded.to, fetched.to);
+        assert_eq!(decoded.input, fetched.input);
+        assert_eq!(decoded.max_fee_per_gas, fetched.max_fee_per_gas);
+        assert_eq!(
+            decoded.access_list.map(|list| list.len()),
+            fetched.access_list.map(|list| list.len())
+        );
+        assert_eq!(decoded.blob_versioned_hashes, fetched.blob_versioned_hashes);
+        assert_eq!(
+            decoded.authorization_list.map(|list| list.len()),
+            fetched.authorization_list.map(|list| list.len())
+        );
+    }
+
+    // A blob transaction with its sidecar hashes like the bare transaction.
+    let blob = provider.get_tx(&tx_hash(4)).await.unwrap();
+    let body = Rlp::decode(&blob.encoded.unwrap()[1..]).unwrap();
+    let empty = Rlp::List(vec![]);
+    let network = Rlp::List(vec![body, empty.clone(), empty.clone(), empty]);
+    let raw = [vec![3], network.encode()].concat();
+    assert_eq!(
+        TransactionHex::decode(&raw)
+            .unwrap()
+            .try_parse()
+            .unwrap()
+            .hash,
+        tx_hash(4)
+    );
+
+    // The nonce 9 as the integer 0x0009.
+    let mut padded = decode_hex(EIP155_TX).unwrap();
+    padded[1] += 2;
+    padded.splice(2..3, [0x82, 0x00, 0x09]);
+    assert_eq!(
+        TransactionHex::decode(&padded).unwrap_err(),
+        "transaction is not canonically encoded"
+    );
+    assert_eq!(
+        TransactionHex::decode(&[0x05, 0xc0]).unwrap_err(),
+        "unknown transaction type 5"
+    );
+}
+
+#[tokio::test]
+async fn call_traces_render_as_a_tree() {
+    let provider = http();
+    let trace = provider.trace_calls(&tx_hash(2)).await.unwrap();
+    let depths: Vec<usize> = trace.frames().iter().map(|(depth, _)| *depth).collect();
+    assert_eq!(depths, [0, 1, 1]);
+    assert_eq!(trace.gas_used(), 0xb41d);
+    assert_eq!(trace.calls[0].value(), 500_000_000_000_000_000);
+
+    let vault = "0x0000000000000000000000000000000000001111";
+    let rendered = trace.render(&SignatureDb::load());
+    let lines: Vec<&str> = rendered.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            format!(
+                "CALL {} -> {}, gas used 46109, transfer(address,uint256)",
+                ADDR_B, TOKEN
+            ),
+            format!(
+                "  CALL {} -> {}, 0.5 ETH, gas used 23902, deposit()",
+                TOKEN, vault
+            ),
+            format!(
+                "  STATICCALL {} -> {}, gas used 2534, 0x70a08231",
+                TOKEN, vault
+            ),
+        ]
+    );
+
+    // The JSON output is the tracer's own.
+    let fixture = chain_fixture();
+    assert_eq!(
+        serde_json::to_value(&trace).unwrap(),
+        fixture["debug_traceTransaction"][0]["result"]
+    );
+
+    let failed = provider.trace_calls(&tx_hash(3)).await.unwrap();
+    assert!(failed.failed());
+    assert!(failed
+        .render(&SignatureDb::load())
+        .ends_with(", gas used 25200 [FAILED: Ownable: caller is not the owner]\n"));
+
+    assert!(matches!(
+        provider.trace_calls(&tx_hash(1)).await,
+        Err(ProviderError::Rpc { .. })
+    ));
+}
+
+#[tokio::test]
+async fn scanner_finds_transfers_and_checkpoints() {
+    let provider = http();
+
+    let scanner = Scanner::new(vec![ADDR_A.to_uppercase().replace("0X", "0x")], 2);
+    let report = scanner.scan(&provider, 1, 3).await.unwrap();
+
+    assert_eq!(report.last_scanned, Some(3));
+    let found: Vec<(i32, &str)> = report
+        .matches
+        .iter()
+        .map(|item| (item.block_number, item.direction.value()))
+        .collect();
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].0, 1);
+    assert!(matches!(report.matches[0].direction, Direction::Out));
+    assert_eq!(found[1].0, 3);
+    assert!(matches!(report.matches[1].direction, Direction::In));
+    assert_eq!(scanner.checkpoint(&provider).await.unwrap(), Some(3));
+
+    // The checkpoint belongs to that endpoint, whatever the order of the addresses.
+    let reordered = Scanner::new(vec![ADDR_B.to_string(), ADDR_A.to_string()], 2);
+    reordered.scan(&provider, 1, 2).await.unwrap();
+    let swapped = Scanner::new(vec![ADDR_A.to_string(), ADDR_B.to_string()], 2);
+    assert_eq!(swapped.checkpoint(&provider).await.unwrap(), Some(2));
+    let other = FixtureProvider::new(chain_fixture());
+    assert_eq!(scanner.checkpoint(&other).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn internal_transfers_from_call_and_parity_traces() {
+    let provider = http();
+    let vault = "0x0000000000000000000000000000000000001111";
+
+    let transfers = trace::tx_transfers(&provider, &tx_hash(2)).await.unwrap();
+    assert_eq!(
+        transfers,
+        [InternalTransfer {
+            tx_hash: tx_hash(2),
+            call_type: "CALL".to_string(),
+            from: TOKEN.to_string(),
+            to: vault.to_string(),
+            value: 500_000_000_000_000_000,
+            trace_address: vec![0],
+        }]
+    );
+
+    // Without the debug namespace the node's trace_transaction is used.
+    let transfers = trace::tx_transfers(&provider, &tx_hash(1)).await.unwrap();
+    assert_eq!(transfers.len(), 1);
+    assert_eq!(
+        (transfers[0].from.as_str(), transfers[0].to.as_str()),
+        (ADDR_B, ADDR_A)
+    );
+    assert_eq!(transfers[0].value, 10_000_000_000_000_000);
+    assert_eq!(
+        transfers[0].to_string(),
+        format!(
+            "{} -> {}, 0.01 ETH (CALL in {})",
+            ADDR_B,
+            ADDR_A,
+            tx_hash(1)
+        )
+    );
+
+    let block = provider.get_block(None, Some(1)).await.unwrap();
+    let transfers = trace::block_transfers(&provider, &block).await.unwrap();
+    assert_eq!(transfers.len(), 1);
+    assert_eq!(transfers[0].tx_hash, tx_hash(1));
+
+    // The reverted call of tx 3 and the DELEGATECALL of tx 4 move nothing, traces
+    // without a hash are matched to transactions by position.
+    let block = provider.get_block(None, Some(3)).await.unwrap();
+    let transfers = trace::block_transfers(&provider, &block).await.unwrap();
+    assert_eq!(transfers.len(), 1);
+    assert_eq!(transfers[0].tx_hash, tx_hash(4));
+    assert_eq!(
+        (transfers[0].from.as_str(), transfers[0].to.as_str()),
+        (ADDR_A, vault)
+    );
+    assert_eq!(transfers[0].value, 200_000_000_000_000_000);
+
+    let scanner = Scanner::new(vec![ADDR_A.to_string()], 2).with_internal_transfers();
+    let report = scanner.scan(&provider, 1, 3).await.unwrap();
+    let found: Vec<(i32, &str, bool)> = report
+        .matches
+        .iter()
+        .map(|item| {
+            (
+                item.block_number,
+                item.direction.value(),
+                item.internal.is_some(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (1, "OUT", false),
+            (1, "IN", true),
+            (3, "IN", false),
+            (3, "OUT", true)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn state_diff_with_labelled_slots() {
+    let provider = http();
+    let diff = provider.trace_state_diff(&tx_hash(2)).await.unwrap();
+    let mut accounts = diff.accounts();
+    let vault = "0x0000000000000000000000000000000000001111";
+    let summary: Vec<(&str, AccountStatus)> = accounts
+        .iter()
+        .map(|account| (account.address.as_str(), account.status))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (vault, AccountStatus::Created),
+            (ADDR_B, AccountStatus::Modified),
+            (TOKEN, AccountStatus::Modified),
+        ]
+    );
+
+    let sender = &accounts[1];
+    assert_eq!(sender.nonce, Some((0, 1)));
+    assert_eq!(
+        sender.balance,
+        Some((1_000_000_000_000_000_000, 999_953_891_000_000_000))
+    );
+    // Code and nonce aren't in `post`, they didn't change.
+    let token = &accounts[2];
+    assert_eq!(token.nonce, None);
+    assert_eq!(token.code, None);
+    assert_eq!(token.storage.len(), 4);
+
+    let layout =
+        StorageLayout::from_file(common::fixture_path("layout.json").to_str().unwrap()).unwrap();
+    let keys = vec![ADDR_A.to_string(), ADDR_B.to_string()];
+    layout.label_slots(&mut accounts[2], &keys);
+    let slots: Vec<(Option<&str>, &str)> = accounts[2]
+        .storage
+        .iter()
+        .map(|change| (change.label.as_deref(), change.after.as_str()))
+        .collect();
+    let zero = encode_hex(&[0u8; 32]);
+    let balance_a = format!("0x{:064x}", 0xf4240);
+    let holder = format!("0x{:0>64}", &ADDR_A[2..]);
+    let length = format!("0x{:064x}", 2);
+    let expected_a = format!("balances[{}]", ADDR_A);
+    let expected_b = format!("balances[{}]", ADDR_B);
+    assert!(slots.contains(&(Some("holders.length"), length.as_str())));
+    assert!(slots.contains(&(Some("holders[1]"), holder.as_str())));
+    assert!(slots.contains(&(Some(expected_a.as_str()), balance_a.as_str())));
+    // Slots cleared to zero are missing from `post`.
+    assert!(slots.contains(&(Some(expected_b.as_str()), zero.as_str())));
+
+    assert_eq!(layout.label("0x0", &keys).as_deref(), Some("owner, paused"));
+    assert_eq!(layout.label("0x1", &keys).as_deref(), Some("totalSupply"));
+    assert_eq!(layout.label("0x9", &keys), None);
+}
+
+#[tokio::test]
+async fn struct_logs_profile_gas() {
+    let provider = http();
+    let trace = provider.trace_struct_logs(&tx_hash(2)).await.unwrap();
+    let profile = GasProfile::new(&trace, Some(TOKEN));
+    let vault = "0x0000000000000000000000000000000000001111";
+
+    // The CALL is charged without the 20003 gas its callee used.
+    assert_eq!(profile.execution_gas(), 38823);
+    let by_opcode = profile.by_opcode();
+    assert_eq!(
+        by_opcode[0],
+        (
+            "SSTORE".to_string(),
+            GasStats {
+                count: 2,
+                gas: 25000
+            }
+        )
+    );
+    assert_eq!(
+        by_opcode[1],
+        (
+            "CALL".to_string(),
+            GasStats {
+                count: 1,
+                gas: 11700
+            }
+        )
+    );
+    let by_depth: Vec<(usize, u64)> = profile
+        .by_depth()
+        .iter()
+        .map(|(depth, stats)| (*depth, stats.gas))
+        .collect();
+    assert_eq!(by_depth, [(1, 18820), (2, 20003)]);
+
+    let hotspots = profile.hotspots(2);
+    assert_eq!(hotspots[0].0, (vault.to_string(), 2, "SSTORE".to_string()));
+    assert_eq!(hotspots[1].0, (TOKEN.to_string(), 7, "CALL".to_string()));
+
+    let folded = profile.folded();
+    assert!(folded.contains(&format!("{};{};SSTORE 20000\n", TOKEN, vault)));
+    assert!(folded.contains(&format!("{};SSTORE 5000\n", TOKEN)));
+    // Free steps are left out of the flamegraph.
+    assert!(!folded.contains("STOP"));
+}
+
+#[tokio::test]
+async fn failed_transactions_replay_to_their_revert_reason() {
+    let provider = http();
+    let tx = provider.get_tx(&tx_hash(3)).await.unwrap();
+    let revert = revert::replay(&provider, &tx).await.unwrap().unwrap();
+    assert_eq!(
+        revert.message,
+        "execution reverted: Ownable: caller is not the owner"
+    );
+    let reason = RevertReason::decode(&revert, &[]);
+    assert!(
+        matches!(&reason, RevertReason::Error(reason) if reason == "Ownable: caller is not the owner")
+    );
+
+    let word = |value: u8| {
+        let mut word = [0u8; 32];
+        word[31] = value;
+        word.to_vec()
+    };
+    let with_selector = |selector: &str, words: &[u8]| Revert {
+        data: [decode_hex(selector).unwrap(), words.to_vec()].concat(),
+        message: "execution reverted".to_string(),
+    };
+
+    let panic = with_selector(revert::PANIC_SELECTOR, &word(0x11));
+    let reason = RevertReason::decode(&panic, &[]);
+    assert!(matches!(reason, RevertReason::Panic(0x11)));
+    assert_eq!(
+        reason.to_string(),
+        "Revert Reason: Panic(0x11): arithmetic overflow or underflow\n"
+    );
+
+    let error = AbiItem::from_signature("error", "InsufficientBalance(uint256,uint256)").unwrap();
+    let custom = with_selector(&error.selector().unwrap(), &[word(5), word(10)].concat());
+    match RevertReason::decode(&custom, &[error]) {
+        RevertReason::Custom(call) => {
+            assert_eq!(call.signature, "InsufficientBalance(uint256,uint256)");
+            assert_eq!(call.params[1].2.to_string(), "10");
+        }
+        other => panic!("decoded as {:?}", other),
+    }
+    assert!(matches!(
+        RevertReason::decode(&custom, &[]),
+        RevertReason::Unknown(_)
+    ));
+
+    let empty = Revert {
+        data: vec![],
+        message: "out of gas".to_string(),
+    };
+    assert_eq!(
+        RevertReason::decode(&empty, &[]).to_string(),
+        "Revert Reason: no revert data (out of gas)\n"
+    );
+}
+
+#[tokio::test]
+async fn transaction_cost_breakdown() {
+    let provider = http();
+    let gwei = 1_000_000_000;
+
+    // A 20 gwei legacy transaction in a block with a 1 gwei base fee.
+    let tx = provider.get_tx(&tx_hash(1)).await.unwrap();
+    let receipt = provider.get_tx_receipt(&tx.hash).await.unwrap().unwrap();
+    let cost = TxCost::fetch(&provider, &tx, &receipt).await.unwrap();
+    assert_eq!(cost.gas_usage(), 100.0);
+    assert_eq!(cost.base_fee, Some(gwei));
+    assert_eq!(cost.burnt(), 21_000 * gwei);
+    assert_eq!(cost.tip(), 21_000 * 19 * gwei);
+    assert_eq!(cost.blob_fee(), None);
+    assert_eq!(cost.total_fee(), 21_000 * 20 * gwei);
+    assert_eq!(cost.total_cost(), 21_000 * 20 * gwei + tx.value);
+
+    let tx = provider.get_tx(&tx_hash(4)).await.unwrap();
+    let mut receipt = provider.get_tx_receipt(&tx.hash).await.unwrap().unwrap();
+    let cost = TxCost::fetch(&provider, &tx, &receipt).await.unwrap();
+    assert_eq!(cost.tip(), 0);
+    assert_eq!(cost.blob_fee(), Some(131_072));
+    assert_eq!(cost.total_fee(), 21_000 * gwei + 131_072);
+
+    // OP stack receipts add the fee for posting the transaction to L1.
+    receipt.l1_fee = Some(5_000);
+    let cost = TxCost::new(&tx, &receipt, Some(gwei));
+    assert_eq!(cost.total_fee(), 21_000 * gwei + 131_072 + 5_000);
+    assert!(cost.to_string().contains("L1 Data Fee: "));
+}
+
+#[tokio::test]
+async fn indexer_syncs_and_queries() {
+    let setup = setup();
+    let provider = http();
+
+    let mut indexer = Indexer::open(setup.home.path().join("test-index.sqlite")).unwrap();
+    let report = indexer.sync(&provider, Some(1), 3).await.unwrap();
+    assert_eq!(report.indexed, 3);
+    assert_eq!(report.rolled_back, 0);
+    assert_eq!(report.head, Some(3));
+
+    // Block 1 has no eth_getBlockReceipts support, so its receipt is fetched per tx.
+    let fallback =
+        setup.server.requests().iter().any(|req| {
+            req["method"] == "eth_getTransactionReceipt" && req["params"][0] == tx_hash(1)
+        });
+    assert!(fallback);
+
+    let txs = indexer.txs_by_address(ADDR_A).unwrap();
+    assert_eq!(txs.len(), 2);
+    let block = indexer.txs_by_block(3).unwrap();
+    assert_eq!(block.len(), 2);
+    assert_eq!(block[0].status, Some(0));
+    let range = indexer.txs_by_time(1700000000, 1700000012).unwrap();
+    assert_eq!(range.len(), 2);
+
+    // Syncing again is a no-op once the head is reached.
+    let report = indexer.sync(&provider, None, 3).await.unwrap();
+    assert_eq!(report.indexed, 0);
+}
+
+#[tokio::test]
+async fn indexer_rejects_other_chains_and_deep_reorgs() {
+    let setup = setup();
+    let mut indexer = Indexer::open(setup.home.path().join("reorg-index.sqlite")).unwrap();
+    indexer.sync(&http(), Some(2), 3).await.unwrap();
+    assert_eq!(indexer.chain_id().unwrap(), Some(1));
+
+    let mut other = chain_fixture();
+    other["eth_chainId"] = json!([{ "result": "0x5" }]);
+    match indexer.sync(&FixtureProvider::new(other), None, 3).await {
+        Err(IndexError::WrongChain {
+            indexed: 1,
+            node: 5,
+        }) => {}
+        other => panic!("expected a chain mismatch, got {:?}", other),
+    }
+
+    // Every indexed block was replaced, there is nothing to roll back to.
+    let mut forked = chain_fixture();
+    for entry in forked["eth_getBlockByNumber"].as_array_mut().unwrap() {
+        if let Some(hash) = entry["result"].get_mut("hash") {
+            *hash = json!(format!("0x{}", "ee".repeat(32)));
+        }
+    }
+    match indexer.sync(&FixtureProvider::new(forked), None, 3).await {
+        Err(IndexError::DeepReorg { oldest: 2 }) => {}
+        other => panic!("expected a deep reorg, got {:?}", other),
+    }
+    assert_eq!(indexer.head().unwrap(), Some(3));
+
+    // An explicit start is respected even when the index has a head.
+    let report = indexer.sync(&http(), Some(3), 3).await.unwrap();
+    assert_eq!(report.rolled_back, 1);
+    assert_eq!(report.indexed, 1);
+}
+
+#[tokio::test]
+async fn indexer_resyncs_a_middle_range() {
+    let setup = setup();
+    let mut indexer = Indexer::open(setup.home.path().join("range-index.sqlite")).unwrap();
+    indexer.sync(&http(), Some(1), 3).await.unwrap();
+
+    let report = indexer.sync(&http(), Some(2), 2).await.unwrap();
+    assert_eq!(report.rolled_back, 1);
+    assert_eq!(report.indexed, 1);
+    assert_eq!(report.head, Some(3));
+    assert_eq!(indexer.block_hash(3).unwrap(), Some(block_hash(3)));
+
+    // Block 3 no longer builds on the re-indexed block 2 and goes away.
+    let mut forked = chain_fixture();
+    for entry in forked["eth_getBlockByNumber"].as_array_mut().unwrap() {
+        if entry["params"][0] == json!("0x2") {
+            entry["result"]["hash"] = json!(format!("0x{}", "ee".repeat(32)));
+        }
+    }
+    let report = indexer
+        .sync(&FixtureProvider::new(forked), Some(2), 2)
+        .await
+        .unwrap();
+    assert_eq!(report.rolled_back, 2);
+    assert_eq!(report.head, Some(2));
+    assert_eq!(indexer.block_hash(3).unwrap(), None);
+}
+
+#[tokio::test]
+async fn mock_server_answers_batches_and_errors() {
+    let setup = setup();
+
+    let batch = json!([
+        { "jsonrpc": "2.0", "id": 1, "method": "eth_blockNumber", "params": [] },
+        { "jsonrpc": "2.0", "id": 2, "method": "eth_getBlockReceipts", "params": ["0x1"] },
+        { "jsonrpc": "2.0", "id": 3, "method": "eth_mining", "params": [] }
+    ]);
+    let res: serde_json::Value = reqwest::Client::new()
+        .post(&setup.server.url)
+        .json(&batch)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    assert_eq!(res[0]["id"], 1);
+    assert_eq!(res[0]["result"], "0x3");
+    assert_eq!(res[1]["error"]["code"], -32601);
+    assert_eq!(res[2]["error"]["code"], -32601);
+    assert!(res[2].get("result").is_none());
+}
+
+#[tokio::test]
+async fn missing_block_is_not_found() {
+    let provider = http();
+
+    match provider.get_block(None, Some(99)).await {
+        Err(ProviderError::NotFound(what)) => assert_eq!(what, "Block"),
+        other => panic!(
+            "expected NotFound, got {:?}",
+            other.map(|block| block.number)
+        ),
+    }
+    match provider.get_tx(&format!("0x{}", "a9".repeat(32))).await {
+        Err(ProviderError::NotFound(_)) => {}
+        other => panic!("expected NotFound, got {:?}", other.map(|tx| tx.hash)),
+    }
+}
+
+#[tokio::test]
+async fn websocket_provider() {
+    setup();
+    let server = MockServer::start_ws(chain_fixture());
+    let provider = WsProvider::new(&server.url);
+
+    assert_eq!(provider.block_number().await.unwrap().to_decimals(), 3);
+    let block = provider.get_block(None, Some(2)).await.unwrap();
+    assert_eq!(block.parent_hash, block_hash(1));
+
+    // Requests share one connection and get unique ids on the wire.
+    let (a, b) = tokio::join!(provider.chain_id(), provider.get_tx_count(ADDR_A, None));
+    assert_eq!(a.unwrap(), Some(1));
+    assert_eq!(b.unwrap().to_decimals(), 5);
+    let ids: Vec<Value> = server
+        .requests()
+        .iter()
+        .map(|req| req["id"].clone())
+        .collect();
+    assert_eq!(ids.len(), 4);
+    assert!(ids.iter().enumerate().all(|(i, id)| !ids[..i].contains(id)));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn ipc_provider() {
+    let setup = setup();
+    let path = setup.home.path().join("mock.ipc");
+    let server = MockServer::start_ipc(chain_fixture(), &path);
+    let provider = providers::connect(&server.url).unwrap();
+    // Same key as the profile's `rateLimits`, so a configured limit applies.
+    assert_eq!(provider.endpoint(), server.url);
+    assert_eq!(provider.endpoint(), format!("ipc://{}", path.display()));
+
+    let block = provider.get_block(None, None).await.unwrap();
+    assert_eq!(block.transactions.len(), 2);
+    let receipts = provider.get_block_receipts(Some(3)).await.unwrap().unwrap();
+    assert_eq!(receipts.len(), 2);
+    assert_eq!(
+        provider.gas_price().await.unwrap().to_decimals(),
+        20_000_000_000
+    );
+    assert!(ratelimit::stats().iter().any(|(url, _)| *url == server.url));
+}
+
+#[tokio::test]
+async fn fixture_provider() {
+    setup();
+    let path = common::fixture_path("chain.json");
+    let provider = providers::connect(&format!("fixture://{}", path.display())).unwrap();
+
+    let tx = provider.get_tx(&tx_hash(1)).await.unwrap();
+    assert_eq!(tx.to.as_deref(), Some(ADDR_B));
+    assert_eq!(provider.chain_id().await.unwrap(), Some(1));
+    match provider.get_block_receipts(Some(1)).await {
+        Ok(None) => {}
+        other => panic!(
+            "expected no receipts, got {:?}",
+            other.map(|r| r.map(|r| r.len()))
+        ),
+    }
+
+    let inline = FixtureProvider::new(json!({ "eth_blockNumber": [{ "result": "0x10" }] }));
+    assert_eq!(inline.block_number().await.unwrap().to_decimals(), 16);
+    match inline.gas_price().await {
+        Err(ProviderError::Rpc { code, .. }) => assert_eq!(code, -32601),
+        other => panic!(
+            "expected an RPC error, got {:?}",
+            other.map(|r| r.to_decimals())
+        ),
+    }
+}
+
+#[tokio::test]
+async fn multi_provider_fails_over_across_transports() {
+    setup();
+    let ws = MockServer::start_ws(chain_fixture());
+    let endpoints = vec![
+        providers::connect("http://127.0.0.1:1").unwrap(),
+        providers::connect(&ws.url).unwrap(),
+    ];
+    let provider = MultiProvider::new(endpoints, Strategy::Failover, 1);
+
+    assert_eq!(provider.block_number().await.unwrap().to_decimals(), 3);
+    assert_eq!(
+        provider.endpoint(),
+        format!("http://127.0.0.1:1, {}", ws.url)
+    );
+    assert_eq!(ws.count("eth_blockNumber"), 1);
+}
+
+#[test]
+fn connect_rejects_unknown_schemes() {
+    assert!(providers::connect("ftp://example.com").is_err());
+    assert!(providers::connect("fixture:///does/not/exist.json").is_err());
+}
+
+#[tokio::test]
+async fn cassette_round_trip() {
+    let setup = setup();
+    let cassette = setup.home.path().join("provider.ndjson");
+    let cassette = cassette.to_str().unwrap();
+
+    let fixtures = json!({
+        "eth_blockNumber": [{ "result": "0x3" }],
+        "eth_gasPrice": [{ "error": { "code": -32000, "message": "busy" } }]
+    });
+    let recorder = RecordingProvider::create(cassette, FixtureProvider::new(fixtures)).unwrap();
+    assert_eq!(recorder.block_number().await.unwrap().to_decimals(), 3);
+    assert!(recorder.gas_price().await.is_err());
+    assert_eq!(
+        std::fs::read_to_string(cassette).unwrap().lines().count(),
+        2
+    );
+
+    let replay = ReplayProvider::from_file(cassette).unwrap();
+    assert_eq!(replay.block_number().await.unwrap().to_decimals(), 3);
+    // Repeated requests keep getting the last recorded answer.
+    assert_eq!(replay.block_number().await.unwrap().to_decimals(), 3);
+    match replay.gas_price().await {
+        Err(ProviderError::Rpc { message, .. }) => assert_eq!(message, "busy"),
+        other => panic!(
+            "expected the recorded error, got {:?}",
+            other.map(|r| r.to_decimals())
+        ),
+    }
+    match replay.chain_id().await {
+        Err(ProviderError::Replay(message)) => assert!(message.contains("eth_chainId")),
+        other => panic!("expected a replay miss, got {:?}", other),
+    }
+}
+
+#[test]
+fn cache_is_scoped_per_chain_and_endpoint() {
+    let dir = TempDir::new().unwrap();
+    let cache = Cache::new(dir.path().to_path_buf(), 1024 * 1024, 15);
+    let mainnet = Cache::namespace(1, "http://a");
+    let sepolia = Cache::namespace(11155111, "http://a");
+    let other = Cache::namespace(1, "http://b");
+    assert_ne!(mainnet, sepolia);
+    assert_ne!(mainnet, other);
+
+    let head = json!({ "method": "eth_blockNumber", "params": [] });
+    cache.store(&mainnet, &head, &json!({ "result": "0x100" }));
+
+    let request = json!({ "method": "eth_getBlockByHash", "params": [block_hash(1), false] });
+    cache.store(
+        &mainnet,
+        &request,
+        &json!({ "result": { "number": "0x1" } }),
+    );
+    assert!(cache.get(&mainnet, &request).is_some());
+    assert!(cache.get(&sepolia, &request).is_none());
+    assert!(cache.get(&other, &request).is_none());
+
+    // Receipts are only kept once their block is final in that namespace.
+    let receipt = json!({ "method": "eth_getTransactionReceipt", "params": [tx_hash(1)] });
+    let response = json!({ "result": { "blockHash": block_hash(1), "blockNumber": "0xff" } });
+    assert_eq!(cache.policy(&mainnet, &receipt, &response), Policy::Skip);
+    let response = json!({ "result": { "blockHash": block_hash(1), "blockNumber": "0x10" } });
+    assert_eq!(
+        cache.policy(&mainnet, &receipt, &response),
+        Policy::Immutable
+    );
+    assert_eq!(cache.policy(&sepolia, &receipt, &response), Policy::Skip);
+
+    // So are traces, debug ones take the block from the cached receipt.
+    let trace = json!({ "method": "debug_traceTransaction", "params": [tx_hash(1), {}] });
+    let traced = json!({ "result": { "gas": "0x5208", "calls": [] } });
+    assert_eq!(cache.policy(&mainnet, &trace, &traced), Policy::Skip);
+    cache.store(&mainnet, &receipt, &response);
+    assert_eq!(cache.policy(&mainnet, &trace, &traced), Policy::Immutable);
+    assert_eq!(cache.policy(&sepolia, &trace, &traced), Policy::Skip);
+
+    let trace = json!({ "method": "trace_transaction", "params": [tx_hash(1)] });
+    let traced = json!({ "result": [{ "blockNumber": 255 }] });
+    assert_eq!(cache.policy(&mainnet, &trace, &traced), Policy::Skip);
+    let traced = json!({ "result": [{ "blockNumber": 16 }] });
+    assert_eq!(cache.policy(&mainnet, &trace, &traced), Policy::Immutable);
+
+    assert_eq!(cache.stats().entries, 2);
+    assert_eq!(cache.clear(), 2);
+}